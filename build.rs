@@ -2,6 +2,8 @@ use anyhow::*;
 use fs_extra::copy_items;
 use fs_extra::dir::CopyOptions;
 use std::env;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 
 fn main() -> Result<()> {
     // This tells cargo to rerun this script if something in /res/ changes.
@@ -11,7 +13,38 @@ fn main() -> Result<()> {
     let mut copy_options: CopyOptions = CopyOptions::new();
     copy_options.overwrite = true;
     let paths_to_copy: Vec<&str> = vec!["res/"];
-    copy_items(&paths_to_copy, out_dir, &copy_options)?;
+    copy_items(&paths_to_copy, &out_dir, &copy_options)?;
+
+    //with the embed-assets feature, every file in res/ is also baked into the binary (see resources::embedded) - unless hot-reload is on too, where assets are always read from disk so edits show up
+    let hot_reload: bool = env::var_os("CARGO_FEATURE_HOT_RELOAD").is_some()
+        && env::var("CARGO_CFG_TARGET_ARCH")? != "wasm32";
+    if env::var_os("CARGO_FEATURE_EMBED_ASSETS").is_some() && !hot_reload {
+        write_embedded_assets(Path::new(&out_dir).join("embedded_assets.rs"))?;
+    }
+
+    Ok(())
+}
+
+//generates a list of (path relative to res/, include_bytes!(file)) for every file in res/
+fn write_embedded_assets(output: PathBuf) -> Result<()> {
+    let res_dir: PathBuf = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("res");
+
+    let mut assets: String = String::from("pub static ASSETS: &[(&str, &[u8])] = &[\n");
+    for entry in glob::glob(&format!("{}/**/*", res_dir.display()))? {
+        let path: PathBuf = entry?;
+        if !path.is_file() {
+            continue;
+        }
+        //assets are looked up the same way they are loaded - with forward slashes, relative to res/
+        let name: String = path
+            .strip_prefix(&res_dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        writeln!(assets, "    ({name:?}, include_bytes!({path:?})),")?;
+    }
+    assets.push_str("];\n");
+
+    std::fs::write(output, assets)?;
 
     Ok(())
 }
@@ -0,0 +1,104 @@
+//keyframe animation of node transforms (loaded from gltf files)
+
+use cgmath::{InnerSpace, Matrix4, Quaternion, Vector3, VectorSpace};
+
+//a set of keyframes for one property - the value at each time (in seconds)
+pub struct Track<T> {
+    pub times: Vec<f32>,
+    pub values: Vec<T>,
+}
+
+impl<T: Copy> Track<T> {
+    //a track that never changes - for properties a channel doesn't animate
+    pub fn constant(value: T) -> Self {
+        Self {
+            times: vec![0.0],
+            values: vec![value],
+        }
+    }
+
+    //the value at a given time, interpolated between the keyframes either side of it (and held before the first/after the last)
+    fn sample(&self, time: f32, interpolate: impl Fn(T, T, f32) -> T) -> T {
+        //the index of the first keyframe after `time`
+        let next: usize = self.times.partition_point(|&keyframe| keyframe <= time);
+        if next == 0 {
+            return self.values[0];
+        }
+        if next >= self.values.len() {
+            return self.values[self.values.len() - 1];
+        }
+
+        let (start, end): (f32, f32) = (self.times[next - 1], self.times[next]);
+        let amount: f32 = if end > start {
+            (time - start) / (end - start)
+        } else {
+            0.0
+        };
+        interpolate(self.values[next - 1], self.values[next], amount)
+    }
+}
+
+//everything animated about a single node - translation, rotation and scale
+pub struct Channel {
+    //the (gltf) index of the node being animated - matches scene::Node::id
+    pub node: usize,
+    pub translation: Track<Vector3<f32>>,
+    pub rotation: Track<Quaternion<f32>>,
+    pub scale: Track<Vector3<f32>>,
+}
+
+impl Channel {
+    //the node's transform at a given time
+    pub fn sample(&self, time: f32) -> Matrix4<f32> {
+        let translation: Vector3<f32> = self.translation.sample(time, |a, b, t| a.lerp(b, t));
+        let rotation: Quaternion<f32> = self.rotation.sample(time, |a, b, t| {
+            //q and -q are the same rotation, so make sure we go the short way round
+            let b: Quaternion<f32> = if a.dot(b) < 0.0 { -b } else { b };
+            a.slerp(b, t)
+        });
+        let scale: Vector3<f32> = self.scale.sample(time, |a, b, t| a.lerp(b, t));
+
+        Matrix4::from_translation(translation)
+            * Matrix4::from(rotation)
+            * Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+    }
+}
+
+pub struct Animation {
+    pub name: String,
+    pub channels: Vec<Channel>,
+    //how long (in seconds) the animation lasts - the time of its last keyframe
+    pub duration: f32,
+}
+
+impl Animation {
+    //the transform of every animated node at a given time (looping once the animation is over)
+    pub fn sample(&self, time: f32) -> Vec<(usize, Matrix4<f32>)> {
+        let time: f32 = if self.duration > 0.0 {
+            time.rem_euclid(self.duration)
+        } else {
+            0.0
+        };
+        self.channels
+            .iter()
+            .map(|channel| (channel.node, channel.sample(time)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_interpolate_and_clamp() {
+        let track: Track<Vector3<f32>> = Track {
+            times: vec![1.0, 3.0],
+            values: vec![Vector3::new(0.0, 0.0, 0.0), Vector3::new(4.0, 0.0, 0.0)],
+        };
+        let lerp = |a: Vector3<f32>, b: Vector3<f32>, t: f32| a.lerp(b, t);
+        assert_eq!(track.sample(0.0, lerp).x, 0.0);
+        assert_eq!(track.sample(2.0, lerp).x, 2.0);
+        assert_eq!(track.sample(10.0, lerp).x, 4.0);
+    }
+}
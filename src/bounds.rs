@@ -0,0 +1,138 @@
+//for describing the space something takes up
+
+//an axis aligned bounding box - the smallest box (lined up with the x, y and z axes) that contains something
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: cgmath::Point3<f32>,
+    pub max: cgmath::Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: cgmath::Point3<f32>, max: cgmath::Point3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    //the smallest box containing all of the points - a box with no size at the origin if there aren't any
+    pub fn from_points(points: impl IntoIterator<Item = cgmath::Point3<f32>>) -> Self {
+        let mut points = points.into_iter();
+        let first: cgmath::Point3<f32> = match points.next() {
+            Some(point) => point,
+            None => {
+                return Self::new(
+                    cgmath::Point3::new(0.0, 0.0, 0.0),
+                    cgmath::Point3::new(0.0, 0.0, 0.0),
+                )
+            }
+        };
+        points.fold(Self::new(first, first), |aabb, point| {
+            Self::new(
+                cgmath::Point3::new(
+                    aabb.min.x.min(point.x),
+                    aabb.min.y.min(point.y),
+                    aabb.min.z.min(point.z),
+                ),
+                cgmath::Point3::new(
+                    aabb.max.x.max(point.x),
+                    aabb.max.y.max(point.y),
+                    aabb.max.z.max(point.z),
+                ),
+            )
+        })
+    }
+
+    //the radius of a sphere around the origin (not the box's centre) that the whole box fits inside - what an instance at some position needs when only its position is known
+    pub fn bounding_radius(&self) -> f32 {
+        use cgmath::{EuclideanSpace, InnerSpace};
+        self.corners()
+            .iter()
+            .map(|corner| corner.to_vec().magnitude())
+            .fold(0.0, f32::max)
+    }
+
+    //the smallest box containing this one after it has been moved/rotated/scaled by the transform (e.g. an instance's model matrix)
+    pub fn transformed(&self, transform: cgmath::Matrix4<f32>) -> Self {
+        use cgmath::Transform;
+        Self::from_points(
+            self.corners()
+                .into_iter()
+                .map(|corner| transform.transform_point(corner)),
+        )
+    }
+
+    //how far a sphere has to move to no longer overlap the box (None if it already doesn't) - out through the nearest side when its centre is inside
+    pub fn push_out_sphere(
+        &self,
+        center: cgmath::Point3<f32>,
+        radius: f32,
+    ) -> Option<cgmath::Vector3<f32>> {
+        use cgmath::InnerSpace;
+
+        let closest: cgmath::Point3<f32> = cgmath::Point3::new(
+            center.x.clamp(self.min.x, self.max.x),
+            center.y.clamp(self.min.y, self.max.y),
+            center.z.clamp(self.min.z, self.max.z),
+        );
+        let offset: cgmath::Vector3<f32> = center - closest;
+        let distance: f32 = offset.magnitude();
+        if distance >= radius {
+            return None;
+        }
+        if distance > f32::EPSILON {
+            return Some(offset / distance * (radius - distance));
+        }
+
+        //the centre is inside, so it leaves along whichever axis is the shortest way out
+        let exits: [(f32, cgmath::Vector3<f32>); 6] = [
+            (center.x - self.min.x, -cgmath::Vector3::unit_x()),
+            (self.max.x - center.x, cgmath::Vector3::unit_x()),
+            (center.y - self.min.y, -cgmath::Vector3::unit_y()),
+            (self.max.y - center.y, cgmath::Vector3::unit_y()),
+            (center.z - self.min.z, -cgmath::Vector3::unit_z()),
+            (self.max.z - center.z, cgmath::Vector3::unit_z()),
+        ];
+        let (depth, direction) =
+            exits.into_iter().fold(
+                exits[0],
+                |nearest, exit| if exit.0 < nearest.0 { exit } else { nearest },
+            );
+        Some(direction * (depth + radius))
+    }
+
+    //the 8 corners of the box - the first 4 are on the min.z side, the last 4 on the max.z side, both going around the box in the same order
+    pub fn corners(&self) -> [cgmath::Point3<f32>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            cgmath::Point3::new(min.x, min.y, min.z),
+            cgmath::Point3::new(max.x, min.y, min.z),
+            cgmath::Point3::new(max.x, max.y, min.z),
+            cgmath::Point3::new(min.x, max.y, min.z),
+            cgmath::Point3::new(min.x, min.y, max.z),
+            cgmath::Point3::new(max.x, min.y, max.z),
+            cgmath::Point3::new(max.x, max.y, max.z),
+            cgmath::Point3::new(min.x, max.y, max.z),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Point3;
+
+    #[test]
+    fn spheres_are_pushed_out_of_the_nearest_side() {
+        let aabb: Aabb = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        //clear of the box
+        assert_eq!(aabb.push_out_sphere(Point3::new(2.0, 0.0, 0.0), 0.5), None);
+        //overlapping the +x side by 0.25
+        let push = aabb
+            .push_out_sphere(Point3::new(1.25, 0.0, 0.0), 0.5)
+            .unwrap();
+        assert!((push.x - 0.25).abs() < 1e-5 && push.y == 0.0 && push.z == 0.0);
+        //inside, nearest to the -y side
+        let push = aabb
+            .push_out_sphere(Point3::new(0.0, -0.75, 0.0), 0.5)
+            .unwrap();
+        assert!((push.y + 0.75).abs() < 1e-5 && push.x == 0.0 && push.z == 0.0);
+    }
+}
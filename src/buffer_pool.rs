@@ -0,0 +1,87 @@
+//for re-using gpu buffers instead of creating (and throwing away) new ones every time something changes size
+
+use std::collections::HashMap;
+
+//the smallest buffer the pool will hand out - tiny buffers aren't worth keeping track of
+const MIN_POOLED_SIZE: wgpu::BufferAddress = 256;
+
+//buffers that have been released, grouped by their usage - a released buffer can be handed back out for anything with the same usage that fits inside it
+#[derive(Default)]
+pub struct BufferPool {
+    free: HashMap<wgpu::BufferUsages, Vec<wgpu::Buffer>>,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //a buffer with the given usage that is at least `size` bytes - a released one if any fit, otherwise a new one (rounded up so growing a little at a time doesn't allocate every time)
+    pub fn acquire(
+        &mut self,
+        device: &wgpu::Device,
+        label: &str,
+        size: wgpu::BufferAddress,
+        usage: wgpu::BufferUsages,
+    ) -> wgpu::Buffer {
+        if let Some(buffers) = self.free.get_mut(&usage) {
+            //the smallest buffer that fits, so big buffers are kept around for big requests
+            let best: Option<usize> = buffers
+                .iter()
+                .enumerate()
+                .filter(|(_, buffer)| buffer.size() >= size)
+                .min_by_key(|(_, buffer)| buffer.size())
+                .map(|(index, _)| index);
+            if let Some(index) = best {
+                return buffers.swap_remove(index);
+            }
+        }
+
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: pooled_size(size),
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    //give a buffer back to the pool so it can be re-used by a later acquire - it must not be used by the caller afterwards
+    pub fn release(&mut self, buffer: wgpu::Buffer) {
+        self.free.entry(buffer.usage()).or_default().push(buffer);
+    }
+
+    //how many released buffers are waiting to be re-used
+    pub fn len(&self) -> usize {
+        self.free.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //free the gpu memory of every released buffer (e.g. after a big scene has been replaced by a small one)
+    pub fn clear(&mut self) {
+        for buffer in self.free.drain().flat_map(|(_, buffers)| buffers) {
+            buffer.destroy();
+        }
+    }
+}
+
+//the size a new pooled buffer is created with - the next power of 2, so a buffer that keeps growing only gets re-allocated log(n) times
+fn pooled_size(size: wgpu::BufferAddress) -> wgpu::BufferAddress {
+    size.max(MIN_POOLED_SIZE).next_power_of_two()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pooled_sizes_round_up_to_powers_of_two() {
+        assert_eq!(pooled_size(0), MIN_POOLED_SIZE);
+        assert_eq!(pooled_size(1), MIN_POOLED_SIZE);
+        assert_eq!(pooled_size(256), 256);
+        assert_eq!(pooled_size(257), 512);
+        assert_eq!(pooled_size(100_000), 131_072);
+    }
+}
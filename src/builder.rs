@@ -0,0 +1,165 @@
+//for configuring how a State is set up, without State::new needing an argument for every option
+
+use crate::{camera, resources, State};
+
+//chainable options for creating a State - the defaults reproduce the engine's original behaviour
+pub struct EngineBuilder {
+    pub(crate) camera: Option<camera::Camera>,
+    pub(crate) fov: cgmath::Rad<f32>,
+    pub(crate) znear: f32,
+    pub(crate) zfar: f32,
+    pub(crate) present_mode: wgpu::PresentMode,
+    pub(crate) power_preference: wgpu::PowerPreference,
+    pub(crate) clear_color: wgpu::Color,
+    pub(crate) hdr: bool,
+    pub(crate) trace_dir: Option<std::path::PathBuf>,
+    pub(crate) features: wgpu::Features,
+    pub(crate) limits: Option<wgpu::Limits>,
+    pub(crate) gpu_timing: bool,
+    pub(crate) rotate_button: winit::event::MouseButton,
+    pub(crate) reverse_z: bool,
+    pub(crate) fixed_timestep: Option<instant::Duration>,
+    pub(crate) gpu_culling: bool,
+    pub(crate) obj_load_options: tobj::LoadOptions,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        Self {
+            camera: None,
+            //a basic, random value - can be changed with State::set_fov
+            fov: cgmath::Deg(45.0).into(),
+            znear: 0.1,
+            zfar: 100.0,
+            //essentially Vsync, and will cap the display rate to the display's frame rate
+            present_mode: wgpu::PresentMode::AutoVsync,
+            power_preference: wgpu::PowerPreference::default(),
+            //a blueish background
+            clear_color: wgpu::Color {
+                r: 0.1,
+                g: 0.2,
+                b: 0.3,
+                a: 1.0,
+            },
+            hdr: false,
+            trace_dir: None,
+            features: wgpu::Features::empty(),
+            limits: None,
+            gpu_timing: false,
+            rotate_button: winit::event::MouseButton::Left,
+            reverse_z: false,
+            fixed_timestep: None,
+            gpu_culling: false,
+            obj_load_options: resources::default_obj_load_options(),
+        }
+    }
+}
+
+impl EngineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //where the camera starts - by default it is 5 units up and 10 back, looking slightly down at the centre of the scene
+    pub fn with_camera(mut self, camera: camera::Camera) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    //the camera's (vertical) field of view and how close and far away things can be drawn - 45 degrees, 0.1 and 100 by default
+    //build() fails if the fov isn't between 0 and 180 degrees, or znear isn't above 0 and below zfar
+    pub fn with_projection<F: Into<cgmath::Rad<f32>>>(
+        mut self,
+        fov: F,
+        znear: f32,
+        zfar: f32,
+    ) -> Self {
+        self.fov = fov.into();
+        self.znear = znear;
+        self.zfar = zfar;
+        self
+    }
+
+    //how frames are presented to the screen (https://docs.rs/wgpu/latest/wgpu/enum.PresentMode.html)
+    pub fn with_present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    //whether to prefer a low power (integrated) or high performance (discrete) gpu
+    pub fn with_power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    //the colour drawn behind the scene (when there is no skybox)
+    pub fn with_clear_color(mut self, clear_color: wgpu::Color) -> Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    //render the scene into a float (Rgba16Float) target so bright values don't clip, then tonemap it onto the surface
+    pub fn with_hdr(mut self, hdr: bool) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    //a directory wgpu writes an api trace into, for replaying gpu issues - only works with the trace feature
+    pub fn with_trace_dir(mut self, trace_dir: impl Into<std::path::PathBuf>) -> Self {
+        self.trace_dir = Some(trace_dir.into());
+        self
+    }
+
+    //extra wgpu features the gpu has to support (e.g. POLYGON_MODE_LINE) - build() fails if the adapter is missing any of them
+    pub fn with_features(mut self, features: wgpu::Features) -> Self {
+        self.features |= features;
+        self
+    }
+
+    //replaces the default limits (downlevel_webgl2_defaults on wasm, Limits::default elsewhere), e.g. for a higher max_texture_dimension_2d
+    pub fn with_limits(mut self, limits: wgpu::Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    //measure how long the gpu spends drawing each frame (see State::last_gpu_times) - quietly does nothing on gpus without timestamp queries
+    pub fn with_gpu_timing(mut self, gpu_timing: bool) -> Self {
+        self.gpu_timing = gpu_timing;
+        self
+    }
+
+    //which mouse button has to be held down to rotate the camera (left by default)
+    pub fn with_rotate_button(mut self, button: winit::event::MouseButton) -> Self {
+        self.rotate_button = button;
+        self
+    }
+
+    //flip the depth buffer so the far plane is at 0 - much better depth precision for large scenes (see camera::Projection::set_reverse_z)
+    pub fn with_reverse_z(mut self, reverse_z: bool) -> Self {
+        self.reverse_z = reverse_z;
+        self
+    }
+
+    //step every update() by the same amount instead of the real time between frames, so the same inputs always give the same frames (e.g. for tests, or recording with render_to)
+    pub fn with_fixed_timestep(mut self, timestep: instant::Duration) -> Self {
+        self.fixed_timestep = Some(timestep);
+        self
+    }
+
+    //skip drawing instances that are outside the camera's view, deciding which on the gpu with a compute shader (see State::gpu_culling) - worth it for scenes with many instances, and quietly does nothing on gpus without compute shaders
+    pub fn with_gpu_culling(mut self, gpu_culling: bool) -> Self {
+        self.gpu_culling = gpu_culling;
+        self
+    }
+
+    //how the model's .obj file is read (triangulated and single indexed by default) - e.g. single_index: false for files whose positions, normals and uvs don't line up
+    pub fn with_obj_load_options(mut self, options: tobj::LoadOptions) -> Self {
+        self.obj_load_options = options;
+        self
+    }
+
+    //creates the renderer for a window with these options
+    pub async fn build(self, window: &winit::window::Window) -> anyhow::Result<State> {
+        State::new(window, self).await
+    }
+}
@@ -13,9 +13,57 @@ pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
     0.0, 0.0, 0.5, 1.0,
 );
 
+//flips depth around so the near plane is at 1.0 and the far plane is at 0.0 (see Projection::set_reverse_z)
+#[rustfmt::skip]
+pub const REVERSE_Z_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, -1.0, 0.0,
+    0.0, 0.0, 1.0, 1.0,
+);
+
 //helps us stop the camera looking straight up or straight down (which causes issues)
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
 
+//how fast the camera rolls (in radians per second) when holding Q or E
+const ROLL_SPEED: f32 = 1.0;
+
+//how far (in units per pixel dragged, for each unit of the controller's speed) the camera moves when panning
+const PAN_SPEED: f32 = 0.005;
+
+//the longest frame (in seconds) the controllers will simulate - after a stall (loading, dragging the window ect) a huge dt would otherwise teleport the camera across the scene
+const MAX_UPDATE_DT: f32 = 0.1;
+
+//how many times the camera is pushed out of the colliders each update - pushing out of one box can push it into another, so once isn't always enough
+const COLLISION_ITERATIONS: usize = 4;
+
+//how much one line (about 100 pixels) of scrolling multiplies the speed by in ScrollMode::Speed, and the field of view by in ScrollMode::Zoom
+const SCROLL_SPEED_STEP: f32 = 1.1;
+const SCROLL_ZOOM_STEP: f32 = 1.05;
+
+//what scrolling does to the free-fly camera
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScrollMode {
+    //move the camera forwards/backwards along where it is looking
+    Dolly,
+    //speed up (scrolling up) or slow down the camera's movement, like in most editors
+    Speed,
+    //narrow (scrolling up) or widen the field of view, without moving the camera
+    Zoom,
+}
+
+//a plain copy of everything needed to put a camera back where it was - cgmath types don't implement serde's traits, so everything is stored as arrays and floats
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CameraState {
+    pub position: [f32; 3],
+    //all angles are in radians
+    pub yaw: f32,
+    pub pitch: f32,
+    //older saved viewpoints don't have a roll, so it defaults to 0
+    #[serde(default)]
+    pub roll: f32,
+}
+
 //a view into our scene that can move and look around
 #[derive(Debug)]
 pub struct Camera {
@@ -25,40 +73,110 @@ pub struct Camera {
     yaw: Rad<f32>,
     //vertical rotation (up-down)
     pitch: Rad<f32>,
+    //rotation around the direction the camera is looking (tilting left-right)
+    roll: Rad<f32>,
 }
 
 impl Camera {
-    pub fn new<V: Into<Point3<f32>>, Y: Into<Rad<f32>>, P: Into<Rad<f32>>>(
+    pub fn new<V: Into<Point3<f32>>, Y: Into<Rad<f32>>, P: Into<Rad<f32>>, R: Into<Rad<f32>>>(
         position: V,
         yaw: Y,
         pitch: P,
+        roll: R,
     ) -> Self {
         Self {
             position: position.into(),
             yaw: yaw.into(),
             pitch: pitch.into(),
+            roll: roll.into(),
         }
     }
 
+    //the direction the camera is looking in (normalized, in world space) - e.g. for aiming from the camera
+    pub fn forward(&self) -> Vector3<f32> {
+        Vector3::new(self.yaw.0.cos(), self.pitch.0.sin(), self.yaw.0.sin()).normalize()
+    }
+
+    //the top of the screen in world space - at right angles to forward (so it tips back as the camera looks down), then rotated around forward by the roll angle
+    pub fn up(&self) -> Vector3<f32> {
+        let forward: Vector3<f32> = self.forward();
+        //pitch is clamped short of straight up/down, so forward is never parallel to +y
+        let level_right: Vector3<f32> = forward.cross(Vector3::unit_y()).normalize();
+        let level_up: Vector3<f32> = level_right.cross(forward).normalize();
+        Matrix3::from_axis_angle(forward, self.roll) * level_up
+    }
+
+    //to the right of where the camera is looking, taking roll into account
+    pub fn right(&self) -> Vector3<f32> {
+        self.forward().cross(self.up()).normalize()
+    }
+
     pub fn calc_matrix(&self) -> Matrix4<f32> {
-        Matrix4::look_to_rh(
-            self.position,
-            Vector3::new(self.yaw.0.cos(), self.pitch.0.sin(), self.yaw.0.sin()).normalize(),
-            Vector3::unit_y(),
+        Matrix4::look_to_rh(self.position, self.forward(), self.up())
+    }
+
+    //a snapshot of where the camera is and where it is looking, e.g. to save as json
+    pub fn to_state(&self) -> CameraState {
+        CameraState {
+            position: self.position.into(),
+            yaw: self.yaw.0,
+            pitch: self.pitch.0,
+            roll: self.roll.0,
+        }
+    }
+
+    pub fn from_state(state: CameraState) -> Self {
+        Self::new(
+            state.position,
+            Rad(state.yaw),
+            Rad(state.pitch),
+            Rad(state.roll),
         )
     }
+
+    //turns the camera (without moving it) to face a point in the scene - the inverse of forward()
+    pub fn look_at(&mut self, target: Point3<f32>) {
+        let direction: Vector3<f32> = target - self.position;
+        let horizontal: f32 = (direction.x * direction.x + direction.z * direction.z).sqrt();
+
+        //straight up or down there is no horizontal direction, so we keep the current yaw
+        if horizontal > f32::EPSILON {
+            self.yaw = Rad(direction.z.atan2(direction.x));
+        }
+        //forward() uses sin(pitch) as its height against a horizontal length of 1
+        let height: f32 = if horizontal > f32::EPSILON {
+            (direction.y / horizontal).clamp(-1.0, 1.0)
+        } else {
+            direction.y.signum()
+        };
+        self.pitch = Rad(height.asin().clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2));
+    }
+}
+
+//which way the field of view is measured - the other direction's field of view follows from the aspect ratio
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FovAxis {
+    //the height of the view stays the same, and wider windows see more to the sides (known as Hor+) - the default
+    Vertical,
+    //the width of the view stays the same, and wider windows see less above and below (known as Vert-)
+    Horizontal,
 }
 
 //a set of settings relating to how the camera looks and percieves the scene
+#[derive(Debug, Clone)]
 pub struct Projection {
     //the aspect ratio
     aspect: f32,
     //field of view
     fov: Rad<f32>,
+    //whether fov is the vertical or horizontal field of view
+    fov_axis: FovAxis,
     //what counts as too close to render
     znear: f32,
     //what counts as too far away to render
     zfar: f32,
+    //whether depth goes from 1 at znear to 0 at zfar instead of the other way around
+    reverse_z: bool,
 }
 
 impl Projection {
@@ -66,8 +184,10 @@ impl Projection {
         Self {
             aspect: width as f32 / height as f32,
             fov: fov.into(),
+            fov_axis: FovAxis::Vertical,
             znear,
             zfar,
+            reverse_z: false,
         }
     }
 
@@ -75,9 +195,87 @@ impl Projection {
         self.aspect = width as f32 / height as f32;
     }
 
+    pub fn znear(&self) -> f32 {
+        self.znear
+    }
+
+    pub fn zfar(&self) -> f32 {
+        self.zfar
+    }
+
+    pub fn fov(&self) -> Rad<f32> {
+        self.fov
+    }
+
+    pub fn fov_axis(&self) -> FovAxis {
+        self.fov_axis
+    }
+
+    //measure the field of view horizontally (fixed width) instead of vertically (fixed height)
+    pub fn set_fov_axis(&mut self, fov_axis: FovAxis) {
+        self.fov_axis = fov_axis;
+    }
+
+    //the vertical field of view the projection actually uses - the same as fov, unless it is measured horizontally
+    pub fn vertical_fov(&self) -> Rad<f32> {
+        match self.fov_axis {
+            FovAxis::Vertical => self.fov,
+            FovAxis::Horizontal => Rad(2.0 * ((self.fov.0 / 2.0).tan() / self.aspect).atan()),
+        }
+    }
+
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    //float depth buffers are most precise near 0, which a normal projection spends on the few units right in front of the camera - flipping it puts that precision out in the distance, where z-fighting happens
+    //the depth buffer has to be cleared to 0 and tested with Greater to match (which State does when it is set up with EngineBuilder::with_reverse_z)
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        self.reverse_z = reverse_z;
+    }
+
+    //the matrix is recalculated every frame, so changes take effect straight away
+    pub fn set_fov<F: Into<Rad<f32>>>(&mut self, fov: F) -> anyhow::Result<()> {
+        let fov: Rad<f32> = fov.into();
+        anyhow::ensure!(
+            fov.0 > 0.0 && fov.0 < std::f32::consts::PI,
+            "field of view must be between 0 and 180 degrees (got {:?})",
+            Deg::from(fov)
+        );
+        self.fov = fov;
+        Ok(())
+    }
+
+    //a znear of 0 (or less) or a zfar at or before znear would make the projection matrix degenerate
+    pub fn set_znear(&mut self, znear: f32) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            znear > 0.0 && znear < self.zfar,
+            "znear must be above 0 and below zfar ({}), got {znear}",
+            self.zfar
+        );
+        self.znear = znear;
+        Ok(())
+    }
+
+    pub fn set_zfar(&mut self, zfar: f32) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            zfar > self.znear,
+            "zfar must be beyond znear ({}), got {zfar}",
+            self.znear
+        );
+        self.zfar = zfar;
+        Ok(())
+    }
+
     //perspective makes a matrix from four values
     pub fn calc_matrix(&self) -> Matrix4<f32> {
-        OPENGL_TO_WGPU_MATRIX * perspective(self.fov, self.aspect, self.znear, self.zfar)
+        let matrix: Matrix4<f32> = OPENGL_TO_WGPU_MATRIX
+            * perspective(self.vertical_fov(), self.aspect, self.znear, self.zfar);
+        if self.reverse_z {
+            REVERSE_Z_MATRIX * matrix
+        } else {
+            matrix
+        }
     }
 }
 
@@ -90,12 +288,38 @@ pub struct CameraController {
     amount_backward: f32,
     amount_up: f32,
     amount_down: f32,
+    amount_roll_left: f32,
+    amount_roll_right: f32,
     rotate_horizontal: f32,
     rotate_vertical: f32,
+    //how far the mouse has been dragged while panning since the last update
+    pan_horizontal: f32,
+    pan_vertical: f32,
     scroll: f32,
     speed: f32,
-    //how fast the camera moves when we tell it to move
-    sensitivity: f32,
+    //how fast the camera swings left/right when the mouse moves horizontally
+    sensitivity_x: f32,
+    //how fast the camera swings up/down when the mouse moves vertically
+    sensitivity_y: f32,
+    //how far scrolling moves the camera forwards/backwards (for each unit of speed) in ScrollMode::Dolly - kept apart from the mouse sensitivities, so changing how fast the camera looks around doesn't change it
+    dolly_speed: f32,
+    //flips the vertical mouse look, for people used to flight-sim style controls
+    invert_y: bool,
+    //how quickly the camera's velocity catches up with the keys being held - None means movement starts and stops instantly
+    smoothing: Option<f32>,
+    //the current (camera relative) movement: x is right, y is up and z is forward
+    velocity: Vector3<f32>,
+    //while held, movement is sped up by sprint_multiplier - None means there is no sprint key
+    sprint_key: Option<VirtualKeyCode>,
+    sprint_multiplier: f32,
+    sprinting: bool,
+    //boxes the camera can't move into (see set_colliders) - empty means it flies through everything
+    colliders: Vec<crate::Aabb>,
+    //the camera is treated as a sphere this big when colliding
+    collision_radius: f32,
+    scroll_mode: ScrollMode,
+    //how much the field of view should be multiplied by since take_zoom was last called (only changed in ScrollMode::Zoom)
+    zoom: f32,
 }
 
 impl CameraController {
@@ -108,21 +332,102 @@ impl CameraController {
             amount_backward: 0.0,
             amount_up: 0.0,
             amount_down: 0.0,
+            amount_roll_left: 0.0,
+            amount_roll_right: 0.0,
             rotate_horizontal: 0.0,
             rotate_vertical: 0.0,
+            pan_horizontal: 0.0,
+            pan_vertical: 0.0,
             scroll: 0.0,
             speed,
-            //how fast the camera swings around
-            sensitivity,
+            //how fast the camera swings around - the same on both axes unless set with with_sensitivity()
+            sensitivity_x: sensitivity,
+            sensitivity_y: sensitivity,
+            //starts out the same as the sensitivity, which scrolling used to be scaled by
+            dolly_speed: sensitivity,
+            invert_y: false,
+            smoothing: None,
+            velocity: Vector3::zero(),
+            sprint_key: None,
+            sprint_multiplier: 1.0,
+            sprinting: false,
+            colliders: Vec::new(),
+            collision_radius: 0.25,
+            scroll_mode: ScrollMode::Dolly,
+            zoom: 1.0,
         }
     }
 
+    //makes the camera accelerate and decelerate instead of starting and stopping instantly - higher factors catch up faster
+    pub fn with_smoothing(mut self, factor: f32) -> Self {
+        self.smoothing = Some(factor.max(0.0));
+        self
+    }
+
+    //sets separate horizontal and vertical mouse sensitivities
+    pub fn with_sensitivity(mut self, sensitivity_x: f32, sensitivity_y: f32) -> Self {
+        self.sensitivity_x = sensitivity_x;
+        self.sensitivity_y = sensitivity_y;
+        self
+    }
+
+    pub fn with_dolly_speed(mut self, dolly_speed: f32) -> Self {
+        self.set_dolly_speed(dolly_speed);
+        self
+    }
+
+    pub fn set_dolly_speed(&mut self, dolly_speed: f32) {
+        self.dolly_speed = dolly_speed.max(0.0);
+    }
+
+    //moving the mouse up will make the camera look down (and vice versa)
+    pub fn with_invert_y(mut self, invert_y: bool) -> Self {
+        self.invert_y = invert_y;
+        self
+    }
+
+    //how fast (in units per second) the camera moves
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    //sets both the horizontal and vertical mouse sensitivity (see with_sensitivity to set them separately)
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity_x = sensitivity;
+        self.sensitivity_y = sensitivity;
+    }
+
+    //holding the key multiplies the speed by multiplier - it takes priority over the key's usual binding (so using LShift gives up moving down with it)
+    pub fn set_sprint(&mut self, key: Option<VirtualKeyCode>, multiplier: f32) {
+        self.sprint_key = key;
+        self.sprint_multiplier = multiplier.max(0.0);
+        self.sprinting = false;
+    }
+
+    //stops the camera moving into any of the boxes - it slides along their surfaces instead (an empty list turns collision off)
+    pub fn set_colliders(&mut self, colliders: Vec<crate::Aabb>) {
+        self.colliders = colliders;
+    }
+
+    //how close (in units) the camera can get to a collider
+    pub fn set_collision_radius(&mut self, radius: f32) {
+        self.collision_radius = radius.max(0.0);
+    }
+
     pub fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
         let amount: f32 = if state == ElementState::Pressed {
             1.0
         } else {
             0.0
         };
+        if self.sprint_key == Some(key) {
+            self.sprinting = state == ElementState::Pressed;
+            return true;
+        }
         match key {
             VirtualKeyCode::W | VirtualKeyCode::Up => {
                 self.amount_forward = amount;
@@ -148,59 +453,364 @@ impl CameraController {
                 self.amount_down = amount;
                 true
             }
+            VirtualKeyCode::Q => {
+                self.amount_roll_left = amount;
+                true
+            }
+            VirtualKeyCode::E => {
+                self.amount_roll_right = amount;
+                true
+            }
             _ => false,
         }
     }
 
+    //forget every key being held and stop moving - for when something else takes over the camera, and won't pass on the key releases
+    pub fn stop(&mut self) {
+        self.amount_left = 0.0;
+        self.amount_right = 0.0;
+        self.amount_forward = 0.0;
+        self.amount_backward = 0.0;
+        self.amount_up = 0.0;
+        self.amount_down = 0.0;
+        self.amount_roll_left = 0.0;
+        self.amount_roll_right = 0.0;
+        self.rotate_horizontal = 0.0;
+        self.rotate_vertical = 0.0;
+        self.pan_horizontal = 0.0;
+        self.pan_vertical = 0.0;
+        self.scroll = 0.0;
+        self.sprinting = false;
+        self.velocity = Vector3::zero();
+    }
+
     pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
         self.rotate_horizontal = mouse_dx as f32;
-        self.rotate_vertical = mouse_dy as f32;
+        self.rotate_vertical = if self.invert_y {
+            -mouse_dy as f32
+        } else {
+            mouse_dy as f32
+        };
+    }
+
+    //slides the camera sideways and up/down (without turning it) as the mouse is dragged - several drags between updates add up
+    pub fn process_pan(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.pan_horizontal += mouse_dx as f32;
+        self.pan_vertical += mouse_dy as f32;
+    }
+
+    pub fn scroll_mode(&self) -> ScrollMode {
+        self.scroll_mode
+    }
+
+    pub fn set_scroll_mode(&mut self, scroll_mode: ScrollMode) {
+        self.scroll_mode = scroll_mode;
+        self.scroll = 0.0;
     }
 
     pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
-        self.scroll = -match delta {
-            //assuming a line is about 100 pixels
-            MouseScrollDelta::LineDelta(_, scroll) => scroll * 100.0,
-            MouseScrollDelta::PixelDelta(PhysicalPosition { y: scroll, .. }) => *scroll as f32,
-        };
+        //in lines, so each step of a mouse wheel changes the speed/zoom by the same amount
+        let lines: f32 = scroll_amount(delta) / 100.0;
+        match self.scroll_mode {
+            ScrollMode::Dolly => self.scroll = scroll_amount(delta),
+            ScrollMode::Speed => self.set_speed(self.speed * SCROLL_SPEED_STEP.powf(-lines)),
+            ScrollMode::Zoom => self.zoom *= SCROLL_ZOOM_STEP.powf(lines),
+        }
+    }
+
+    //the controller doesn't own the projection, so whoever does applies this to its field of view - it resets to 1 each time it is taken
+    pub fn take_zoom(&mut self) -> f32 {
+        std::mem::replace(&mut self.zoom, 1.0)
     }
 
     //dt = delta_time
     pub fn update_camera(&mut self, camera: &mut Camera, dt: Duration) {
-        let dt: f32 = dt.as_secs_f32();
+        let dt: f32 = dt.as_secs_f32().min(MAX_UPDATE_DT);
+
+        //pan - the scene follows the mouse, so the camera moves the opposite way to the drag (dragging down moves the camera up)
+        //this is a distance the mouse moved rather than a speed, so it isn't scaled by dt
+        camera.position += (camera.right() * -self.pan_horizontal
+            + camera.up() * self.pan_vertical)
+            * self.speed
+            * PAN_SPEED;
+        self.pan_horizontal = 0.0;
+        self.pan_vertical = 0.0;
+
+        //the velocity the held keys are asking for
+        let target_velocity: Vector3<f32> = Vector3::new(
+            self.amount_right - self.amount_left,
+            self.amount_up - self.amount_down,
+            self.amount_forward - self.amount_backward,
+        );
+        self.velocity = match self.smoothing {
+            //exponential smoothing - framerate independent as the blend amount is based on dt
+            Some(factor) => {
+                self.velocity + (target_velocity - self.velocity) * (1.0 - (-factor * dt).exp())
+            }
+            None => target_velocity,
+        };
+
+        //the keys move the camera faster while sprinting
+        let speed: f32 = if self.sprinting {
+            self.speed * self.sprint_multiplier
+        } else {
+            self.speed
+        };
 
         //move forward/backward and left/right
         let (yaw_sin, yaw_cos) = camera.yaw.0.sin_cos();
         let forward: Vector3<f32> = Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
         let right: Vector3<f32> = Vector3::new(-yaw_sin, 0.0, yaw_cos).normalize();
-        camera.position += forward * (self.amount_forward - self.amount_backward) * self.speed * dt;
-        camera.position += right * (self.amount_right - self.amount_left) * self.speed * dt;
+        camera.position += forward * self.velocity.z * speed * dt;
+        camera.position += right * self.velocity.x * speed * dt;
 
         //move in/out (aka. "zoom")
         //note: this isn't an actual zoom - The camera's position changes when zooming - this is just to make it easier to get closer to an object you want to focus on
-        //[TODO] create an actual zoom (for gun sights ect)
+        //(ScrollMode::Zoom changes the field of view instead, for an actual zoom)
         let (pitch_sin, pitch_cos) = camera.pitch.0.sin_cos();
         let scrollward: Vector3<f32> =
             Vector3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalize();
-        camera.position += scrollward * self.scroll * self.speed * self.sensitivity * dt;
+        camera.position += scrollward * self.scroll * self.speed * self.dolly_speed * dt;
         self.scroll = 0.0;
 
-        //move up/down - since we don't use roll, we can just modify the y coordinate directly
-        camera.position.y += (self.amount_up - self.amount_down) * self.speed * dt;
+        //move up/down - this has to be along the camera's rolled up vector rather than just the y coordinate, otherwise "up" would be wrong as soon as the camera is tilted
+        camera.position += camera.up() * self.velocity.y * speed * dt;
 
         //rotate
-        camera.yaw += Rad(self.rotate_horizontal) * self.sensitivity * dt;
-        camera.pitch += Rad(-self.rotate_vertical) * self.sensitivity * dt;
+        camera.yaw += Rad(self.rotate_horizontal) * self.sensitivity_x * dt;
+        camera.pitch += Rad(-self.rotate_vertical) * self.sensitivity_y * dt;
+        camera.roll += Rad(self.amount_roll_right - self.amount_roll_left) * ROLL_SPEED * dt;
+
+        //only the part of the movement going into a box is undone, so the camera slides along it
+        for _ in 0..COLLISION_ITERATIONS {
+            let mut collided: bool = false;
+            for collider in &self.colliders {
+                if let Some(push) = collider.push_out_sphere(camera.position, self.collision_radius)
+                {
+                    camera.position += push;
+                    collided = true;
+                }
+            }
+            if !collided {
+                break;
+            }
+        }
 
         //if process_mouse isn't called every frame, these values will not get set to zero, and the camera will rotate when moving in a non cardinal direction
         self.rotate_horizontal = 0.0;
         self.rotate_vertical = 0.0;
 
-        //keep the camera's angle from going too high/low (as this can cause issues)
-        if camera.pitch < -Rad(SAFE_FRAC_PI_2) {
-            camera.pitch = -Rad(SAFE_FRAC_PI_2);
-        } else if camera.pitch > Rad(SAFE_FRAC_PI_2) {
-            camera.pitch = Rad(SAFE_FRAC_PI_2);
+        clamp_pitch(camera);
+    }
+}
+
+//which controller is currently driving the camera
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CameraMode {
+    //move freely around the scene with the keyboard and look around with the mouse
+    FreeFly,
+    //orbit around a target point, like in most model viewers
+    Orbit,
+}
+
+//the closest an orbiting camera is allowed to get to its target
+const MIN_ORBIT_DISTANCE: f32 = 0.1;
+
+//an arcball style controller that keeps the camera looking at a target point from a set distance
+#[derive(Debug)]
+pub struct OrbitController {
+    //the point the camera orbits around
+    pub target: Point3<f32>,
+    //how far away from the target the camera is
+    pub distance: f32,
+    rotate_horizontal: f32,
+    rotate_vertical: f32,
+    scroll: f32,
+    //how fast the camera swings around the target (and how fast scrolling changes the distance)
+    sensitivity: f32,
+}
+
+impl OrbitController {
+    pub fn new<T: Into<Point3<f32>>>(target: T, distance: f32, sensitivity: f32) -> Self {
+        Self {
+            target: target.into(),
+            distance: distance.max(MIN_ORBIT_DISTANCE),
+            rotate_horizontal: 0.0,
+            rotate_vertical: 0.0,
+            scroll: 0.0,
+            sensitivity,
         }
     }
+
+    pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.rotate_horizontal = mouse_dx as f32;
+        self.rotate_vertical = mouse_dy as f32;
+    }
+
+    pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        self.scroll = scroll_amount(delta);
+    }
+
+    //dt = delta_time
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: Duration) {
+        let dt: f32 = dt.as_secs_f32().min(MAX_UPDATE_DT);
+
+        //dragging the mouse swings the camera around the target
+        camera.yaw += Rad(self.rotate_horizontal) * self.sensitivity * dt;
+        camera.pitch += Rad(-self.rotate_vertical) * self.sensitivity * dt;
+        self.rotate_horizontal = 0.0;
+        self.rotate_vertical = 0.0;
+        clamp_pitch(camera);
+
+        //scrolling moves the camera towards/away from the target
+        self.distance =
+            (self.distance + self.scroll * self.sensitivity * dt).max(MIN_ORBIT_DISTANCE);
+        self.scroll = 0.0;
+
+        //place the camera on the sphere around the target, looking straight at it
+        camera.position = self.target - camera.forward() * self.distance;
+    }
+}
+
+//converts a scroll event into a single amount - scrolling up (away from the user) is negative
+fn scroll_amount(delta: &MouseScrollDelta) -> f32 {
+    -match delta {
+        //assuming a line is about 100 pixels
+        MouseScrollDelta::LineDelta(_, scroll) => scroll * 100.0,
+        MouseScrollDelta::PixelDelta(PhysicalPosition { y: scroll, .. }) => *scroll as f32,
+    }
+}
+
+//keep the camera's angle from going too high/low (as this can cause issues)
+fn clamp_pitch(camera: &mut Camera) {
+    if camera.pitch < -Rad(SAFE_FRAC_PI_2) {
+        camera.pitch = -Rad(SAFE_FRAC_PI_2);
+    } else if camera.pitch > Rad(SAFE_FRAC_PI_2) {
+        camera.pitch = Rad(SAFE_FRAC_PI_2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //the depth a point straight ahead of the camera ends up with after the perspective divide
+    fn depth_at(projection: &Projection, distance: f32) -> f32 {
+        let clip: Vector4<f32> = projection.calc_matrix() * Vector4::new(0.0, 0.0, -distance, 1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn camera_basis_is_orthonormal() {
+        let level: Camera = Camera::new((0.0, 0.0, 0.0), Deg(30.0), Deg(-20.0), Deg(0.0));
+        let rolled: Camera = Camera::new((0.0, 0.0, 0.0), Deg(30.0), Deg(-20.0), Deg(25.0));
+        for camera in [&level, &rolled] {
+            let (forward, right, up) = (camera.forward(), camera.right(), camera.up());
+            for vector in [forward, right, up] {
+                assert!((vector.magnitude() - 1.0).abs() < 1e-5);
+            }
+            assert!(forward.dot(right).abs() < 1e-5);
+            assert!(forward.dot(up).abs() < 1e-5);
+            assert!(right.dot(up).abs() < 1e-5);
+        }
+        //without roll, right stays level and up leans back as the camera looks down
+        assert!(level.right().y.abs() < 1e-5);
+        assert!(level.up().y > 0.0 && level.up().y < 1.0);
+    }
+
+    #[test]
+    fn reverse_z_flips_the_depth_range() {
+        let mut projection: Projection = Projection::new(800, 600, Deg(45.0), 0.1, 100.0);
+        assert!(depth_at(&projection, 0.1).abs() < 1e-5);
+        assert!((depth_at(&projection, 100.0) - 1.0).abs() < 1e-5);
+
+        projection.set_reverse_z(true);
+        assert!((depth_at(&projection, 0.1) - 1.0).abs() < 1e-5);
+        assert!(depth_at(&projection, 100.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn horizontal_fov_shrinks_vertically_on_wide_screens() {
+        let mut projection: Projection = Projection::new(800, 800, Deg(90.0), 0.1, 100.0);
+        projection.set_fov_axis(FovAxis::Horizontal);
+        //a square view has the same field of view both ways
+        assert!((projection.vertical_fov().0 - Rad::from(Deg(90.0)).0).abs() < 1e-5);
+
+        //at 2:1, tan(vertical / 2) is half of tan(horizontal / 2)
+        projection.resize(1600, 800);
+        assert!(((projection.vertical_fov().0 / 2.0).tan() - 0.5).abs() < 1e-5);
+
+        projection.set_fov_axis(FovAxis::Vertical);
+        assert_eq!(projection.vertical_fov(), projection.fov());
+    }
+
+    #[test]
+    fn camera_state_round_trips_through_json() {
+        let camera: Camera = Camera::new((1.0, 2.0, 3.0), Rad(0.5), Rad(-0.25), Rad(0.1));
+        let json: String = serde_json::to_string(&camera.to_state()).unwrap();
+        let restored: Camera = Camera::from_state(serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.to_state(), camera.to_state());
+    }
+
+    #[test]
+    fn degenerate_projections_are_rejected() {
+        let mut projection: Projection = Projection::new(800, 600, Deg(45.0), 0.1, 100.0);
+        assert!(projection.set_znear(0.0).is_err());
+        assert!(projection.set_zfar(0.05).is_err());
+        assert!(projection.set_fov(Deg(180.0)).is_err());
+        assert!(projection.set_znear(1.0).is_ok());
+        assert_eq!(projection.znear(), 1.0);
+    }
+
+    #[test]
+    fn look_at_faces_the_target() {
+        let mut camera: Camera = Camera::new((0.0, 5.0, 10.0), Deg(0.0), Deg(0.0), Deg(0.0));
+        let target: Point3<f32> = Point3::new(3.0, 2.0, -4.0);
+        camera.look_at(target);
+
+        let expected: Vector3<f32> = (target - camera.position).normalize();
+        assert!((camera.forward() - expected).magnitude() < 1e-5);
+    }
+
+    fn test_camera() -> Camera {
+        Camera::new((0.0, 0.0, 0.0), Deg(0.0), Deg(0.0), Deg(0.0))
+    }
+
+    #[test]
+    fn large_dt_is_clamped() {
+        let mut camera: Camera = test_camera();
+        let mut controller: CameraController = CameraController::new(4.0, 0.4);
+        controller.process_keyboard(VirtualKeyCode::W, ElementState::Pressed);
+        //a 10 second stall should only move the camera as far as MAX_UPDATE_DT would
+        controller.update_camera(&mut camera, Duration::from_secs(10));
+        let travelled: f32 = camera.position.to_vec().magnitude();
+        assert!((travelled - 4.0 * MAX_UPDATE_DT).abs() < 1e-5);
+    }
+
+    #[test]
+    fn smoothing_accelerates_towards_target() {
+        let mut camera: Camera = test_camera();
+        let mut controller: CameraController = CameraController::new(4.0, 0.4).with_smoothing(5.0);
+        controller.process_keyboard(VirtualKeyCode::W, ElementState::Pressed);
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        let first: f32 = controller.velocity.z;
+        assert!(first > 0.0 && first < 1.0);
+        for _ in 0..200 {
+            controller.update_camera(&mut camera, Duration::from_millis(16));
+        }
+        assert!((controller.velocity.z - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stopping_forgets_held_keys() {
+        let mut camera: Camera = test_camera();
+        let mut controller: CameraController = CameraController::new(4.0, 0.4).with_smoothing(5.0);
+        controller.process_keyboard(VirtualKeyCode::W, ElementState::Pressed);
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        //W is never released - e.g. it was let go of while another controller had the camera
+        controller.stop();
+        let position: Point3<f32> = camera.position;
+        controller.update_camera(&mut camera, Duration::from_millis(16));
+        assert_eq!(camera.position, position);
+    }
 }
@@ -0,0 +1,322 @@
+//for skipping instances that are off screen on the gpu - a compute shader tests each one against the camera's frustum and packs the visible ones together, which the meshes are then drawn from with draw_indexed_indirect (so the cpu never needs to know how many survived)
+
+use wgpu::util::DeviceExt;
+
+use crate::{model, InstanceRaw};
+
+//how many instances each workgroup of the compute shader tests (has to match @workgroup_size in cull.wgsl)
+const WORKGROUP_SIZE: u32 = 64;
+
+//the arguments of one draw_indexed_indirect call, laid out how wgpu reads them from the buffer
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct DrawArgs {
+    index_count: u32,
+    //counted up by the compute shader, once for each instance that is visible
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    //always 0 (the visible instances start at the beginning of the culled buffer), so Features::INDIRECT_FIRST_INSTANCE isn't needed
+    first_instance: u32,
+}
+
+//what the compute shader needs to know about this frame
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CullUniform {
+    //each plane is (normal, distance), with the normal pointing into the frustum
+    planes: [[f32; 4]; 6],
+    instance_count: u32,
+    //the radius of a sphere around the model's origin that the whole model fits inside (before the instance's scale)
+    radius: f32,
+    mesh_count: u32,
+    _padding: u32,
+}
+
+//the six planes (left, right, bottom, top, near, far) around what a view-projection matrix can see, pointing inwards and normalized - a point p is inside all of them when dot(plane.xyz, p) + plane.w >= 0
+//wgpu's clip space has z going from 0 to 1, which holds for reverse-z too (the planes just swap which is near and which is far)
+pub fn frustum_planes(view_proj: cgmath::Matrix4<f32>) -> [[f32; 4]; 6] {
+    use cgmath::{InnerSpace, Matrix};
+
+    //rows of the matrix (cgmath stores columns)
+    let row: [cgmath::Vector4<f32>; 4] = [
+        view_proj.row(0),
+        view_proj.row(1),
+        view_proj.row(2),
+        view_proj.row(3),
+    ];
+    let planes: [cgmath::Vector4<f32>; 6] = [
+        row[3] + row[0],
+        row[3] - row[0],
+        row[3] + row[1],
+        row[3] - row[1],
+        row[2],
+        row[3] - row[2],
+    ];
+    planes.map(|plane| {
+        //scaling the whole plane by its normal's length makes the distance in world units, so it can be compared with a radius
+        let length: f32 = plane.truncate().magnitude();
+        (plane / length).into()
+    })
+}
+
+//everything needed to cull our instances on the gpu and draw whatever is left
+pub struct InstanceCuller {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    //the visible instances, packed together - used as the instance (vertex) buffer instead of the real one
+    culled_buffer: wgpu::Buffer,
+    //one DrawArgs per mesh of the model
+    args_buffer: wgpu::Buffer,
+    //re-created by prepare, as the instance buffer it reads from can be swapped for a bigger one at any time
+    bind_group: Option<wgpu::BindGroup>,
+    instance_count: u32,
+}
+
+impl InstanceCuller {
+    //what the adapter has to support for culling on the gpu - compute shaders to cull with, and indirect draws to use the results (webgl has neither)
+    pub const DOWNLEVEL_FLAGS: wgpu::DownlevelFlags =
+        wgpu::DownlevelFlags::COMPUTE_SHADERS.union(wgpu::DownlevelFlags::INDIRECT_EXECUTION);
+
+    pub fn new(device: &wgpu::Device) -> anyhow::Result<Self> {
+        let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("cull_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    //every instance
+                    storage(1, true),
+                    //the visible instances
+                    storage(2, false),
+                    //the draw arguments
+                    storage(3, false),
+                ],
+            });
+
+        let layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Cull Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        //the same as create_render_pipeline, a shader that doesn't compile is returned as an error instead of panicking
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader: wgpu::ShaderModule =
+            device.create_shader_module(wgpu::include_wgsl!("shaders/cull.wgsl"));
+        let pipeline: wgpu::ComputePipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Cull Pipeline"),
+                layout: Some(&layout),
+                module: &shader,
+                entry_point: "cs_main",
+            });
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!("couldn't create the culling pipeline: {error}");
+        }
+
+        let uniform_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Cull Uniform Buffer"),
+                contents: bytemuck::cast_slice(&[<CullUniform as bytemuck::Zeroable>::zeroed()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            culled_buffer: Self::create_culled_buffer(device, 1),
+            args_buffer: Self::create_args_buffer(device, 1),
+            bind_group: None,
+            instance_count: 0,
+        })
+    }
+
+    fn create_culled_buffer(device: &wgpu::Device, instances: u32) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Culled Instance Buffer"),
+            size: (instances.max(1) as usize * std::mem::size_of::<InstanceRaw>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_args_buffer(device: &wgpu::Device, meshes: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Indirect Draw Buffer"),
+            size: (meshes.max(1) * std::mem::size_of::<DrawArgs>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    //gets this frame's culling ready - resetting the draw arguments (no instances yet) and growing the buffers if there are more instances or meshes than before
+    //instances has to have been created with BufferUsages::STORAGE, as the compute shader reads from it
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instances: &wgpu::Buffer,
+        instance_count: u32,
+        model: &model::Model,
+        view_proj: cgmath::Matrix4<f32>,
+    ) {
+        let instance_size: wgpu::BufferAddress =
+            std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress;
+        if instance_count as wgpu::BufferAddress * instance_size > self.culled_buffer.size() {
+            self.culled_buffer = Self::create_culled_buffer(device, instance_count);
+        }
+        let args: Vec<DrawArgs> = model
+            .meshes
+            .iter()
+            .map(|mesh| DrawArgs {
+                index_count: mesh.num_elements,
+                instance_count: 0,
+                first_index: 0,
+                base_vertex: 0,
+                first_instance: 0,
+            })
+            .collect();
+        let args_data: &[u8] = bytemuck::cast_slice(&args);
+        if args_data.len() as wgpu::BufferAddress > self.args_buffer.size() {
+            self.args_buffer = Self::create_args_buffer(device, args.len());
+        }
+        if !args_data.is_empty() {
+            queue.write_buffer(&self.args_buffer, 0, args_data);
+        }
+
+        let uniform: CullUniform = CullUniform {
+            planes: frustum_planes(view_proj),
+            instance_count,
+            radius: model.bounds.bounding_radius(),
+            mesh_count: args.len() as u32,
+            _padding: 0,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("cull_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: instances.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.culled_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.args_buffer.as_entire_binding(),
+                },
+            ],
+        }));
+        self.instance_count = instance_count;
+    }
+
+    //records the compute pass that does the culling - has to come before the render pass that draws with the results
+    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(bind_group) = &self.bind_group else {
+            return;
+        };
+        if self.instance_count == 0 {
+            return;
+        }
+        let mut compute_pass: wgpu::ComputePass =
+            encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Cull Pass"),
+            });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.instance_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    //draws the meshes whose material needs the pass's pipeline (which should already be set) at the visible instances only - the instance buffer in slot 1 is left pointing at the culled one
+    pub fn draw_pass<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        model: &'a model::Model,
+        pass: model::MaterialPass,
+        camera_bind_group: &'a wgpu::BindGroup,
+        light_bind_group: &'a wgpu::BindGroup,
+    ) {
+        render_pass.set_vertex_buffer(1, self.culled_buffer.slice(..));
+        //the draw arguments are per mesh, so they are found by the mesh's index in the whole model
+        for (index, mesh) in model.meshes.iter().enumerate() {
+            if !mesh.visible {
+                continue;
+            }
+            let Some(material) = model
+                .materials
+                .get(mesh.material)
+                .filter(|material| material.pass() == pass)
+            else {
+                continue;
+            };
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+            render_pass.set_bind_group(0, &material.bind_group, &[]);
+            render_pass.set_bind_group(1, camera_bind_group, &[]);
+            render_pass.set_bind_group(2, light_bind_group, &[]);
+            render_pass.draw_indexed_indirect(
+                &self.args_buffer,
+                (index * std::mem::size_of::<DrawArgs>()) as wgpu::BufferAddress,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frustum_planes_contain_what_the_camera_sees() {
+        //a camera at the origin looking down -z, with a near plane of 1 and far plane of 10
+        let proj: cgmath::Matrix4<f32> = crate::camera::OPENGL_TO_WGPU_MATRIX
+            * cgmath::perspective(cgmath::Deg(90.0), 1.0, 1.0, 10.0);
+        let planes: [[f32; 4]; 6] = frustum_planes(proj);
+        let inside = |point: [f32; 3]| {
+            planes
+                .iter()
+                .all(|p| p[0] * point[0] + p[1] * point[1] + p[2] * point[2] + p[3] >= -1e-4)
+        };
+        assert!(inside([0.0, 0.0, -5.0]));
+        assert!(inside([4.0, 0.0, -5.0]));
+        assert!(inside([0.0, 0.0, -1.5]));
+        //behind the camera, past the far plane, and off to the side
+        assert!(!inside([0.0, 0.0, 5.0]));
+        assert!(!inside([0.0, 0.0, -0.5]));
+        assert!(!inside([0.0, 0.0, -11.0]));
+        assert!(!inside([6.0, 0.0, -5.0]));
+    }
+}
@@ -0,0 +1,233 @@
+//for reading the header of .dds files (directx's texture container) - just enough to upload BC1/BC3/BC7 data straight to the gpu
+//hand-rolled rather than using the ddsfile crate, as we only need a handful of header fields (and none of its writing or format conversion) - so it has to treat every field as untrusted itself
+
+use anyhow::*;
+
+use crate::texture::ColorSpace;
+
+//"DDS " - every .dds file starts with it
+const MAGIC: &[u8; 4] = b"DDS ";
+//the main header comes straight after the magic, and the dx10 header (if there is one) straight after that
+const HEADER_SIZE: usize = 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+//DXGI_FORMAT values (from dxgiformat.h) the dx10 header can hold
+const DXGI_FORMAT_BC1_UNORM: u32 = 71;
+const DXGI_FORMAT_BC1_UNORM_SRGB: u32 = 72;
+const DXGI_FORMAT_BC3_UNORM: u32 = 77;
+const DXGI_FORMAT_BC3_UNORM_SRGB: u32 = 78;
+const DXGI_FORMAT_BC7_UNORM: u32 = 98;
+const DXGI_FORMAT_BC7_UNORM_SRGB: u32 = 99;
+
+//what Texture::from_dds needs to know to upload a .dds file
+#[derive(Debug, PartialEq)]
+pub struct Dds<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub mip_level_count: u32,
+    pub format: wgpu::TextureFormat,
+    //how many bytes each 4x4 block of pixels takes up
+    pub block_size: u32,
+    //the data of each mip level, largest first
+    pub levels: Vec<&'a [u8]>,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+//color_space is only used for files with the older (pre-dx10) header, which doesn't say whether its data is srgb or not
+pub fn parse(bytes: &[u8], color_space: ColorSpace) -> Result<Dds<'_>> {
+    ensure!(
+        bytes.len() >= MAGIC.len() + HEADER_SIZE && bytes.starts_with(MAGIC),
+        "not a dds file"
+    );
+    let header: &[u8] = &bytes[MAGIC.len()..MAGIC.len() + HEADER_SIZE];
+    let height: u32 = read_u32(header, 8);
+    let width: u32 = read_u32(header, 12);
+    ensure!(
+        width > 0 && height > 0,
+        "dds file is {width}x{height}, which has no pixels"
+    );
+    //0 when the file has no mip levels beyond the first - and there can't be more than it takes to halve the largest side down to 1
+    let mip_level_count: u32 = read_u32(header, 24).max(1);
+    let max_mip_level_count: u32 = u32::BITS - width.max(height).leading_zeros();
+    ensure!(
+        mip_level_count <= max_mip_level_count,
+        "dds file has {mip_level_count} mip levels, but a {width}x{height} texture can only have {max_mip_level_count}"
+    );
+    //the pixel format is at 72, with its four character code 8 bytes in
+    let four_cc: &[u8] = &header[80..84];
+
+    let mut data_offset: usize = MAGIC.len() + HEADER_SIZE;
+    let (srgb, linear, block_size) = match four_cc {
+        b"DXT1" => (
+            wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+            wgpu::TextureFormat::Bc1RgbaUnorm,
+            8,
+        ),
+        b"DXT5" => (
+            wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+            wgpu::TextureFormat::Bc3RgbaUnorm,
+            16,
+        ),
+        b"DX10" => {
+            ensure!(
+                bytes.len() >= data_offset + DX10_HEADER_SIZE,
+                "dds file is missing its dx10 header"
+            );
+            let dxgi_format: u32 = read_u32(bytes, data_offset);
+            data_offset += DX10_HEADER_SIZE;
+            //the dx10 header says exactly which format it is, so color_space doesn't matter
+            let (format, block_size): (wgpu::TextureFormat, u32) = match dxgi_format {
+                DXGI_FORMAT_BC1_UNORM => (wgpu::TextureFormat::Bc1RgbaUnorm, 8),
+                DXGI_FORMAT_BC1_UNORM_SRGB => (wgpu::TextureFormat::Bc1RgbaUnormSrgb, 8),
+                DXGI_FORMAT_BC3_UNORM => (wgpu::TextureFormat::Bc3RgbaUnorm, 16),
+                DXGI_FORMAT_BC3_UNORM_SRGB => (wgpu::TextureFormat::Bc3RgbaUnormSrgb, 16),
+                DXGI_FORMAT_BC7_UNORM => (wgpu::TextureFormat::Bc7RgbaUnorm, 16),
+                DXGI_FORMAT_BC7_UNORM_SRGB => (wgpu::TextureFormat::Bc7RgbaUnormSrgb, 16),
+                format => bail!("unsupported dds dxgi format {format}"),
+            };
+            (format, format, block_size)
+        }
+        four_cc => bail!(
+            "unsupported dds format {:?}",
+            String::from_utf8_lossy(four_cc)
+        ),
+    };
+    let format: wgpu::TextureFormat = match color_space {
+        ColorSpace::Srgb => srgb,
+        ColorSpace::Linear => linear,
+    };
+
+    //compressed textures have to be made up of whole blocks
+    ensure!(
+        width.is_multiple_of(4) && height.is_multiple_of(4),
+        "compressed textures must have a width and height that are multiples of 4"
+    );
+
+    //the mip levels are packed one after another, so each one starts where the last ended - and every one has to actually be there
+    let mut remaining: &[u8] = &bytes[data_offset..];
+    let mut levels: Vec<&[u8]> = Vec::with_capacity(mip_level_count as usize);
+    for mip_level in 0..mip_level_count {
+        let size: usize = level_size(width, height, mip_level, block_size)
+            .filter(|size| *size <= remaining.len())
+            .with_context(|| {
+                format!("dds file is too short for mip level {mip_level} of {mip_level_count}")
+            })?;
+        let (level, rest): (&[u8], &[u8]) = remaining.split_at(size);
+        levels.push(level);
+        remaining = rest;
+    }
+
+    Ok(Dds {
+        width,
+        height,
+        mip_level_count,
+        format,
+        block_size,
+        levels,
+    })
+}
+
+//how many bytes one mip level takes up - each is half the size of the last, but still rounded up to whole blocks (also used for .ktx2 files, which store BC levels the same way)
+//None if it's too big to even count (only possible with a made up header)
+pub fn level_size(width: u32, height: u32, mip_level: u32, block_size: u32) -> Option<usize> {
+    let blocks_wide: u64 = (width >> mip_level).max(1).div_ceil(4) as u64;
+    let blocks_high: u64 = (height >> mip_level).max(1).div_ceil(4) as u64;
+    blocks_wide
+        .checked_mul(blocks_high)?
+        .checked_mul(block_size as u64)
+        .and_then(|size| usize::try_from(size).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //the headers of a .dds file with the given four character code (and dxgi format, for DX10) - the mip levels still have to be added after
+    fn dds_file(
+        four_cc: &[u8; 4],
+        dxgi_format: Option<u32>,
+        width: u32,
+        height: u32,
+        mips: u32,
+    ) -> Vec<u8> {
+        let mut header: Vec<u8> = vec![0; HEADER_SIZE];
+        header[0..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        header[8..12].copy_from_slice(&height.to_le_bytes());
+        header[12..16].copy_from_slice(&width.to_le_bytes());
+        header[24..28].copy_from_slice(&mips.to_le_bytes());
+        header[80..84].copy_from_slice(four_cc);
+
+        let mut file: Vec<u8> = MAGIC.to_vec();
+        file.extend(header);
+        if let Some(dxgi_format) = dxgi_format {
+            let mut dx10: Vec<u8> = vec![0; DX10_HEADER_SIZE];
+            dx10[0..4].copy_from_slice(&dxgi_format.to_le_bytes());
+            file.extend(dx10);
+        }
+        file
+    }
+
+    #[test]
+    fn reads_legacy_and_dx10_headers() {
+        //8x8 BC1 with 2 mips is 4 blocks then 1 block, at 8 bytes each
+        let mut file: Vec<u8> = dds_file(b"DXT1", None, 8, 8, 2);
+        file.extend([0; 40]);
+        let dds: Dds = parse(&file, ColorSpace::Srgb).unwrap();
+        assert_eq!(dds.format, wgpu::TextureFormat::Bc1RgbaUnormSrgb);
+        assert_eq!((dds.width, dds.height, dds.mip_level_count), (8, 8, 2));
+        assert_eq!(
+            dds.levels
+                .iter()
+                .map(|level| level.len())
+                .collect::<Vec<_>>(),
+            [32, 8]
+        );
+
+        let mut file: Vec<u8> = dds_file(b"DX10", Some(DXGI_FORMAT_BC7_UNORM), 4, 4, 0);
+        file.extend([0; 16]);
+        let dds: Dds = parse(&file, ColorSpace::Srgb).unwrap();
+        assert_eq!(dds.format, wgpu::TextureFormat::Bc7RgbaUnorm);
+        assert_eq!(dds.mip_level_count, 1);
+    }
+
+    #[test]
+    fn rejects_truncated_and_unsupported_files() {
+        let file: Vec<u8> = dds_file(b"DXT5", None, 8, 8, 1);
+        assert!(parse(&file, ColorSpace::Linear).is_err());
+        let mut file: Vec<u8> = dds_file(b"ATI2", None, 4, 4, 1);
+        file.extend([0; 16]);
+        assert!(parse(&file, ColorSpace::Linear).is_err());
+        assert!(parse(b"not a dds", ColorSpace::Linear).is_err());
+        //cut off half way through its second mip level
+        let mut file: Vec<u8> = dds_file(b"DXT1", None, 8, 8, 2);
+        file.extend([0; 36]);
+        assert!(parse(&file, ColorSpace::Linear).is_err());
+        //cut off half way through the dx10 header
+        let mut file: Vec<u8> = dds_file(b"DX10", Some(DXGI_FORMAT_BC7_UNORM), 4, 4, 1);
+        file.truncate(file.len() - 10);
+        assert!(parse(&file, ColorSpace::Linear).is_err());
+    }
+
+    #[test]
+    fn rejects_hostile_headers() {
+        //no pixels
+        let mut file: Vec<u8> = dds_file(b"DXT1", None, 0, 4, 1);
+        file.extend([0; 8]);
+        assert!(parse(&file, ColorSpace::Linear).is_err());
+        //more mip levels than halving 8 down to 1 gives (which would also shift past the width of a u32)
+        let mut file: Vec<u8> = dds_file(b"DXT1", None, 8, 8, 40);
+        file.extend([0; 1024]);
+        assert!(parse(&file, ColorSpace::Linear).is_err());
+        //a first mip level of 2^60 bytes, which would overflow a u32 (and is far bigger than the file)
+        let file: Vec<u8> = dds_file(b"DX10", Some(DXGI_FORMAT_BC7_UNORM), 1 << 30, 1 << 30, 1);
+        assert!(parse(&file, ColorSpace::Linear).is_err());
+    }
+}
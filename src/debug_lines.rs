@@ -0,0 +1,154 @@
+//for drawing lines (and outlines of boxes) into the scene while debugging - they only last for the frame they were added in
+
+use crate::{bounds, buffer_pool, texture};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LineVertex {
+    position: [f32; 3],
+    color: [f32; 3],
+}
+
+impl LineVertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                //position
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                //colour
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+//line segments collected over a frame, drawn with a LineList pipeline and then thrown away
+pub struct DebugLines {
+    //every 2 vertices is one line
+    vertices: Vec<LineVertex>,
+    buffer: wgpu::Buffer,
+    //how many vertices were last uploaded to the buffer
+    num_vertices: u32,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl DebugLines {
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        reverse_z: bool,
+    ) -> anyhow::Result<Self> {
+        let layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Debug Lines Pipeline Layout"),
+                bind_group_layouts: &[camera_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let shader = wgpu::ShaderModuleDescriptor {
+            label: Some("Debug Lines Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/lines.wgsl").into()),
+        };
+
+        Ok(Self {
+            vertices: Vec::new(),
+            //big enough for one line to start with (a buffer can't be bound with a size of 0) - grown by upload when more are added
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Debug Lines Buffer"),
+                size: 2 * std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            num_vertices: 0,
+            pipeline: crate::create_render_pipeline(
+                device,
+                &layout,
+                color_format,
+                Some(texture::Texture::DEPTH_FORMAT),
+                &[LineVertex::desc()],
+                crate::PipelineOptions {
+                    topology: wgpu::PrimitiveTopology::LineList,
+                    depth_compare: texture::Texture::depth_compare(reverse_z),
+                    ..Default::default()
+                },
+                shader,
+            )?,
+        })
+    }
+
+    //adds a line from start to end to this frame
+    pub fn line(&mut self, start: cgmath::Point3<f32>, end: cgmath::Point3<f32>, color: [f32; 3]) {
+        self.vertices.push(LineVertex {
+            position: start.into(),
+            color,
+        });
+        self.vertices.push(LineVertex {
+            position: end.into(),
+            color,
+        });
+    }
+
+    //adds the 12 edges of a box to this frame
+    pub fn aabb(&mut self, aabb: &bounds::Aabb, color: [f32; 3]) {
+        let corners: [cgmath::Point3<f32>; 8] = aabb.corners();
+        for i in 0..4 {
+            //around the min.z face, around the max.z face, and then between the two
+            self.line(corners[i], corners[(i + 1) % 4], color);
+            self.line(corners[i + 4], corners[(i + 1) % 4 + 4], color);
+            self.line(corners[i], corners[i + 4], color);
+        }
+    }
+
+    //sends this frame's lines to the gpu - the buffer is swapped for a bigger one (from the pool) if they don't fit
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pool: &mut buffer_pool::BufferPool,
+    ) {
+        let data: &[u8] = bytemuck::cast_slice(&self.vertices);
+        if data.len() as wgpu::BufferAddress > self.buffer.size() {
+            let buffer: wgpu::Buffer = pool.acquire(
+                device,
+                "Debug Lines Buffer",
+                data.len() as wgpu::BufferAddress,
+                wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            );
+            pool.release(std::mem::replace(&mut self.buffer, buffer));
+        }
+        if !data.is_empty() {
+            queue.write_buffer(&self.buffer, 0, data);
+        }
+        self.num_vertices = self.vertices.len() as u32;
+    }
+
+    //forget this frame's lines, ready for the next one
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.num_vertices = 0;
+    }
+
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera_bind_group: &'a wgpu::BindGroup,
+    ) {
+        if self.num_vertices == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+        render_pass.draw(0..self.num_vertices, 0..1);
+    }
+}
@@ -0,0 +1,171 @@
+//for drawing the renderer's internal buffers to the screen instead of the lit scene
+
+use wgpu::util::DeviceExt;
+
+use crate::{fullscreen, texture};
+
+//what gets shown on the screen
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugView {
+    //the scene as it would normally be rendered
+    Normal,
+    //the depth buffer, linearized so near is black and far is white
+    Depth,
+    //the scene unlit, with the world space normal of each pixel (after normal mapping) as its colour - for checking normal maps and tangents
+    Normals,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct DepthUniform {
+    znear: f32,
+    zfar: f32,
+    //1 if the depth buffer goes from 1 at znear to 0 at zfar (a bool, but bools can't be put in uniforms)
+    reverse_z: u32,
+    //the size of the depth texture compared to the screen (see State::set_render_scale), for finding the texel under each pixel
+    render_scale: f32,
+}
+
+//everything needed to draw a depth texture onto the screen as a grayscale image
+pub struct DepthView {
+    uniform: DepthUniform,
+    uniform_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    //has to be re-created whenever the depth texture is, as it points at the texture
+    bind_group: wgpu::BindGroup,
+    pass: fullscreen::FullscreenPass,
+}
+
+impl DepthView {
+    pub fn new(
+        device: &wgpu::Device,
+        output_format: wgpu::TextureFormat,
+        depth_texture: &texture::Texture,
+        znear: f32,
+        zfar: f32,
+        reverse_z: bool,
+    ) -> anyhow::Result<Self> {
+        let uniform: DepthUniform = DepthUniform {
+            znear,
+            zfar,
+            reverse_z: reverse_z as u32,
+            render_scale: 1.0,
+        };
+        let uniform_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Depth View Buffer"),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        //the depth texture (read directly with textureLoad, so no sampler) and the projection's near/far planes
+        let bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("depth_view_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Depth,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let bind_group: wgpu::BindGroup =
+            Self::create_bind_group(device, &bind_group_layout, depth_texture, &uniform_buffer);
+
+        let pass: fullscreen::FullscreenPass = fullscreen::FullscreenPass::new(
+            device,
+            "Depth View Pass",
+            &bind_group_layout,
+            output_format,
+            wgpu::ShaderModuleDescriptor {
+                label: Some("Depth View Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/depth.wgsl").into()),
+            },
+        )?;
+
+        Ok(Self {
+            uniform,
+            uniform_buffer,
+            bind_group_layout,
+            bind_group,
+            pass,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        depth_texture: &texture::Texture,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("depth_view_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&depth_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    //point the view at a new depth texture (e.g. after the window is resized) - render_scale is its size compared to the output
+    pub fn set_depth_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        depth_texture: &texture::Texture,
+        render_scale: f32,
+    ) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            depth_texture,
+            &self.uniform_buffer,
+        );
+        self.uniform.render_scale = render_scale;
+        self.write_uniform(queue);
+    }
+
+    //the near/far planes have to match the projection the depth was rendered with to linearize it correctly
+    pub fn set_planes(&mut self, queue: &wgpu::Queue, znear: f32, zfar: f32) {
+        self.uniform.znear = znear;
+        self.uniform.zfar = zfar;
+        self.write_uniform(queue);
+    }
+
+    fn write_uniform(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+    }
+
+    //draws the depth texture onto the output view
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        self.pass.render(encoder, output, &self.bind_group);
+    }
+}
@@ -0,0 +1,106 @@
+//for keeping track of how evenly frames are being drawn - an average fps hides the odd long frame, which is what is seen as stutter
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+//how many of the most recent frames the stats are worked out from (a few seconds' worth at 60fps)
+const FRAME_HISTORY: usize = 240;
+
+//a frame is counted as dropped if it took more than this many times the target frame time
+const DROPPED_FRAME_FACTOR: u32 = 2;
+
+//how long the recent frames took (see State::frame_stats)
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub avg: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    //99% of the recent frames were at least this quick
+    pub p99: Duration,
+    //how many of the recent frames took over twice as long as they should have
+    pub dropped: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct FrameTimer {
+    //the time each recent frame took, oldest first
+    frame_times: VecDeque<Duration>,
+    //how long a frame should take - None uses the average of the recent frames
+    target: Option<Duration>,
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, dt: Duration) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(dt);
+    }
+
+    pub fn set_target(&mut self, target: Option<Duration>) {
+        self.target = target;
+    }
+
+    pub fn target(&self) -> Option<Duration> {
+        self.target
+    }
+
+    //None until a frame has been recorded
+    pub fn stats(&self) -> Option<FrameStats> {
+        let mut sorted: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted.sort_unstable();
+        let (min, max): (Duration, Duration) = (*sorted.first()?, *sorted.last()?);
+
+        let avg: Duration = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+        //nearest rank - the smallest time that at least 99% of frames are at or under
+        let p99: Duration = sorted[(sorted.len() * 99).div_ceil(100) - 1];
+        let threshold: Duration = self.target.unwrap_or(avg) * DROPPED_FRAME_FACTOR;
+        let dropped: u32 = sorted.iter().filter(|dt| **dt > threshold).count() as u32;
+
+        Some(FrameStats {
+            avg,
+            min,
+            max,
+            p99,
+            dropped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_slow_frame_shows_up_in_the_stats() {
+        let mut timer: FrameTimer = FrameTimer::new();
+        assert!(timer.stats().is_none());
+
+        for _ in 0..99 {
+            timer.record(Duration::from_millis(10));
+        }
+        timer.record(Duration::from_millis(50));
+        timer.set_target(Some(Duration::from_millis(10)));
+
+        let stats: FrameStats = timer.stats().unwrap();
+        assert_eq!(stats.min, Duration::from_millis(10));
+        assert_eq!(stats.max, Duration::from_millis(50));
+        assert_eq!(stats.avg, Duration::from_micros(10_400));
+        assert_eq!(stats.p99, Duration::from_millis(10));
+        assert_eq!(stats.dropped, 1);
+    }
+
+    #[test]
+    fn only_the_most_recent_frames_are_kept() {
+        let mut timer: FrameTimer = FrameTimer::new();
+        timer.record(Duration::from_secs(1));
+        for _ in 0..FRAME_HISTORY {
+            timer.record(Duration::from_millis(16));
+        }
+        assert_eq!(timer.stats().unwrap().max, Duration::from_millis(16));
+    }
+}
@@ -0,0 +1,85 @@
+//for passes that draw one triangle over the whole screen, reading from a texture (post-processing, fxaa, the depth view) - they only differ in their shader and bind group
+
+//the bind group layout entries of a filterable texture (binding 0) and its sampler (binding 1), which most fullscreen passes read from
+pub fn texture_layout_entries() -> [wgpu::BindGroupLayoutEntry; 2] {
+    [
+        wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+    ]
+}
+
+pub struct FullscreenPass {
+    label: &'static str,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl FullscreenPass {
+    //the shader's vertex stage has to generate the triangle itself from the vertex index (see postprocess.wgsl) - its only bind group is group 0
+    pub fn new(
+        device: &wgpu::Device,
+        label: &'static str,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        output_format: wgpu::TextureFormat,
+        shader: wgpu::ShaderModuleDescriptor,
+    ) -> anyhow::Result<Self> {
+        let layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        //no depth and no vertex buffers - the fullscreen triangle is generated in the shader
+        let pipeline: wgpu::RenderPipeline = crate::create_render_pipeline(
+            device,
+            &layout,
+            output_format,
+            None,
+            &[],
+            crate::PipelineOptions::default(),
+            shader,
+        )?;
+
+        Ok(Self { label, pipeline })
+    }
+
+    //covers the output view with the shader's output
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        bind_group: &wgpu::BindGroup,
+    ) {
+        let mut render_pass: wgpu::RenderPass =
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(self.label),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: output,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        //every pixel gets overwritten by the fullscreen triangle, so there's no need to clear
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
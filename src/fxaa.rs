@@ -0,0 +1,105 @@
+//anti-aliasing, to smooth out the jagged edges of triangles
+
+use crate::{fullscreen, texture};
+
+//how the edges of triangles are smoothed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AaMode {
+    None,
+    //fast approximate anti-aliasing - a cheap full screen pass that blurs along the edges it finds in the final image
+    Fxaa,
+}
+
+//everything needed to draw the post-processed image onto the screen with fxaa applied
+pub struct Fxaa {
+    //the post-processing pass draws into this (instead of the screen), so fxaa works on the final colours
+    pub texture: texture::Texture,
+    format: wgpu::TextureFormat,
+    bind_group_layout: wgpu::BindGroupLayout,
+    //has to be re-created whenever the texture is, as it points at it
+    bind_group: wgpu::BindGroup,
+    pass: fullscreen::FullscreenPass,
+}
+
+impl Fxaa {
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> anyhow::Result<Self> {
+        let texture: texture::Texture =
+            Self::create_texture(device, config.width, config.height, config.format);
+
+        let bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("fxaa_bind_group_layout"),
+                entries: &fullscreen::texture_layout_entries(),
+            });
+
+        let bind_group: wgpu::BindGroup =
+            Self::create_bind_group(device, &bind_group_layout, &texture);
+
+        let pass: fullscreen::FullscreenPass = fullscreen::FullscreenPass::new(
+            device,
+            "FXAA Pass",
+            &bind_group_layout,
+            config.format,
+            wgpu::ShaderModuleDescriptor {
+                label: Some("FXAA Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/fxaa.wgsl").into()),
+            },
+        )?;
+
+        Ok(Self {
+            texture,
+            format: config.format,
+            bind_group_layout,
+            bind_group,
+            pass,
+        })
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> texture::Texture {
+        //a texture can't have a size of 0, so we make sure it is at least 1x1
+        texture::Texture::create_render_texture(
+            device,
+            width.max(1),
+            height.max(1),
+            format,
+            "fxaa_texture",
+        )
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        texture: &texture::Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("fxaa_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        })
+    }
+
+    //the texture has to stay the same size as the screen
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.texture = Self::create_texture(device, width, height, self.format);
+        self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, &self.texture);
+    }
+
+    //draws the texture onto the output view with its edges smoothed
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        self.pass.render(encoder, output, &self.bind_group);
+    }
+}
@@ -0,0 +1,157 @@
+//for measuring how long the gpu spends drawing each part of the scene, using timestamp queries
+
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+//where in the render pass each timestamp is written
+pub const LIGHT_START: u32 = 0;
+pub const SCENE_START: u32 = 1;
+pub const SCENE_END: u32 = 2;
+const QUERY_COUNT: u32 = 3;
+
+//how far along mapping the readback buffer is
+const MAP_PENDING: u8 = 0;
+const MAP_DONE: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+//how long the gpu took to draw each part of the most recently measured frame
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GpuTimes {
+    //drawing the light's model (with the light pipeline)
+    pub light: Duration,
+    //drawing our model's instances (with the main pipeline) and the skybox
+    pub scene: Duration,
+}
+
+//records timestamps into a query set and reads them back a frame or so later, without ever stalling the gpu
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    //where the queries are resolved to - query results can't be read straight from the query set
+    resolve_buffer: wgpu::Buffer,
+    //a copy of resolve_buffer we can map and read on the cpu
+    readback_buffer: wgpu::Buffer,
+    //how many nanoseconds one timestamp tick is
+    period: f32,
+    //one of the MAP_ states - set from wgpu's callback once mapping the readback buffer has finished
+    map_state: Arc<AtomicU8>,
+    //whether the readback buffer is mapped (or being mapped) - it can't be copied into again until it is unmapped
+    in_flight: bool,
+    last_times: Option<GpuTimes>,
+}
+
+impl GpuTimer {
+    //timestamps are written between draw calls inside the render pass, which needs both of these
+    pub const FEATURES: wgpu::Features =
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::WRITE_TIMESTAMP_INSIDE_PASSES);
+
+    //None if the device wasn't created with FEATURES (usually because the gpu doesn't support them)
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(Self::FEATURES) {
+            return None;
+        }
+
+        let size: wgpu::BufferAddress =
+            (QUERY_COUNT as usize * std::mem::size_of::<u64>()) as wgpu::BufferAddress;
+
+        Some(Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("GPU Timer Query Set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: QUERY_COUNT,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("GPU Timer Resolve Buffer"),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("GPU Timer Readback Buffer"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            period: queue.get_timestamp_period(),
+            map_state: Arc::new(AtomicU8::new(MAP_PENDING)),
+            in_flight: false,
+            last_times: None,
+        })
+    }
+
+    //records the time the gpu reaches this point in the pass (one of LIGHT_START, SCENE_START or SCENE_END)
+    pub fn timestamp(&self, render_pass: &mut wgpu::RenderPass, query: u32) {
+        render_pass.write_timestamp(&self.query_set, query);
+    }
+
+    //copies this frame's timestamps somewhere they can be read - skipped while the previous frame's are still being read
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.in_flight {
+            return;
+        }
+        encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.readback_buffer.size(),
+        );
+    }
+
+    //starts reading back the timestamps resolve() copied - call after the frame has been submitted
+    pub fn map(&mut self) {
+        if self.in_flight {
+            return;
+        }
+        self.in_flight = true;
+        let map_state: Arc<AtomicU8> = self.map_state.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let state: u8 = if result.is_ok() { MAP_DONE } else { MAP_FAILED };
+                map_state.store(state, Ordering::Release)
+            });
+    }
+
+    //picks up the timestamps if they have finished being read back
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        if !self.in_flight {
+            return;
+        }
+        device.poll(wgpu::Maintain::Poll);
+        match self.map_state.swap(MAP_PENDING, Ordering::Acquire) {
+            MAP_DONE => {}
+            //this frame's times are lost, but we can try again with the next one
+            MAP_FAILED => {
+                self.in_flight = false;
+                return;
+            }
+            _ => return,
+        }
+
+        {
+            let data: wgpu::BufferView = self.readback_buffer.slice(..).get_mapped_range();
+            let ticks: &[u64] = bytemuck::cast_slice(&data);
+            self.last_times = Some(GpuTimes {
+                light: self.duration(ticks[LIGHT_START as usize], ticks[SCENE_START as usize]),
+                scene: self.duration(ticks[SCENE_START as usize], ticks[SCENE_END as usize]),
+            });
+        }
+        self.readback_buffer.unmap();
+        self.in_flight = false;
+    }
+
+    //the most recent frame's times that have made it back from the gpu
+    pub fn last_times(&self) -> Option<GpuTimes> {
+        self.last_times
+    }
+
+    fn duration(&self, start: u64, end: u64) -> Duration {
+        //some drivers' timestamps aren't always in order, which would otherwise underflow
+        let ticks: u64 = end.saturating_sub(start);
+        Duration::from_nanos((ticks as f64 * self.period as f64) as u64)
+    }
+}
@@ -0,0 +1,56 @@
+//for watching the res/ directory and reloading assets when they change (native + hot-reload feature only)
+
+use std::path::Path;
+use std::sync::mpsc;
+
+use notify::Watcher;
+
+//the kinds of files that make up a model - changes to anything else in res/ are ignored
+const WATCHED_EXTENSIONS: [&str; 6] = ["obj", "mtl", "png", "jpg", "jpeg", "tiff"];
+
+//watches a directory for changes to model/texture files
+pub struct AssetWatcher {
+    //has to be kept alive, as the directory stops being watched when it is dropped
+    _watcher: notify::RecommendedWatcher,
+    //the watcher sends its events here from its own thread
+    receiver: mpsc::Receiver<notify::Result<notify::Event>>,
+}
+
+impl AssetWatcher {
+    pub fn new(dir: &Path) -> anyhow::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher: notify::RecommendedWatcher = notify::recommended_watcher(sender)?;
+        watcher.watch(dir, notify::RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    //whether any assets have been created or modified since this was last called - never blocks
+    pub fn changed(&self) -> bool {
+        //editors often write a file in multiple steps, so drain everything that has queued up and only report one change
+        let mut changed: bool = false;
+        for event in self.receiver.try_iter() {
+            match event {
+                Ok(event) => {
+                    if (event.kind.is_create() || event.kind.is_modify())
+                        && event.paths.iter().any(|path| is_asset(path))
+                    {
+                        changed = true;
+                    }
+                }
+                Err(e) => log::warn!("error watching assets: {e}"),
+            }
+        }
+        changed
+    }
+}
+
+fn is_asset(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| WATCHED_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
@@ -0,0 +1,113 @@
+//helpers for laying out instances, to pass to State::set_instances
+
+use cgmath::{InnerSpace, Rotation3, Zero};
+use rand::{Rng, SeedableRng};
+
+use crate::Instance;
+
+//rows * cols instances spaced out on the ground (along z and x), centred around the origin and each tilted 45 degrees away from it - the scene run() shows is grid(10, 10, 3.0)
+pub fn grid(rows: u32, cols: u32, spacing: f32) -> Vec<Instance> {
+    (0..rows)
+        .flat_map(|z| {
+            (0..cols).map(move |x| {
+                let position: cgmath::Vector3<f32> = cgmath::Vector3 {
+                    x: spacing * (x as f32 - cols as f32 / 2.0),
+                    y: 0.0,
+                    z: spacing * (z as f32 - rows as f32 / 2.0),
+                };
+
+                let rotation: cgmath::Quaternion<f32> = if position.is_zero() {
+                    //this is needed so an object at (0, 0, 0) won't get scaled to zero as Quaternions can effect scale if they're not created correctly
+                    cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_z(), cgmath::Deg(0.0))
+                } else {
+                    cgmath::Quaternion::from_axis_angle(position.normalize(), cgmath::Deg(45.0))
+                };
+
+                Instance {
+                    position,
+                    rotation,
+                    scale: cgmath::Vector3::new(1.0, 1.0, 1.0),
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+}
+
+//count instances scattered through a cube (2 * extent wide) around the origin, each turned and scaled differently - the same seed always gives the same instances, for repeatable stress tests and benchmarks
+//there's no random colour, as an Instance has no colour of its own - the shaders colour every instance from the model's materials, and per-instance colour would need another vertex attribute (and a change to the culling and draw_model_at paths) just for this
+pub fn random(count: u32, seed: u64, extent: f32) -> Vec<Instance> {
+    //StdRng's algorithm can change between rand versions, but Cargo.lock pins it - so a seed gives the same scene from build to build
+    let mut rng: rand::rngs::StdRng = rand::rngs::StdRng::seed_from_u64(seed);
+
+    (0..count)
+        .map(|_| {
+            let position: cgmath::Vector3<f32> = random_vector(&mut rng) * extent;
+
+            //a point in a cube could be right at the middle, which has no direction to rotate around
+            let axis: cgmath::Vector3<f32> = random_vector(&mut rng);
+            let axis: cgmath::Vector3<f32> = if axis.magnitude2() > 1e-6 {
+                axis.normalize()
+            } else {
+                cgmath::Vector3::unit_y()
+            };
+            let rotation: cgmath::Quaternion<f32> =
+                cgmath::Quaternion::from_axis_angle(axis, cgmath::Deg(rng.gen_range(0.0..360.0)));
+
+            //the same on every axis, so the instances aren't stretched
+            let scale: f32 = rng.gen_range(0.5..1.5);
+
+            Instance {
+                position,
+                rotation,
+                scale: cgmath::Vector3::new(scale, scale, scale),
+            }
+        })
+        .collect()
+}
+
+//a point in the cube from -1 to 1 on every axis
+fn random_vector(rng: &mut impl Rng) -> cgmath::Vector3<f32> {
+    cgmath::Vector3::new(
+        rng.gen_range(-1.0..=1.0),
+        rng.gen_range(-1.0..=1.0),
+        rng.gen_range(-1.0..=1.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_keeps_the_centre_instance_unscaled() {
+        let instances: Vec<Instance> = grid(2, 4, 3.0);
+        assert_eq!(instances.len(), 8);
+        assert_eq!(instances[0].position, cgmath::Vector3::new(-6.0, 0.0, -3.0));
+
+        //the instance at the origin would get a NaN rotation from normalizing a zero vector
+        let centre: &Instance = instances
+            .iter()
+            .find(|instance| instance.position.is_zero())
+            .unwrap();
+        assert!((centre.rotation.magnitude() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn random_instances_only_depend_on_the_seed() {
+        let positions = |seed: u64| -> Vec<cgmath::Vector3<f32>> {
+            random(50, seed, 10.0)
+                .iter()
+                .map(|instance| instance.position)
+                .collect()
+        };
+        assert_eq!(positions(7).len(), 50);
+        assert_eq!(positions(7), positions(7));
+        assert_ne!(positions(7), positions(8));
+        assert!(positions(7).iter().all(|position| position
+            .x
+            .abs()
+            .max(position.y.abs())
+            .max(position.z.abs())
+            <= 10.0));
+    }
+}
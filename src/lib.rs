@@ -1,10 +1,39 @@
 //for now, before everything is implimented, we will allow unused/dead code to exist without warnings
 #![allow(dead_code)]
 
+mod animation;
+mod bounds;
+mod buffer_pool;
+mod builder;
 mod camera;
+mod culling;
+mod dds;
+mod debug_lines;
+mod debug_view;
+mod frame_stats;
+mod fullscreen;
+mod fxaa;
+mod gpu_timer;
+mod grid;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+mod hot_reload;
+pub mod instances;
 mod model;
+mod outline;
+mod particles;
+mod pipeline_cache;
+mod post_process;
+mod render_stats;
+mod render_target;
 mod resources;
+mod scene;
+mod skin;
+mod skybox;
+mod sprites;
+mod sun;
+mod text;
 mod texture;
+mod viewport;
 
 use wgpu::util::DeviceExt;
 
@@ -18,6 +47,26 @@ use cgmath::prelude::*;
 
 use model::Vertex;
 
+pub use animation::{Animation, Channel, Track};
+pub use bounds::Aabb;
+pub use builder::EngineBuilder;
+pub use camera::{Camera, CameraMode, CameraState, FovAxis, ScrollMode};
+pub use debug_view::DebugView;
+pub use frame_stats::FrameStats;
+pub use fxaa::AaMode;
+pub use gpu_timer::GpuTimes;
+pub use model::{ShadingMode, ShadingModel};
+pub use particles::ParticleEmitter;
+pub use post_process::PostEffect;
+pub use render_stats::RenderStats;
+pub use render_target::RenderTarget;
+pub use scene::Node;
+pub use texture::{ColorSpace, SamplerOptions, Texture};
+pub use viewport::Viewport;
+
+#[cfg(target_arch = "wasm32")]
+pub use resources::set_asset_base;
+
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
@@ -30,15 +79,49 @@ macro_rules! log {
     }
 }
 
+//the model that gets loaded into the scene (from res/)
+const OBJ_MODEL_FILE: &str = "cube.obj";
+
+//how many frames in a row can fail to get the surface's texture before run() re-creates the surface (see State::recover)
+const MAX_SURFACE_ERRORS: u32 = 3;
+
 //wgsl doesn't have a representation for quarterons, so we convert the instance into just a matrix
 #[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct InstanceRaw {
     model: [[f32; 4]; 4],
     normal: [[f32; 3]; 3],
 }
 
+//the matrix normals are transformed by - the inverse-transpose of the model's rotation/scale, otherwise they stop being perpendicular to the surface when the scale isn't uniform
+//a scale of 0 on an axis can't be inverted, so the cofactor matrix (the inverse-transpose without dividing by the determinant) is used instead - it points the normals of something squashed flat along the flattened axis, and the shader normalizes them anyway
+fn normal_matrix(model: cgmath::Matrix4<f32>) -> cgmath::Matrix3<f32> {
+    let (x, y, z) = (model.x.truncate(), model.y.truncate(), model.z.truncate());
+    let linear: cgmath::Matrix3<f32> = cgmath::Matrix3::from_cols(x, y, z);
+    linear
+        .invert()
+        .map(|inverse| inverse.transpose())
+        .unwrap_or_else(|| {
+            let cofactor: cgmath::Matrix3<f32> =
+                cgmath::Matrix3::from_cols(y.cross(z), z.cross(x), x.cross(y));
+            //squashed down to a line or a point, there's no direction left for normals to point in
+            if cofactor.x.magnitude2() + cofactor.y.magnitude2() + cofactor.z.magnitude2() > 0.0 {
+                cofactor
+            } else {
+                cgmath::Matrix3::identity()
+            }
+        })
+}
+
 impl InstanceRaw {
+    //for when we only have a model matrix (e.g. from the scene graph) rather than an Instance
+    fn from_matrix(model: cgmath::Matrix4<f32>) -> Self {
+        Self {
+            model: model.into(),
+            normal: normal_matrix(model).into(),
+        }
+    }
+
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         use std::mem;
         wgpu::VertexBufferLayout {
@@ -90,22 +173,23 @@ impl InstanceRaw {
 }
 
 //allows us to draw the same object multiple times with different properties
-struct Instance {
-    position: cgmath::Vector3<f32>,
+pub struct Instance {
+    pub position: cgmath::Vector3<f32>,
     //really very complicated black box, but is a mathematical structure often used to represent rotation
     //[TODO] read https://mathworld.wolfram.com/Quaternion.html to try and vaguely understand what this is doing
-    rotation: cgmath::Quaternion<f32>,
+    pub rotation: cgmath::Quaternion<f32>,
+    //how much bigger (or smaller) the instance is along each axis
+    pub scale: cgmath::Vector3<f32>,
 }
 
 impl Instance {
     //convert to a wgsl interpretable InstanceRaw
     fn to_raw(&self) -> InstanceRaw {
-        let model: cgmath::Matrix4<f32> =
-            cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation);
-        InstanceRaw {
-            model: model.into(),
-            normal: cgmath::Matrix3::from(self.rotation).into(),
-        }
+        InstanceRaw::from_matrix(
+            cgmath::Matrix4::from_translation(self.position)
+                * cgmath::Matrix4::from(self.rotation)
+                * cgmath::Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z),
+        )
     }
 }
 
@@ -119,6 +203,9 @@ struct CameraUniform {
     view_position: [f32; 4],
     //we can't use cgmath with bytemuck directly so we'll have to convert the Matrix4 into a 4x4 f32 array
     view_proj: [[f32; 4]; 4],
+    //the inverse projection and view matrices - used to turn a point on the screen back into a direction in the world (for the skybox)
+    inv_proj: [[f32; 4]; 4],
+    inv_view: [[f32; 4]; 4],
 }
 
 impl CameraUniform {
@@ -126,25 +213,155 @@ impl CameraUniform {
         Self {
             view_position: [0.0; 4],
             view_proj: cgmath::Matrix4::identity().into(),
+            inv_proj: cgmath::Matrix4::identity().into(),
+            inv_view: cgmath::Matrix4::identity().into(),
         }
     }
 
     //convert a Camera into a CameraUniform so it can be used in a uniform buffer
     fn update_view_proj(&mut self, camera: &camera::Camera, projection: &camera::Projection) {
+        let view: cgmath::Matrix4<f32> = camera.calc_matrix();
+        let proj: cgmath::Matrix4<f32> = projection.calc_matrix();
         self.view_position = camera.position.to_homogeneous().into();
-        self.view_proj = (projection.calc_matrix() * camera.calc_matrix()).into();
+        self.view_proj = (proj * view).into();
+        //both are always invertible (as long as the projection has a sensible fov and znear/zfar), but identity is a safe fallback
+        self.inv_proj = proj.invert().unwrap_or(cgmath::Matrix4::identity()).into();
+        self.inv_view = view.invert().unwrap_or(cgmath::Matrix4::identity()).into();
     }
 }
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct LightUniform {
+    //the direction towards the light instead, when it is directional
     position: [f32; 3],
-    //due to uniforms requiring 16 byte (4 float) spacing, we need to use a padding field here
-    _padding: u32,
+    //1 if the light is infinitely far away and shines the same way everywhere, like the sun (a bool, but bools can't be put in uniforms) - fills the rest of position's 16 bytes
+    directional: u32,
     color: [f32; 3],
-    //we need to use a padding field here too
+    //a vec3 is aligned to 16 bytes in a uniform, but only 12 of them are used - fills the rest of color's 16 bytes so ambient starts on the next 16 byte boundary
     _padding2: u32,
+    //the light every surface gets regardless of where the light is, so faces pointing away from it aren't pure black
+    ambient: [f32; 3],
+    //fills the rest of ambient's 16 bytes, so no padding is needed
+    ambient_strength: f32,
+    //how many of the spotlights below are in use
+    spotlight_count: u32,
+    //how big the light's marker is drawn (see State::set_light_marker_size)
+    marker_size: f32,
+    //fills the rest of spotlight_count and marker_size's 16 bytes, as the spotlights (structs) have to start on a 16 byte boundary
+    _padding3: [u32; 2],
+    //uniforms can't be resized, so there is a fixed number of spotlights
+    spotlights: [SpotLightUniform; MAX_SPOTLIGHTS],
+}
+
+//the most spotlights that can light the scene at once
+const MAX_SPOTLIGHTS: usize = 4;
+
+//the size of each buffer in the instance staging belt (see State::set_dynamic_instances) - enough for hundreds of instances, bigger uploads get a buffer of their own size
+const INSTANCE_BELT_CHUNK_SIZE: wgpu::BufferAddress = 64 * 1024;
+
+//a light shining in one direction, in a cone that fades out between its inner and outer angles
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpotLightUniform {
+    position: [f32; 3],
+    //the cosine of the inner cone angle - everything inside it is fully lit
+    inner_cos: f32,
+    //which way the light is pointing (normalized)
+    direction: [f32; 3],
+    //the cosine of the outer cone angle - everything outside it is unlit
+    outer_cos: f32,
+    color: [f32; 3],
+    //how far (in world units) the light reaches - it fades out smoothly on the way there - fills the rest of color's 16 bytes
+    range: f32,
+}
+
+//the settings that differ between the pipelines made by create_render_pipeline - the default is opaque triangles that write to the depth buffer
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct PipelineOptions {
+    topology: wgpu::PrimitiveTopology,
+    blend: wgpu::BlendState,
+    //see-through things are depth tested but don't write depth, so whatever is behind them can still be drawn afterwards
+    depth_write_enabled: bool,
+    //which side of triangles isn't drawn - None draws both sides
+    cull_mode: Option<wgpu::Face>,
+    //Less, unless reverse-z is on (see texture::Texture::depth_compare)
+    depth_compare: wgpu::CompareFunction,
+    //which functions in the shader place the vertices and draw the pixels
+    vertex_entry_point: &'static str,
+    fragment_entry_point: &'static str,
+    //pushes the depth of what is drawn back, so coplanar surfaces (like decals) don't flicker through each other - only works with triangles
+    depth_bias: wgpu::DepthBiasState,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        Self {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            blend: wgpu::BlendState::REPLACE,
+            depth_write_enabled: true,
+            cull_mode: Some(wgpu::Face::Back),
+            depth_compare: wgpu::CompareFunction::Less,
+            vertex_entry_point: "vs_main",
+            fragment_entry_point: "fs_main",
+            depth_bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+//the options are a key of the pipeline cache - the depth bias' floats are compared by their bits, which is fine as they are never NaN
+impl Eq for PipelineOptions {}
+
+impl std::hash::Hash for PipelineOptions {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.topology.hash(state);
+        self.blend.hash(state);
+        self.depth_write_enabled.hash(state);
+        self.cull_mode.hash(state);
+        self.depth_compare.hash(state);
+        self.vertex_entry_point.hash(state);
+        self.fragment_entry_point.hash(state);
+        self.depth_bias.constant.hash(state);
+        self.depth_bias.slope_scale.to_bits().hash(state);
+        self.depth_bias.clamp.to_bits().hash(state);
+    }
+}
+
+//the requested present mode if the surface supports it, otherwise the nearest one to it that it does (with a warning)
+fn closest_present_mode(
+    requested: wgpu::PresentMode,
+    supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+    use wgpu::PresentMode;
+
+    //the Auto modes are always supported, as wgpu picks a supported mode for them itself
+    if matches!(requested, PresentMode::AutoVsync | PresentMode::AutoNoVsync)
+        || supported.contains(&requested)
+    {
+        return requested;
+    }
+
+    //stay on the same side of vsync as what was asked for - Fifo (and so AutoVsync) is supported everywhere
+    let fallback: PresentMode = match requested {
+        PresentMode::Immediate if supported.contains(&PresentMode::Mailbox) => PresentMode::Mailbox,
+        PresentMode::Mailbox if supported.contains(&PresentMode::Immediate) => {
+            PresentMode::Immediate
+        }
+        PresentMode::Immediate | PresentMode::Mailbox => PresentMode::AutoNoVsync,
+        _ => PresentMode::AutoVsync,
+    };
+    log::warn!("present mode {requested:?} isn't supported, using {fallback:?} instead");
+    fallback
+}
+
+//the instance buffer is only read by the culling compute shader when gpu culling is on - storage buffers aren't available everywhere (like webgl)
+fn instance_buffer_usage(gpu_culling: bool) -> wgpu::BufferUsages {
+    let usage: wgpu::BufferUsages = wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST;
+    if gpu_culling {
+        usage | wgpu::BufferUsages::STORAGE
+    } else {
+        usage
+    }
 }
 
 fn create_render_pipeline(
@@ -153,48 +370,78 @@ fn create_render_pipeline(
     color_format: wgpu::TextureFormat,
     depth_format: Option<wgpu::TextureFormat>,
     vertex_layouts: &[wgpu::VertexBufferLayout],
+    options: PipelineOptions,
     shader: wgpu::ShaderModuleDescriptor,
-) -> wgpu::RenderPipeline {
+) -> anyhow::Result<wgpu::RenderPipeline> {
+    let label: String = shader.label.unwrap_or("unnamed shader").to_string();
+
+    //without an error scope, a shader that doesn't compile (or a pipeline that doesn't match it) makes wgpu panic with an opaque device error
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
     //creates a shader from our shader file (in this case, shader.wgsl)
     let shader: wgpu::ShaderModule = device.create_shader_module(shader);
 
+    let pipeline: wgpu::RenderPipeline = build_render_pipeline(
+        device,
+        layout,
+        color_format,
+        depth_format,
+        vertex_layouts,
+        options,
+        &shader,
+    );
+
+    //errors are reported asynchronously, but native and webgl devices have already found them by the time the scope is popped, so this never actually waits
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        anyhow::bail!("couldn't create a pipeline from {label}: {error}");
+    }
+    Ok(pipeline)
+}
+
+//create_render_pipeline with an already compiled shader (see pipeline_cache) - errors aren't caught, so has to be called inside an error scope
+fn build_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    depth_format: Option<wgpu::TextureFormat>,
+    vertex_layouts: &[wgpu::VertexBufferLayout],
+    options: PipelineOptions,
+    shader: &wgpu::ShaderModule,
+) -> wgpu::RenderPipeline {
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some("Render Pipeline"),
         layout: Some(layout),
         vertex: wgpu::VertexState {
-            module: &shader,
+            module: shader,
             //specifies which shader function should be our entrypoint
-            entry_point: "vs_main",
+            entry_point: options.vertex_entry_point,
             //the types of vertices we want to pass to the vertex shader
             buffers: vertex_layouts,
         },
         //technically optional, so has to be wrapped in a Some enum
         fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: "fs_main",
+            module: shader,
+            entry_point: options.fragment_entry_point,
             //for now, only need one for surface
             targets: &[Some(wgpu::ColorTargetState {
                 format: color_format,
-                //for now, blending should just replace old pixel data with new pixel data
-                blend: Some(wgpu::BlendState {
-                    alpha: wgpu::BlendComponent::REPLACE,
-                    color: wgpu::BlendComponent::REPLACE,
-                }),
+                //usually REPLACE, which just replaces old pixel data with new pixel data - ALPHA_BLENDING mixes see-through pixels with what's behind them
+                blend: Some(options.blend),
                 //for now, we write to all colours (rgba)
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
         //how to interpret converting vertices to triangles
         primitive: wgpu::PrimitiveState {
-            //every 3 vertices corrisponds to one triange - no overlapping triangles or lines ect
-            topology: wgpu::PrimitiveTopology::TriangleList,
+            //usually TriangleList - every 3 vertices corrisponds to one triange, no overlapping triangles or lines ect
+            topology: options.topology,
             //doesn't apply
             strip_index_format: None,
             //front_face + cull_face - tells wgpu how to decide whether a triangle is facing forwards or not
             //dictates a right-handed coordinates system (which we will use for now)
             front_face: wgpu::FrontFace::Ccw,
-            //the back of a trianges face will not be included in the render
-            cull_mode: Some(wgpu::Face::Back),
+            //usually the back of a trianges face will not be included in the render
+            cull_mode: options.cull_mode,
             //setting this to anything other than fill requires Features::NON_FILL_POLYGON_MODE
             polygon_mode: wgpu::PolygonMode::Fill,
             //requires Features::DEPTH_CLIP_CONTROL
@@ -205,12 +452,12 @@ fn create_render_pipeline(
         //how depth is rendered (so elements are properly on top of one another)
         depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
             format,
-            depth_write_enabled: true,
+            depth_write_enabled: options.depth_write_enabled,
             //pixels will be drawn from front to back
-            depth_compare: wgpu::CompareFunction::Less,
+            depth_compare: options.depth_compare,
             //will be used later, so for now is just default
             stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
+            bias: options.depth_bias,
         }),
         //[TODO] learn what multisampling is and add comments for it
         multisample: wgpu::MultisampleState {
@@ -225,56 +472,265 @@ fn create_render_pipeline(
     })
 }
 
+//the names the main and light pipelines' shaders and layouts are added to the pipeline cache with
+const MAIN_SHADER: &str = "main";
+const MAIN_LAYOUT: &str = "main";
+const LIGHT_SHADER: &str = "light";
+//the main shader and layout for models drawn at a transform rather than at instances (see State::draw_model_at) - only added if the device has push constants
+const MAIN_AT_SHADER: &str = "main_at";
+const MAIN_AT_LAYOUT: &str = "main_at";
+const LIGHT_LAYOUT: &str = "light";
+
+//the fragment shader in shader.wgsl that draws normals as colours, skipping lighting (see DebugView::Normals)
+const NORMALS_ENTRY_POINT: &str = "fs_normals";
+
+//the key of the main pipeline for a kind of material (see-through and/or double-sided), fragment shader and topology
+fn main_pipeline_key(
+    pass: model::MaterialPass,
+    fragment_entry_point: &'static str,
+    topology: wgpu::PrimitiveTopology,
+    scene_format: wgpu::TextureFormat,
+    reverse_z: bool,
+    depth_bias: wgpu::DepthBiasState,
+) -> pipeline_cache::PipelineKey {
+    //with reverse-z the far plane is at 0, so the bias has to go the other way to push things back
+    let depth_bias: wgpu::DepthBiasState = if reverse_z {
+        wgpu::DepthBiasState {
+            constant: -depth_bias.constant,
+            slope_scale: -depth_bias.slope_scale,
+            clamp: -depth_bias.clamp,
+        }
+    } else {
+        depth_bias
+    };
+
+    //see-through materials are blended with what's behind them, and double-sided ones don't have their backs culled
+    pipeline_cache::PipelineKey {
+        shader: MAIN_SHADER,
+        layout: MAIN_LAYOUT,
+        color_format: scene_format,
+        depth_format: Some(texture::Texture::DEPTH_FORMAT),
+        options: PipelineOptions {
+            topology,
+            blend: if pass.transparent {
+                wgpu::BlendState::ALPHA_BLENDING
+            } else {
+                wgpu::BlendState::REPLACE
+            },
+            depth_write_enabled: !pass.transparent,
+            cull_mode: if pass.double_sided {
+                None
+            } else {
+                Some(wgpu::Face::Back)
+            },
+            depth_compare: texture::Texture::depth_compare(reverse_z),
+            vertex_entry_point: "vs_main",
+            fragment_entry_point,
+            depth_bias: if topology == wgpu::PrimitiveTopology::TriangleList {
+                depth_bias
+            } else {
+                wgpu::DepthBiasState::default()
+            },
+        },
+    }
+}
+
+//the same as the main pipeline for the key, but placing the model with a transform in push constants instead of instances
+fn main_at_pipeline_key(key: pipeline_cache::PipelineKey) -> pipeline_cache::PipelineKey {
+    pipeline_cache::PipelineKey {
+        shader: MAIN_AT_SHADER,
+        layout: MAIN_AT_LAYOUT,
+        options: PipelineOptions {
+            vertex_entry_point: "vs_at",
+            ..key.options
+        },
+        ..key
+    }
+}
+
+//the same as the key's pipeline, but ignoring the depth buffer - so what it draws is always on top of the scene (for gizmos ect)
+fn overlay_pipeline_key(key: pipeline_cache::PipelineKey) -> pipeline_cache::PipelineKey {
+    pipeline_cache::PipelineKey {
+        options: PipelineOptions {
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            ..key.options
+        },
+        ..key
+    }
+}
+
+fn light_pipeline_key(
+    scene_format: wgpu::TextureFormat,
+    reverse_z: bool,
+) -> pipeline_cache::PipelineKey {
+    pipeline_cache::PipelineKey {
+        shader: LIGHT_SHADER,
+        layout: LIGHT_LAYOUT,
+        color_format: scene_format,
+        depth_format: Some(texture::Texture::DEPTH_FORMAT),
+        options: PipelineOptions {
+            depth_compare: texture::Texture::depth_compare(reverse_z),
+            ..Default::default()
+        },
+    }
+}
+
 //the state of the everything related to the program - the window, device, buffers, textures, models, ect
-struct State {
+pub struct State {
+    //the handle to the graphics api - kept so the surface can be re-created (see recover)
+    instance: wgpu::Instance,
     //the part of the window that we actually draw to
     surface: wgpu::Surface,
     //connection to the graphics/compute device
     device: wgpu::Device,
+    //the name, type, backend and driver of the gpu the device is on
+    adapter_info: wgpu::AdapterInfo,
+    //the optional features (compute shaders ect) the gpu supports beyond webgl
+    downlevel_flags: wgpu::DownlevelFlags,
+    //the limits the device was actually created with (kept so limits() can hand out a reference)
+    limits: wgpu::Limits,
     //the command queue for the device
     queue: wgpu::Queue,
     //defines how our surface will create the underlying SurfaceTextures
     config: wgpu::SurfaceConfiguration,
+    //the format the scene is rendered in - the surface's format, or a float format when rendering in hdr
+    scene_format: wgpu::TextureFormat,
     //size of our window
     size: winit::dpi::PhysicalSize<u32>,
     //describes the actions our gpu will perform when acting on a set of data (like a set of verticies)
-    render_pipeline: wgpu::RenderPipeline,
+    //the main and light pipelines - every variant of the main pipeline shares one compiled shader and layout
+    pipelines: pipeline_cache::PipelineCache,
+    depth_bias: wgpu::DepthBiasState,
+    //whether the scene is drawn with smooth or flat normals
+    shading: ShadingMode,
     //our imported model
     obj_model: model::Model,
+    //how a model's textures are laid out for the shader - kept around so models can be (re)loaded after startup
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    //how our model's .obj file is read - kept around for reloading it
+    obj_load_options: tobj::LoadOptions,
+    //reloads the model whenever its files in res/ change
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    asset_watcher: hot_reload::AssetWatcher,
     //a view into our scene that can move and look around
     camera: camera::Camera,
     //a set of settings relating to how the camera looks and percieves the scene
     projection: camera::Projection,
-    //how the camera is controlled
+    //cameras beyond the main one, for viewports to draw from - camera index 1 is cameras[0]
+    cameras: Vec<camera::Camera>,
+    //the parts of the screen the scene is drawn into - empty draws it once, from the main camera, over the whole screen
+    viewports: Vec<viewport::ViewportView>,
+    //which controller is currently driving the camera
+    camera_mode: camera::CameraMode,
+    //how the camera is controlled when flying freely around the scene
     camera_controller: camera::CameraController,
+    //how the camera is controlled when orbiting around a target
+    orbit_controller: camera::OrbitController,
     //whether the mouse is pressed or not (both scroll wheel and buttons)
     mouse_pressed: bool,
+    //which mouse button has to be held to rotate the camera
+    rotate_button: MouseButton,
+    //whether the middle mouse button is held, which drags the camera around (unless it is the rotate button)
+    pan_pressed: bool,
     //the camera matrix data for use in the buffer
     camera_uniform: CameraUniform,
     //to store the matrix data associated with the camera
     camera_buffer: wgpu::Buffer,
     //describes how the camera can be accessed by the shader
     camera_bind_group: wgpu::BindGroup,
+    //how the camera is laid out for the shader - kept around for pipelines created after startup (like the skybox)
+    camera_bind_group_layout: wgpu::BindGroupLayout,
 
     //the list of our instances
     instances: Vec<Instance>,
     //to store the model and matrix data associated with our instances
     instance_buffer: wgpu::Buffer,
+    //how many instances are in the instance buffer
+    num_instances: u32,
+    //where our model is drawn this frame on top of its instances, and whether it is drawn over everything else (see draw_model_at)
+    model_transforms: Vec<(cgmath::Matrix4<f32>, bool)>,
+    //uploads the instances through re-used mapped buffers instead of queue.write_buffer - None unless turned on with set_dynamic_instances
+    instance_belt: Option<wgpu::util::StagingBelt>,
+    //buffers that have been outgrown, kept around to be re-used instead of allocating new ones
+    buffer_pool: buffer_pool::BufferPool,
+    //culls our instances against the camera on the gpu before they are drawn - None if it is off (see EngineBuilder::with_gpu_culling) or unsupported
+    culler: Option<culling::InstanceCuller>,
+    //particles moved by a compute shader - created the first time some are spawned
+    particles: Option<particles::ParticleSystem>,
+    //if set, our model is drawn wherever this scene graph says instead of at our instances
+    scene: Option<scene::Node>,
+    //the index (into our model's animations) of the animation being played on the scene graph, and how far (in seconds) into it we are
+    active_animation: Option<(usize, f32)>,
     //how depth is percieved by the renderer
     depth_texture: texture::Texture,
     //the position and colour of light data
     light_uniform: LightUniform,
+    //while set, run() stops calling update() so the last frame is drawn unchanged (toggled with P)
+    paused: bool,
+    //draws only the first instance, without instancing, to tell apart bugs in the instance buffer from ones in the model itself
+    single_instance: bool,
+    //whether the free-fly camera collides with the instances (see set_camera_collision)
+    camera_collision: bool,
+    //what the light is shown as - a small sphere unless another model has been loaded with load_light_marker
+    light_marker: model::Model,
+    light_marker_visible: bool,
+    //whether update() spins the light around the y axis
+    light_orbit: bool,
+    //how fast the light spins when it is orbiting
+    light_orbit_speed: cgmath::Deg<f32>,
+    //where the sun is, while the light is following the time of day (see set_time_of_day)
+    time_of_day: Option<f32>,
+    //if set, update() always steps by this much instead of by the real time since the last frame - see EngineBuilder::with_fixed_timestep
+    fixed_timestep: Option<instant::Duration>,
     //to store the
     light_buffer: wgpu::Buffer,
     //describes how our light should be accessed by the shader
     light_bind_group: wgpu::BindGroup,
-    //describes the actions our gpu will perform to render our light into our scene
-    light_render_pipeline: wgpu::RenderPipeline,
+    //where our model's skin joints currently are, for the vertex shader to move skinned vertices with
+    joint_palette: skin::JointPalette,
+    //full screen effects applied after the scene is rendered
+    post_process: post_process::PostProcess,
+    //smooths the edges in the final image, after post-processing - None unless it has been turned on with set_aa
+    fxaa: Option<fxaa::Fxaa>,
+    //the environment drawn behind the scene - just the clear colour if there isn't one
+    skybox: Option<skybox::Skybox>,
+    //a grid on the ground for getting your bearings - only drawn if grid_visible is set
+    grid: grid::Grid,
+    grid_visible: bool,
+    //the outline drawn around the selected instance
+    outline: outline::Outline,
+    //the index of the instance that gets outlined, if any
+    selected: Option<usize>,
+    //lines added with draw_line/draw_aabb, drawn (and then forgotten) at the end of the frame
+    debug_lines: debug_lines::DebugLines,
+    //what gets shown on the screen - the scene, or one of the renderer's buffers
+    debug_view: debug_view::DebugView,
+    //for drawing the depth buffer to the screen
+    depth_view: debug_view::DepthView,
+    //2d text drawn over everything else at the end of the frame
+    text_overlay: text::TextOverlay,
+    //2d quads drawn over the scene, under the text (see draw_sprite)
+    sprite_overlay: sprites::SpriteOverlay,
+    //the colour drawn behind the scene (when there is no skybox)
+    clear_color: wgpu::Color,
+    //measures how long the gpu spends on each part of the scene - None if gpu timing is off or unsupported
+    gpu_timer: Option<gpu_timer::GpuTimer>,
+    //how long each recent frame took, for spotting stutter (see frame_stats)
+    frame_timer: frame_stats::FrameTimer,
+    //what the last frame drew - counted while its passes are recorded, which only has &self
+    render_stats: std::cell::Cell<RenderStats>,
 }
 
 impl State {
     // creating some of the wgpu types requires async code
-    async fn new(window: &Window) -> Self {
+    //use EngineBuilder to choose the options
+    async fn new(window: &Window, options: EngineBuilder) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let hdr: bool = options.hdr;
+        let reverse_z: bool = options.reverse_z;
+
         //find the safe size of the current window
         let size: winit::dpi::PhysicalSize<u32> = window.inner_size();
 
@@ -291,34 +747,99 @@ impl State {
             //should work for most devices,
             .request_adapter(&wgpu::RequestAdapterOptions {
                 //can be LowPower or HighPower - LowPower will try and use an adapter that favours battery life, HighPower will target a more power consuming but higher performance gpu
-                //[TODO] allow the user to choose a performance mode
-                power_preference: wgpu::PowerPreference::default(),
+                //chosen with EngineBuilder::with_power_preference
+                power_preference: options.power_preference,
                 compatible_surface: Some(&surface),
                 //will force wgpu to use an adapter that works on all hardware, rendering with software on the cpu instead of using dedicated graphics processing renderers
                 force_fallback_adapter: false,
             })
             .await
-            .unwrap();
+            .ok_or_else(|| {
+                anyhow::anyhow!("couldn't find a gpu adapter compatible with the window")
+            })?;
+
+        //which gpu (and graphics api) we ended up on - the first thing to know when a rendering bug is reported (shown with RUST_LOG=info)
+        let adapter_info: wgpu::AdapterInfo = adapter.get_info();
+        let downlevel_flags: wgpu::DownlevelFlags = adapter.get_downlevel_capabilities().flags;
+        log::info!(
+            "using {} ({:?}, {:?} backend, driver: {} {})",
+            adapter_info.name,
+            adapter_info.device_type,
+            adapter_info.backend,
+            adapter_info.driver,
+            adapter_info.driver_info
+        );
+
+        //any features the user asked for that the adapter doesn't have would make request_device panic, so we report them all up front instead
+        let missing_features: wgpu::Features = options.features - adapter.features();
+        anyhow::ensure!(
+            missing_features.is_empty(),
+            "the gpu ({}) doesn't support the requested features: {:?}",
+            adapter.get_info().name,
+            missing_features
+        );
 
         // device: opens a connection to the graphics/compute device
         // queue: handles the command queue for the device
+        //wgpu can only record traces when it is built with its trace feature
+        let trace_path: Option<&std::path::Path> = if cfg!(feature = "trace") {
+            options.trace_dir.as_deref()
+        } else {
+            if options.trace_dir.is_some() {
+                log::warn!("a trace directory was given, but the trace feature isn't enabled - no trace will be recorded");
+            }
+            None
+        };
+
+        let gpu_timing_features: wgpu::Features = if options.gpu_timing {
+            gpu_timer::GpuTimer::FEATURES
+        } else {
+            wgpu::Features::empty()
+        };
+        if !adapter.features().contains(gpu_timing_features) {
+            log::warn!(
+                "the gpu doesn't support timestamp queries inside passes - gpu timing is disabled"
+            );
+        }
+
+        //push constants let models be drawn at a transform without an instance buffer (see State::draw_model_at) - only asked for if the adapter has them
+        let push_constants: bool = adapter.features().contains(wgpu::Features::PUSH_CONSTANTS)
+            && adapter.limits().max_push_constant_size >= model::ModelTransform::SIZE;
+        //WebGL doesn't support all of wgpu's features, so if we're building for the web we'll have to disable some of them (unless the user chose their own limits)
+        let mut limits: wgpu::Limits = options.limits.clone().unwrap_or_else(|| {
+            if cfg!(target_arch = "wasm32") {
+                wgpu::Limits::downlevel_webgl2_defaults()
+            } else {
+                wgpu::Limits::default()
+            }
+        });
+        if push_constants {
+            limits.max_push_constant_size = limits
+                .max_push_constant_size
+                .max(model::ModelTransform::SIZE);
+        }
+
         let (device, queue): (wgpu::Device, wgpu::Queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    //here we can choose extra features we want from wgpu (currently none) - not all gpus can support these extra features, so we would have to limit the allowed gpus
-                    features: wgpu::Features::empty(),
-                    //WebGL doesn't support all of wgpu's features, so if we're building for the web we'll have to disable some of them
-                    limits: if cfg!(target_arch = "wasm32") {
-                        wgpu::Limits::downlevel_webgl2_defaults()
-                    } else {
-                        wgpu::Limits::default()
-                    },
+                    //here we can choose extra features we want from wgpu - the ones the user asked for (checked above), plus any optional ones the adapter actually has
+                    //TEXTURE_COMPRESSION_BC lets us load compressed (.ktx2) textures, which use far less vram
+                    //the timestamp query features are only asked for if gpu timing is on, and only if the adapter has them
+                    features: options.features
+                        | (adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC)
+                        | (adapter.features() & gpu_timing_features)
+                        | if push_constants {
+                            wgpu::Features::PUSH_CONSTANTS
+                        } else {
+                            wgpu::Features::empty()
+                        },
+                    limits,
                     label: None,
                 },
-                None, //trace path
+                trace_path,
             )
             .await
-            .unwrap();
+            .context("couldn't create the gpu device")?;
 
         //defines how our surface will create the underlying SurfaceTextures
         let config: wgpu::SurfaceConfiguration = wgpu::SurfaceConfiguration {
@@ -327,16 +848,31 @@ impl State {
             //defines how the SurfaceTextures will be stored on our gpu - we will choose the best format based on what display is being used
             format: surface.get_supported_formats(&adapter)[0],
             //typically width and height are the size of the window
-            //[WARNING] if either width or height is 0, the program will crash
+            //a surface can't have a width or height of 0 (which some platforms report while minimized), so it is at least 1x1
             //[TODO] allow the user to choose a screen resolution
-            width: size.width,
-            height: size.height,
-            //essentially Vsync, and will cap the display rate to the display's frame rate - there are other options to choose from https://docs.rs/wgpu/latest/wgpu/enum.PresentMode.html
-            //[TODO] allow the user to choose what mode they want (probably between AutoNoVsync and AutoVsync)
-            present_mode: wgpu::PresentMode::AutoVsync,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            //AutoVsync by default, which caps the display rate to the display's frame rate - there are other options to choose from https://docs.rs/wgpu/latest/wgpu/enum.PresentMode.html
+            //configuring a mode the surface doesn't support panics, so the closest supported one is used instead
+            present_mode: closest_present_mode(
+                options.present_mode,
+                &surface.get_supported_present_modes(&adapter),
+            ),
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
         };
         surface.configure(&device, &config);
+        log::info!(
+            "surface format: {:?}, present mode: {:?}",
+            config.format,
+            config.present_mode
+        );
+
+        //the scene's pipelines draw in this format - when it isn't the surface's format, the post-processing pass converts it
+        let scene_format: wgpu::TextureFormat = if hdr {
+            texture::Texture::HDR_FORMAT
+        } else {
+            config.format
+        };
 
         //[TODO] really very black box
         //used to create a bind group with the specified config, so that bind groups can be swapped in and out (as long as they share the same BindGroupLayout)
@@ -383,6 +919,51 @@ impl State {
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                         count: None,
                     },
+                    //per-material settings (see model::Material)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    //metallic/roughness map (for pbr materials)
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    //ambient occlusion map
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 8,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
                 ],
             });
 
@@ -390,25 +971,38 @@ impl State {
         let depth_texture: texture::Texture =
             texture::Texture::create_depth_texture(&device, &config, "depth_texture");
 
-        let camera: camera::Camera = camera::Camera::new(
-            // position the camera one unit up and 2 units back - the +z coordinate is out of the screen (coord ranges are 1.0 to -1.0)
-            (0.0, 5.0, 10.0),
-            cgmath::Deg(-90.0),
-            cgmath::Deg(-20.0),
-        );
+        let camera: camera::Camera = options.camera.unwrap_or_else(|| {
+            camera::Camera::new(
+                // position the camera one unit up and 2 units back - the +z coordinate is out of the screen (coord ranges are 1.0 to -1.0)
+                (0.0, 5.0, 10.0),
+                cgmath::Deg(-90.0),
+                cgmath::Deg(-20.0),
+                cgmath::Deg(0.0),
+            )
+        });
 
-        let projection: camera::Projection = camera::Projection::new(
+        anyhow::ensure!(
+            options.znear > 0.0 && options.znear < options.zfar,
+            "znear must be above 0 and below zfar ({}), got {}",
+            options.zfar,
+            options.znear
+        );
+        let mut projection: camera::Projection = camera::Projection::new(
             config.width,
             config.height,
-            //a basic, random value
-            //[TODO] allow user to change in settings
-            cgmath::Deg(45.0),
-            0.1,
-            100.0,
+            options.fov,
+            options.znear,
+            options.zfar,
         );
+        //checks the fov is usable
+        projection.set_fov(options.fov)?;
+        projection.set_reverse_z(reverse_z);
 
         //how the camera is controlled
         let camera_controller: camera::CameraController = camera::CameraController::new(4.0, 0.4);
+        //orbits around the centre of the scene, at roughly the distance the camera starts at
+        let orbit_controller: camera::OrbitController =
+            camera::OrbitController::new((0.0, 0.0, 0.0), 10.0, 0.4);
 
         //convert our camera matrix into a CameraUniform
         let mut camera_uniform: CameraUniform = CameraUniform::new();
@@ -454,52 +1048,39 @@ impl State {
                 }],
             });
 
-        //how far away each model should be from one another
-        const SPACE_BETWEEN: f32 = 3.0;
-        //how many instances of our model are we going to display
-        const NUM_INSTANCES_PER_ROW: u32 = 10;
-        //define our instances (should be 100 pentagons in a 10x10 grid, each rotated based on an axis)
-        let instances: Vec<Instance> = (0..NUM_INSTANCES_PER_ROW)
-            .flat_map(|z| {
-                (0..NUM_INSTANCES_PER_ROW).map(move |x| {
-                    let position: cgmath::Vector3<f32> = cgmath::Vector3 {
-                        x: SPACE_BETWEEN * (x as f32 - NUM_INSTANCES_PER_ROW as f32 / 2.0),
-                        y: 0.0,
-                        z: SPACE_BETWEEN * (z as f32 - NUM_INSTANCES_PER_ROW as f32 / 2.0),
-                    };
-
-                    let rotation: cgmath::Quaternion<f32> = if position.is_zero() {
-                        //this is needed so an object at (0, 0, 0) won't get scaled to zero as Quaternions can effect scale if they're not created correctly
-                        cgmath::Quaternion::from_axis_angle(
-                            cgmath::Vector3::unit_z(),
-                            cgmath::Deg(0.0),
-                        )
-                    } else {
-                        cgmath::Quaternion::from_axis_angle(position.normalize(), cgmath::Deg(45.0))
-                    };
-
-                    Instance { position, rotation }
-                })
-            })
-            .collect::<Vec<_>>();
+        //nothing is drawn until instances are given to set_instances (or a scene to set_scene)
+        let instances: Vec<Instance> = Vec::new();
 
-        //the instances created turned into InstanceRaw's so they can be interpreted by the shader
-        let instance_data: Vec<InstanceRaw> =
-            instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
+        //culling on the gpu needs compute shaders and indirect draws, which webgl doesn't have
+        let culler: Option<culling::InstanceCuller> = if !options.gpu_culling {
+            None
+        } else if downlevel_flags.contains(culling::InstanceCuller::DOWNLEVEL_FLAGS) {
+            Some(culling::InstanceCuller::new(&device)?)
+        } else {
+            log::warn!("the gpu doesn't support compute shaders and indirect draws - gpu culling is disabled");
+            None
+        };
 
-        //to store the model and matrix data associated with our instances
-        let instance_buffer: wgpu::Buffer =
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Instance Buffer"),
-                contents: bytemuck::cast_slice(&instance_data),
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            });
+        //to store the model and matrix data associated with our instances - big enough for one to start with (a buffer can't be bound with a size of 0), and grown by update_instance_buffer when more are added
+        let instance_buffer: wgpu::Buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            usage: instance_buffer_usage(culler.is_some()),
+            mapped_at_creation: false,
+        });
 
         let light_uniform: LightUniform = LightUniform {
             position: [2.0, 2.0, 2.0],
-            _padding: 0,
+            directional: 0,
             color: [1.0, 1.0, 1.0],
             _padding2: 0,
+            //we don't need (or want) much ambient light, so 0.1 is fine
+            ambient: [1.0, 1.0, 1.0],
+            ambient_strength: 0.1,
+            spotlight_count: 0,
+            marker_size: 0.25,
+            _padding3: [0; 2],
+            spotlights: [bytemuck::Zeroable::zeroed(); MAX_SPOTLIGHTS],
         };
 
         let light_buffer: wgpu::Buffer =
@@ -535,28 +1116,33 @@ impl State {
                 }],
             });
 
-        let light_render_pipeline: wgpu::RenderPipeline = {
-            let layout: wgpu::PipelineLayout =
-                device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("Light Pipeline Layout"),
-                    bind_group_layouts: &[&camera_bind_group_layout, &light_bind_group_layout],
-                    push_constant_ranges: &[],
-                });
-            //creates a shader from our shader file
-            let shader = wgpu::ShaderModuleDescriptor {
+        let mut pipelines: pipeline_cache::PipelineCache = pipeline_cache::PipelineCache::new();
+
+        pipelines.add_shader(
+            &device,
+            LIGHT_SHADER,
+            wgpu::ShaderModuleDescriptor {
                 label: Some("Light Shader"),
-                //the include_wgsl!() macro makes it so we don't have to write really dumb boilerplate code to create the shader
                 source: wgpu::ShaderSource::Wgsl(include_str!("shaders/light.wgsl").into()),
-            };
-            create_render_pipeline(
-                &device,
-                &layout,
-                config.format,
-                Some(texture::Texture::DEPTH_FORMAT),
-                &[model::ModelVertex::desc()],
-                shader,
-            )
-        };
+            },
+        )?;
+        pipelines.add_layout(
+            LIGHT_LAYOUT,
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Light Pipeline Layout"),
+                bind_group_layouts: &[&camera_bind_group_layout, &light_bind_group_layout],
+                push_constant_ranges: &[],
+            }),
+            vec![model::ModelVertex::desc()],
+        );
+        //describes the actions our gpu will perform to render our light into our scene
+        pipelines.get_or_create(&device, light_pipeline_key(scene_format, reverse_z))?;
+
+        //the matrices of the model's skin joints (if it has one)
+        let joint_bind_group_layout: wgpu::BindGroupLayout =
+            skin::JointPalette::bind_group_layout(&device);
+        let joint_palette: skin::JointPalette =
+            skin::JointPalette::new(&device, &joint_bind_group_layout);
 
         //setup for our rendering pipeline
         let render_pipeline_layout: wgpu::PipelineLayout =
@@ -567,229 +1153,1918 @@ impl State {
                     &texture_bind_group_layout,
                     &camera_bind_group_layout,
                     &light_bind_group_layout,
+                    &joint_bind_group_layout,
                 ],
                 push_constant_ranges: &[],
             });
 
-        //describes the actions our gpu will perform when acting on a set of data
-        let render_pipeline: wgpu::RenderPipeline = {
-            let shader = wgpu::ShaderModuleDescriptor {
+        //describes the actions our gpu will perform when acting on a set of data - one for each combination of settings a material can need, way of shading it, and topology
+        pipelines.add_shader(
+            &device,
+            MAIN_SHADER,
+            wgpu::ShaderModuleDescriptor {
                 label: Some("Normal Shader"),
                 source: wgpu::ShaderSource::Wgsl(include_str!("shaders/shader.wgsl").into()),
-            };
-            create_render_pipeline(
+            },
+        )?;
+        pipelines.add_layout(
+            MAIN_LAYOUT,
+            render_pipeline_layout,
+            vec![model::ModelVertex::desc(), InstanceRaw::desc()],
+        );
+        //the transform is pushed instead of read from the instance buffer, so there is no instance vertex buffer
+        if device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            pipelines.add_shader(
                 &device,
-                &render_pipeline_layout,
-                config.format,
-                Some(texture::Texture::DEPTH_FORMAT),
-                &[model::ModelVertex::desc(), InstanceRaw::desc()],
-                shader,
-            )
-        };
+                MAIN_AT_SHADER,
+                wgpu::ShaderModuleDescriptor {
+                    label: Some("Normal Shader (At A Transform)"),
+                    source: wgpu::ShaderSource::Wgsl(
+                        concat!(
+                            include_str!("shaders/shader.wgsl"),
+                            include_str!("shaders/model_at.wgsl")
+                        )
+                        .into(),
+                    ),
+                },
+            )?;
+            pipelines.add_layout(
+                MAIN_AT_LAYOUT,
+                device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout (At A Transform)"),
+                    bind_group_layouts: &[
+                        &texture_bind_group_layout,
+                        &camera_bind_group_layout,
+                        &light_bind_group_layout,
+                        &joint_bind_group_layout,
+                    ],
+                    push_constant_ranges: &[wgpu::PushConstantRange {
+                        stages: wgpu::ShaderStages::VERTEX,
+                        range: 0..model::ModelTransform::SIZE,
+                    }],
+                }),
+                vec![model::ModelVertex::desc()],
+            );
+        }
+        //the main pipelines are only made once a frame needs them (see create_frame_pipelines)
+
+        let light_marker: model::Model = resources::sphere_model(&device);
 
         //load our model from its .obj file
-        let obj_model: model::Model =
-            resources::load_obj_model("cube.obj", &device, &queue, &texture_bind_group_layout)
-                .await
-                .unwrap();
+        let obj_model: model::Model = resources::load_obj_model(
+            OBJ_MODEL_FILE,
+            &device,
+            &queue,
+            &texture_bind_group_layout,
+            &options.obj_load_options,
+        )
+        .await
+        .with_context(|| format!("couldn't load the model {OBJ_MODEL_FILE}"))?;
+
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        let asset_watcher: hot_reload::AssetWatcher =
+            hot_reload::AssetWatcher::new(&resources::res_dir())?;
+
+        //full screen effects applied after the scene is rendered (none by default)
+        let mut post_process: post_process::PostProcess =
+            post_process::PostProcess::new(&device, &config, scene_format)?;
+        //without tonemapping, everything brighter than 1.0 would just clip when drawn to the surface
+        if hdr {
+            post_process.set_effect(&queue, post_process::PostEffect::Tonemap);
+        }
+
+        //draws the post-processing target's depth to the screen when debugging
+        let depth_view: debug_view::DepthView = debug_view::DepthView::new(
+            &device,
+            config.format,
+            &post_process.target.depth,
+            projection.znear(),
+            projection.zfar(),
+            reverse_z,
+        )?;
+
+        //hidden until set_grid_visible is called
+        let grid: grid::Grid =
+            grid::Grid::new(&device, scene_format, &camera_bind_group_layout, reverse_z);
+        let outline: outline::Outline =
+            outline::Outline::new(&device, scene_format, &camera_bind_group_layout, reverse_z)?;
+
+        let debug_lines: debug_lines::DebugLines = debug_lines::DebugLines::new(
+            &device,
+            scene_format,
+            &camera_bind_group_layout,
+            reverse_z,
+        )?;
+
+        //has nothing to draw text with until a font is given to set_font
+        let text_overlay: text::TextOverlay = text::TextOverlay::new(&device, &config);
+        let sprite_overlay: sprites::SpriteOverlay = sprites::SpriteOverlay::new(&device, &config);
+
+        //only created if the device ended up with the timestamp query features
+        let gpu_timer: Option<gpu_timer::GpuTimer> = if options.gpu_timing {
+            gpu_timer::GpuTimer::new(&device, &queue)
+        } else {
+            None
+        };
 
         //return all of our created data in a State struct
-        Self {
+        Ok(Self {
+            instance,
             surface,
+            limits: device.limits(),
             device,
+            adapter_info,
+            downlevel_flags,
             queue,
             config,
+            scene_format,
             size,
-            render_pipeline,
+            pipelines,
+            depth_bias: wgpu::DepthBiasState::default(),
+            shading: ShadingMode::Smooth,
             obj_model,
+            texture_bind_group_layout,
+            obj_load_options: options.obj_load_options,
+            #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+            asset_watcher,
             depth_texture,
             camera,
             projection,
+            cameras: Vec::new(),
+            viewports: Vec::new(),
             mouse_pressed: false,
+            rotate_button: options.rotate_button,
+            pan_pressed: false,
             camera_uniform,
             camera_buffer,
             camera_bind_group,
+            camera_bind_group_layout,
+            camera_mode: camera::CameraMode::FreeFly,
             camera_controller,
+            orbit_controller,
+            num_instances: instances.len() as u32,
             instances,
             instance_buffer,
+            instance_belt: None,
+            model_transforms: Vec::new(),
+            buffer_pool: buffer_pool::BufferPool::new(),
+            culler,
+            particles: None,
+            scene: None,
+            active_animation: None,
             light_uniform,
+            paused: false,
+            single_instance: false,
+            camera_collision: false,
+            light_marker,
+            light_marker_visible: true,
+            light_orbit: true,
+            light_orbit_speed: cgmath::Deg(60.0),
+            time_of_day: None,
+            fixed_timestep: options.fixed_timestep,
             light_buffer,
             light_bind_group,
-            light_render_pipeline,
-        }
+            joint_palette,
+            post_process,
+            fxaa: None,
+            skybox: None,
+            grid,
+            grid_visible: false,
+            outline,
+            selected: None,
+            debug_lines,
+            debug_view: debug_view::DebugView::Normal,
+            depth_view,
+            text_overlay,
+            sprite_overlay,
+            clear_color: options.clear_color,
+            gpu_timer,
+            frame_timer: frame_stats::FrameTimer::new(),
+            render_stats: std::cell::Cell::new(RenderStats::default()),
+        })
     }
 
     //resizing the window requires reconfiguring the surface
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+        self.size = new_size;
+        //minimized windows can have a size of 0, which the surface (and textures) can't - render() skips frames until the window has a size again
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
         }
+        //resize events often come in with the same size (e.g. a scale factor change with identical pixels, or while dragging) - there's no need to re-allocate everything for them
+        if new_size.width == self.config.width && new_size.height == self.config.height {
+            return;
+        }
+        self.config.width = new_size.width;
+        self.config.height = new_size.height;
+        self.surface.configure(&self.device, &self.config);
         self.depth_texture =
             texture::Texture::create_depth_texture(&self.device, &self.config, "depth_texture");
+        self.post_process
+            .resize(&self.device, self.config.width, self.config.height);
+        self.update_depth_view();
+        if let Some(fxaa) = &mut self.fxaa {
+            fxaa.resize(&self.device, self.config.width, self.config.height);
+        }
+        self.text_overlay
+            .resize(&self.queue, self.config.width, self.config.height);
+        self.sprite_overlay
+            .resize(&self.queue, self.config.width, self.config.height);
         self.projection.resize(new_size.width, new_size.height);
     }
 
-    //an inputs should return true if something changed, and false if nothing changed
-    fn input(&mut self, event: &WindowEvent) -> bool {
-        match event {
-            WindowEvent::KeyboardInput {
-                input:
-                    KeyboardInput {
-                        virtual_keycode: Some(key),
-                        state,
-                        ..
-                    },
-                ..
-            } => self.camera_controller.process_keyboard(*key, *state),
-            WindowEvent::MouseWheel { delta, .. } => {
-                self.camera_controller.process_scroll(delta);
-                true
-            }
-            WindowEvent::MouseInput {
-                button: MouseButton::Left,
-                state,
-                ..
-            } => {
-                self.mouse_pressed = *state == ElementState::Pressed;
-                true
-            }
-            _ => false,
+    //re-creates the surface's swap chain at its current size (e.g. after it was lost)
+    fn reconfigure_surface(&mut self) {
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    //throws away the surface and makes a new one for the window - for when reconfiguring isn't enough (e.g. the gpu driver was reset), which run() does after MAX_SURFACE_ERRORS failed frames in a row
+    //the window has to be the one the State was made with
+    pub fn recover(&mut self, window: &Window) {
+        log::warn!("re-creating the surface");
+        //has to be unsafe for the same reason as in new()
+        self.surface = unsafe { self.instance.create_surface(window) };
+        //the window may have changed size while it couldn't be drawn to
+        let size: winit::dpi::PhysicalSize<u32> = window.inner_size();
+        if size != self.size {
+            self.resize(size);
         }
+        self.reconfigure_surface();
     }
 
-    fn update(&mut self, dt: instant::Duration) {
-        self.camera_controller.update_camera(&mut self.camera, dt);
-        self.camera_uniform
-            .update_view_proj(&self.camera, &self.projection);
-        //write to the buffer with our updated data
-        self.queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&[self.camera_uniform]),
-        );
+    //use a cubemap as the environment behind the scene (see texture::Texture::from_cubemap) - None goes back to the plain clear colour
+    pub fn set_skybox(&mut self, cubemap: Option<texture::Texture>) {
+        self.skybox = cubemap.map(|texture| {
+            skybox::Skybox::new(
+                &self.device,
+                self.scene_format,
+                &self.camera_bind_group_layout,
+                texture,
+                self.projection.reverse_z(),
+            )
+        });
+    }
 
-        //update light positon
-        let old_position: cgmath::Vector3<_> = self.light_uniform.position.into();
-        self.light_uniform.position = (cgmath::Quaternion::from_axis_angle(
-            (0.0, 1.0, 0.0).into(),
-            cgmath::Deg(60.0 * dt.as_secs_f32()),
-        ) * old_position)
-            .into();
+    //adds a camera for viewports to draw from, returning its index (the main camera is 0) - it isn't moved by the camera controllers
+    pub fn add_camera(&mut self, camera: camera::Camera) -> usize {
+        self.cameras.push(camera);
+        self.cameras.len()
+    }
 
-        self.queue.write_buffer(
-            &self.light_buffer,
-            0,
-            bytemuck::cast_slice(&[self.light_uniform]),
-        );
+    //the main camera, e.g. to move things relative to where it is looking with its forward, right and up vectors
+    pub fn camera(&self) -> &camera::Camera {
+        &self.camera
     }
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        //wait for the surface to produce a new texture that we will render to
-        let output: wgpu::SurfaceTexture = self.surface.get_current_texture()?;
+    //one of the cameras by index (0 is the main camera), e.g. to move a viewport's view each frame
+    pub fn camera_at_mut(&mut self, index: usize) -> Option<&mut camera::Camera> {
+        match index {
+            0 => Some(&mut self.camera),
+            index => self.cameras.get_mut(index - 1),
+        }
+    }
 
-        //creates a TextureView with default settings to control how the render code interacts with the textures
-        let view: wgpu::TextureView = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+    //draw the scene into each part of the screen from the given camera (e.g. two halves for split-screen) - an empty list goes back to one view from the main camera
+    pub fn set_viewports(&mut self, viewports: Vec<(Viewport, usize)>) -> anyhow::Result<()> {
+        for (_, camera) in &viewports {
+            anyhow::ensure!(
+                *camera <= self.cameras.len(),
+                "camera {camera} doesn't exist (there are {})",
+                self.cameras.len() + 1
+            );
+        }
+        self.viewports = viewports
+            .into_iter()
+            .map(|(viewport, camera)| {
+                viewport::ViewportView::new(
+                    &self.device,
+                    &self.camera_bind_group_layout,
+                    viewport,
+                    camera,
+                )
+            })
+            .collect();
+        Ok(())
+    }
 
-        //creates a command buffer (which most modern gpu's expect to recieve) that we can then send to the gpu
+    //show (or hide) a grid on the ground plane, fading out into the distance
+    pub fn set_grid_visible(&mut self, visible: bool) {
+        self.grid_visible = visible;
+    }
+
+    //outline one of the instances (by its index in the list given to set_instances), or None to outline nothing
+    pub fn set_selected(&mut self, instance: Option<usize>) {
+        self.selected = instance;
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    //how far in front of the camera the scene was at a pixel of the window (from the top left) in the last frame, or None if nothing was drawn there
+    //waits for the gpu to copy the texel back, so is meant for clicks rather than every frame - on the web it can't wait, so is always None
+    pub fn depth_at(&self, x: u32, y: u32) -> Option<f32> {
+        if x >= self.config.width || y >= self.config.height {
+            return None;
+        }
+        //the scene is drawn into the post-processing target instead of the depth texture, which might not be the size of the window (see set_render_scale)
+        let (depth_texture, x, y): (&texture::Texture, u32, u32) =
+            if self.debug_view == debug_view::DebugView::Depth || self.post_processing() {
+                let target: &render_target::RenderTarget = &self.post_process.target;
+                (
+                    &target.depth,
+                    (x as u64 * target.width as u64 / self.config.width as u64) as u32,
+                    (y as u64 * target.height as u64 / self.config.height as u64) as u32,
+                )
+            } else {
+                (&self.depth_texture, x, y)
+            };
+
+        let readback_buffer: wgpu::Buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Depth Readback Buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
         let mut encoder: wgpu::CommandEncoder =
             self.device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
+                    label: Some("Depth Readback Encoder"),
                 });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &depth_texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::DepthOnly,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
 
-        //this block is needed to tell rust to drop all references and variables within it so we can finish() it (as encoder is  borrowed mutably)
-        {
-            //contains all the methods to actually draw to the window
-            let mut render_pass: wgpu::RenderPass =
-                encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    //can be anything
-                    label: Some("Render Pass"),
-                    //black box config for setting up colours properly
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        //tells wgpu what texture to save the colours to
-                        view: &view,
-                        //only used if multi-sampling is enabled (its not)
-                        resolve_target: None,
-                        //tells wgpu what to do with the colours on the screen
-                        ops: wgpu::Operations {
-                            //tells wgpu how to handle colours stored from the previous frame (currently just clearing the screen with a blueish colour) - this is compairable to a default background?
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.1,
-                                g: 0.2,
-                                b: 0.3,
-                                a: 1.0,
-                            }),
-                            //whether we should store our rendered results to the Texture from the TextureView
-                            store: true,
-                        },
-                    })],
-                    //actually uses the depth texture
-                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                        view: &self.depth_texture.view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
-                            store: true,
-                        }),
-                        //only using depth, no stensil yet
-                        stencil_ops: None,
-                    }),
-                });
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                //the receiver is still around, as depth_at is waiting on it
+                let _ = sender.send(result);
+            });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.try_recv().ok()?.ok()?;
+
+        let depth: f32 = {
+            let data: wgpu::BufferView = readback_buffer.slice(..).get_mapped_range();
+            bytemuck::cast_slice::<u8, f32>(&data)[0]
+        };
+        readback_buffer.unmap();
+
+        let reverse_z: bool = self.projection.reverse_z();
+        if depth == texture::Texture::depth_clear_value(reverse_z) {
+            return None;
+        }
+        Some(texture::Texture::linear_depth(
+            depth,
+            self.projection.znear(),
+            self.projection.zfar(),
+            reverse_z,
+        ))
+    }
+
+    //the colour and thickness (in world units) of the selection outline
+    pub fn set_outline(&mut self, color: [f32; 4], width: f32) {
+        self.outline.set_color(&self.queue, color);
+        self.outline.set_width(&self.queue, width);
+    }
+
+    //draw a line between two points in the world for this frame only (e.g. a ray, or a normal)
+    pub fn draw_line(
+        &mut self,
+        start: cgmath::Point3<f32>,
+        end: cgmath::Point3<f32>,
+        color: [f32; 3],
+    ) {
+        self.debug_lines.line(start, end, color);
+    }
+
+    //draw the outline of a box for this frame only
+    pub fn draw_aabb(&mut self, aabb: Aabb, color: [f32; 3]) {
+        self.debug_lines.aabb(&aabb, color);
+    }
+
+    //how long the gpu spent drawing the light and the rest of the scene in a recent frame (they arrive a frame or two late) - None until one has been measured, or if gpu timing is off or unsupported
+    pub fn last_gpu_times(&self) -> Option<GpuTimes> {
+        self.gpu_timer
+            .as_ref()
+            .and_then(gpu_timer::GpuTimer::last_times)
+    }
+
+    //the average, best, worst and 99th percentile times of the last few seconds of frames, and how many of them took over twice as long as they should have - None before the first frame
+    pub fn frame_stats(&self) -> Option<FrameStats> {
+        self.frame_timer.stats()
+    }
+
+    //how long a frame should take, for counting dropped frames (run_with_config sets it from max_fps) - None compares frames to the recent average instead
+    pub fn set_frame_target(&mut self, target: Option<instant::Duration>) {
+        self.frame_timer.set_target(target);
+    }
+
+    pub fn frame_target(&self) -> Option<instant::Duration> {
+        self.frame_timer.target()
+    }
+
+    //at most how many draw calls and triangles the last frame (or render_to) drew (see RenderStats for what isn't exact) - set RUST_LOG to trace to have them logged every frame
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats.get()
+    }
+
+    //adds a draw of each of the meshes to this frame's stats
+    fn count_draws<'a>(
+        &self,
+        meshes: impl IntoIterator<Item = &'a model::Mesh>,
+        instances: u32,
+        topology: wgpu::PrimitiveTopology,
+    ) {
+        let mut stats: RenderStats = self.render_stats.get();
+        stats.add_meshes(meshes, instances, topology);
+        self.render_stats.set(stats);
+    }
+
+    //called with the time between each frame, whether or not the scene is paused
+    fn record_frame(&mut self, dt: instant::Duration) {
+        self.frame_timer.record(dt);
+    }
+
+    //which gpu, graphics api (backend) and driver the renderer is running on - e.g. for showing in an about screen or attaching to bug reports
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    //whether the device has all of the given features enabled - e.g. to check for POLYGON_MODE_LINE before turning on wireframe, or fall back when something is missing
+    pub fn supports(&self, features: wgpu::Features) -> bool {
+        self.device.features().contains(features)
+    }
+
+    //the limits (max texture size, bind groups, buffer sizes ect) of the device
+    pub fn limits(&self) -> &wgpu::Limits {
+        &self.limits
+    }
+
+    //whether off-screen instances are being culled on the gpu - only if it was asked for with EngineBuilder::with_gpu_culling and the gpu supports it
+    pub fn gpu_culling(&self) -> bool {
+        self.culler.is_some()
+    }
+
+    //spawns the emitter's particles, which then move (and fade out) on the gpu until they die - errors if the gpu doesn't support compute shaders (e.g. webgl)
+    pub fn spawn_particles(&mut self, emitter: ParticleEmitter) -> anyhow::Result<()> {
+        if !self
+            .downlevel_flags
+            .contains(particles::ParticleSystem::DOWNLEVEL_FLAGS)
+        {
+            anyhow::bail!("the gpu doesn't support compute shaders, which particles need");
+        }
+        let particles: &mut particles::ParticleSystem = match &mut self.particles {
+            Some(particles) => particles,
+            None => self.particles.insert(particles::ParticleSystem::new(
+                &self.device,
+                self.scene_format,
+                &self.camera_bind_group_layout,
+                self.projection.reverse_z(),
+            )?),
+        };
+        particles.spawn(&self.queue, &emitter);
+        Ok(())
+    }
+
+    //kills every particle that has been spawned
+    pub fn clear_particles(&mut self) {
+        if let Some(particles) = &mut self.particles {
+            particles.clear(&self.queue);
+        }
+    }
+
+    //unloads everything in the scene - our model (and its animations), the instances, the scene graph and the particles - ready for new content to be loaded (e.g. with load_gltf and set_instances)
+    //the gpu buffers and textures they used are freed, as nothing refers to them any more
+    pub fn clear_scene(&mut self) {
+        self.obj_model = model::Model::empty();
+        self.active_animation = None;
+        self.scene = None;
+        self.instances.clear();
+        self.selected = None;
+        self.particles = None;
+
+        //a buffer grown for lots of instances would otherwise be kept around, empty
+        self.instance_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            usage: instance_buffer_usage(self.culler.is_some()),
+            mapped_at_creation: false,
+        });
+        self.buffer_pool.clear();
+        self.update_instance_buffer();
+    }
+
+    //draw our model at a transform for this frame only, without adding an instance - for one-off objects that would otherwise need the instances re-uploaded
+    //an overlay ignores depth and is drawn after the rest of the scene, so it is never hidden behind anything (for gizmos and UI placed in the world)
+    //errors if the gpu doesn't support push constants (e.g. webgl)
+    pub fn draw_model_at(
+        &mut self,
+        transform: cgmath::Matrix4<f32>,
+        overlay: bool,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.pipelines.has_shader(MAIN_AT_SHADER),
+            "the gpu doesn't support push constants, which drawing at a transform needs"
+        );
+        self.model_transforms.push((transform, overlay));
+        Ok(())
+    }
+
+    //replace the instances our model is drawn at (ignored while a scene is set)
+    pub fn set_instances(&mut self, instances: Vec<Instance>) {
+        self.instances = instances;
+        self.update_instance_buffer();
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    //change our instances' transforms in place (e.g. to animate them) - nothing changes on screen until sync_instances is called
+    pub fn instances_mut(&mut self) -> &mut [Instance] {
+        &mut self.instances
+    }
+
+    //uploads the instances to the gpu - has to be called after changing them through instances_mut (once per frame at most is plenty)
+    pub fn sync_instances(&mut self) {
+        self.update_instance_buffer();
+    }
+
+    //draw our model wherever the scene graph's nodes say to (nodes with model 0), instead of at our instances - None goes back to the instances
+    pub fn set_scene(&mut self, scene: Option<scene::Node>) {
+        self.scene = scene;
+        self.update_instance_buffer();
+    }
+
+    //replace our model with a gltf (.gltf or .glb) file's meshes, drawn wherever the file's scene graph puts them - its animations (which also move its skin, if it has one) can then be started with play_animation
+    pub async fn load_gltf(&mut self, file_name: &str) -> anyhow::Result<()> {
+        let model: model::Model = resources::load_gltf_model(
+            file_name,
+            &self.device,
+            &self.queue,
+            &self.texture_bind_group_layout,
+        )
+        .await?;
+        let (scene, animations) = resources::load_gltf_animations(file_name).await?;
+        self.obj_model = model;
+        self.set_animations(animations);
+        self.set_scene(Some(scene));
+        Ok(())
+    }
+
+    //replace our model's animations (e.g. with those from resources::load_gltf_animations) - stopping any that are playing
+    pub fn set_animations(&mut self, animations: Vec<animation::Animation>) {
+        self.obj_model.animations = animations;
+        self.active_animation = None;
+    }
+
+    //start playing one of our model's animations on the scene graph (from the beginning) - None stops it where it is
+    pub fn play_animation(&mut self, animation: Option<usize>) {
+        self.active_animation = animation
+            .filter(|&index| index < self.obj_model.animations.len())
+            .map(|index| (index, 0.0));
+    }
+
+    //moves the scene graph's nodes to where the active animation says they should be
+    fn update_animation(&mut self, dt: instant::Duration) {
+        let (index, time): (usize, f32) = match &mut self.active_animation {
+            Some((index, time)) => {
+                *time += dt.as_secs_f32();
+                (*index, *time)
+            }
+            None => return,
+        };
+        let scene: &mut scene::Node = match &mut self.scene {
+            Some(scene) => scene,
+            None => return,
+        };
+
+        let poses: Vec<(usize, cgmath::Matrix4<f32>)> =
+            self.obj_model.animations[index].sample(time);
+        scene.visit_mut(&mut |node| {
+            if let Some((_, transform)) = poses.iter().find(|(id, _)| Some(*id) == node.id) {
+                node.transform = *transform;
+            }
+        });
+        self.update_instance_buffer();
+    }
+
+    //re-uploads our instances (or the scene graph's, if there is one) to the gpu - the buffer is re-created if it is too small
+    fn update_instance_buffer(&mut self) {
+        let instance_data: Vec<InstanceRaw> = match &self.scene {
+            Some(root) => root.collect_instances(0),
+            None => self.instances.iter().map(Instance::to_raw).collect(),
+        };
+        let data: &[u8] = bytemuck::cast_slice(&instance_data);
+
+        if self.single_instance {
+            match instance_data.first() {
+                Some(instance) => log::info!("drawing only the first instance: {instance:?}"),
+                None => log::info!("there are no instances to draw"),
+            }
+        }
+
+        //the outgrown buffer goes back into the pool, so a scene that shrinks and grows again doesn't keep allocating
+        if data.len() as wgpu::BufferAddress > self.instance_buffer.size() {
+            let buffer: wgpu::Buffer = self.buffer_pool.acquire(
+                &self.device,
+                "Instance Buffer",
+                data.len() as wgpu::BufferAddress,
+                instance_buffer_usage(self.culler.is_some()),
+            );
+            let old_buffer: wgpu::Buffer = std::mem::replace(&mut self.instance_buffer, buffer);
+            self.buffer_pool.release(old_buffer);
+        }
+        if let Some(size) = std::num::NonZeroU64::new(data.len() as wgpu::BufferAddress) {
+            match &mut self.instance_belt {
+                //the copy out of the belt goes in its own command buffer, as this can be called outside of render()
+                Some(belt) => {
+                    let mut encoder: wgpu::CommandEncoder =
+                        self.device
+                            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                                label: Some("Instance Upload Encoder"),
+                            });
+                    belt.write_buffer(&mut encoder, &self.instance_buffer, 0, size, &self.device)
+                        .copy_from_slice(data);
+                    belt.finish();
+                    self.queue.submit(std::iter::once(encoder.finish()));
+                    belt.recall();
+                }
+                None => self.queue.write_buffer(&self.instance_buffer, 0, data),
+            }
+        }
+        self.num_instances = instance_data.len() as u32;
+
+        //the colliders follow the instances around
+        if self.camera_collision {
+            self.camera_controller.set_colliders(
+                instance_data
+                    .iter()
+                    .map(|instance| self.obj_model.bounds.transformed(instance.model.into()))
+                    .collect(),
+            );
+        }
+
+        //the skin's joints are nodes of the scene graph, so they move along with it
+        if let (Some(skin), Some(scene)) = (&self.obj_model.skin, &self.scene) {
+            self.joint_palette
+                .write(&self.queue, &skin.joint_matrices(scene));
+        }
+    }
+
+    //render the scene at a fraction of the window's size (e.g. 0.5 on high-dpi displays) and upscale it to fill the window - 1.0 renders at full size
+    pub fn set_render_scale(&mut self, scale: f32) {
+        //the target's textures can't be smaller than 1x1, but anything below a pixel is nonsense anyway
+        let scale: f32 = scale.clamp(0.1, 2.0);
+        self.post_process.set_render_scale(
+            &self.device,
+            scale,
+            self.config.width,
+            self.config.height,
+        );
+        self.update_depth_view();
+    }
+
+    //the depth view reads the post-processing target's depth, so has to follow it whenever it is re-created
+    fn update_depth_view(&mut self) {
+        self.depth_view.set_depth_texture(
+            &self.device,
+            &self.queue,
+            &self.post_process.target.depth,
+            self.post_process.render_scale(),
+        );
+    }
+
+    //how bright the scene is before it is tonemapped (1.0 leaves it unchanged)
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.post_process.set_exposure(&self.queue, exposure);
+    }
+
+    //the gamma of the display the scene is shown on (2.2 leaves it unchanged)
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.post_process.set_gamma(&self.queue, gamma);
+    }
+
+    //choose the full screen effect applied to the scene - PostEffect::None renders straight to the screen
+    pub fn set_post_effect(&mut self, effect: post_process::PostEffect) {
+        self.post_process.set_effect(&self.queue, effect);
+    }
+
+    //how the edges of triangles are smoothed (not at all by default) - fxaa makes the scene always go through the post-processing pass
+    pub fn set_aa(&mut self, mode: fxaa::AaMode) -> anyhow::Result<()> {
+        match mode {
+            fxaa::AaMode::None => self.fxaa = None,
+            fxaa::AaMode::Fxaa => {
+                if self.fxaa.is_none() {
+                    self.fxaa = Some(fxaa::Fxaa::new(&self.device, &self.config)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn aa(&self) -> fxaa::AaMode {
+        if self.fxaa.is_some() {
+            fxaa::AaMode::Fxaa
+        } else {
+            fxaa::AaMode::None
+        }
+    }
+
+    //whether the scene is rendered into the post-processing target rather than straight to the screen
+    fn post_processing(&self) -> bool {
+        self.post_process.is_enabled() || self.fxaa.is_some()
+    }
+
+    //move the (point) light - if it is orbiting it will carry on orbiting from here, so you probably want set_light_orbit(false) too
+    //turns the light back into a point light if it was following the time of day
+    pub fn set_light_position(&mut self, position: cgmath::Vector3<f32>) {
+        self.light_uniform.position = position.into();
+        self.light_uniform.directional = 0;
+        self.time_of_day = None;
+        self.write_light_uniform();
+    }
+
+    //turns the light into the sun, at where it would be at a time of day from 0 to 1 (0 is midnight, 0.25 dawn, 0.5 noon and 0.75 dusk) - warm near the horizon, white at noon and off at night
+    //the light stops orbiting, and set_light_position turns it back into a point light
+    pub fn set_time_of_day(&mut self, time_of_day: f32) {
+        let sun: sun::Sun = sun::Sun::at(time_of_day);
+        self.light_uniform.position = sun.direction.into();
+        self.light_uniform.color = sun.color;
+        self.light_uniform.directional = 1;
+        self.light_orbit = false;
+        self.time_of_day = Some(time_of_day);
+        self.write_light_uniform();
+    }
+
+    //None while the light is a point light
+    pub fn time_of_day(&self) -> Option<f32> {
+        self.time_of_day
+    }
+
+    pub fn set_light_color(&mut self, color: [f32; 3]) {
+        self.light_uniform.color = color;
+        self.write_light_uniform();
+    }
+
+    //whether the (point) light is shown as a marker in its colour where it is (on by default)
+    pub fn set_light_marker_visible(&mut self, visible: bool) {
+        self.light_marker_visible = visible;
+    }
+
+    pub fn light_marker_visible(&self) -> bool {
+        self.light_marker_visible
+    }
+
+    //how much the light's marker is scaled by (0.25 by default) - the default sphere has a radius of 1
+    pub fn set_light_marker_size(&mut self, size: f32) {
+        self.light_uniform.marker_size = size.max(0.0);
+        self.write_light_uniform();
+    }
+
+    pub fn light_marker_size(&self) -> f32 {
+        self.light_uniform.marker_size
+    }
+
+    //show the light as a model from a file instead of the default sphere - only its shape is used, as the marker is drawn in the light's colour
+    pub async fn load_light_marker(&mut self, file_name: &str) -> anyhow::Result<()> {
+        let marker: model::Model = resources::load_obj_model(
+            file_name,
+            &self.device,
+            &self.queue,
+            &self.texture_bind_group_layout,
+            &self.obj_load_options,
+        )
+        .await?;
+        //the light pipeline only draws triangles
+        anyhow::ensure!(
+            marker.topology == wgpu::PrimitiveTopology::TriangleList,
+            "the light marker {file_name} isn't made of triangles"
+        );
+        self.light_marker = marker;
+        Ok(())
+    }
+
+    //freeze the scene (e.g. to take a screenshot, or inspect a frame) - it is still drawn, but run() doesn't move anything on until it is unpaused
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    //for scenes whose instances change every frame - uploads them through a wgpu::util::StagingBelt, whose mapped buffers are kept and re-used between frames instead of being allocated for every write
+    pub fn set_dynamic_instances(&mut self, dynamic: bool) {
+        self.instance_belt =
+            dynamic.then(|| wgpu::util::StagingBelt::new(INSTANCE_BELT_CHUNK_SIZE));
+    }
+
+    pub fn dynamic_instances(&self) -> bool {
+        self.instance_belt.is_some()
+    }
+
+    //for debugging instancing - only the first instance is drawn (with draw_model_pass rather than the instanced draw), and its transform is logged whenever the instances change
+    pub fn set_single_instance(&mut self, single_instance: bool) {
+        self.single_instance = single_instance;
+        //re-uploading logs the first instance straight away
+        self.update_instance_buffer();
+    }
+
+    pub fn single_instance(&self) -> bool {
+        self.single_instance
+    }
+
+    //whether the light spins around the centre of the scene on its own (on by default)
+    pub fn set_light_orbit(&mut self, orbit: bool) {
+        self.light_orbit = orbit;
+    }
+
+    //how many degrees a second the light orbits by (60 by default) - negative spins it the other way
+    pub fn set_light_orbit_speed(&mut self, degrees_per_second: f32) {
+        self.light_orbit_speed = cgmath::Deg(degrees_per_second);
+    }
+
+    //make update() step by a fixed amount, no matter how long frames really take (None goes back to real time)
+    pub fn set_fixed_timestep(&mut self, timestep: Option<instant::Duration>) {
+        self.fixed_timestep = timestep;
+    }
+
+    fn write_light_uniform(&self) {
+        self.queue.write_buffer(
+            &self.light_buffer,
+            0,
+            bytemuck::cast_slice(&[self.light_uniform]),
+        );
+    }
+
+    //the colour and strength of the light every surface gets, even when facing away from the light
+    pub fn set_ambient(&mut self, color: [f32; 3], strength: f32) {
+        self.light_uniform.ambient = color;
+        self.light_uniform.ambient_strength = strength;
+        self.write_light_uniform();
+    }
+
+    //add a light shining from position towards direction, fading out from inner_deg to outer_deg away from its centre, and to nothing range units away from it - returns the index to remove it with
+    //only MAX_SPOTLIGHTS can be active at once - adding more than that is an error
+    pub fn add_spotlight(
+        &mut self,
+        position: cgmath::Point3<f32>,
+        direction: cgmath::Vector3<f32>,
+        inner_deg: f32,
+        outer_deg: f32,
+        color: [f32; 3],
+        range: f32,
+    ) -> anyhow::Result<usize> {
+        let index: usize = self.light_uniform.spotlight_count as usize;
+        anyhow::ensure!(
+            index < MAX_SPOTLIGHTS,
+            "only {MAX_SPOTLIGHTS} spotlights are supported"
+        );
+        anyhow::ensure!(
+            range > 0.0,
+            "a spotlight's range must be above 0, got {range}"
+        );
+
+        self.light_uniform.spotlights[index] = SpotLightUniform {
+            position: position.into(),
+            inner_cos: cgmath::Deg(inner_deg).cos(),
+            direction: direction.normalize().into(),
+            //the outer angle can't be inside the inner one, or smoothstep's edges would be the wrong way round
+            outer_cos: cgmath::Deg(outer_deg.max(inner_deg)).cos(),
+            color,
+            range,
+        };
+        self.light_uniform.spotlight_count += 1;
+        self.write_light_uniform();
+
+        Ok(index)
+    }
+
+    //stop a spotlight from lighting the scene - the spotlights added after it move down an index to fill the gap (like Vec::remove)
+    pub fn remove_spotlight(&mut self, index: usize) -> anyhow::Result<()> {
+        let count: usize = self.light_uniform.spotlight_count as usize;
+        anyhow::ensure!(
+            index < count,
+            "there is no spotlight {index} (only {count} have been added)"
+        );
+        self.light_uniform.spotlights[index..count].rotate_left(1);
+        self.light_uniform.spotlights[count - 1] = bytemuck::Zeroable::zeroed();
+        self.light_uniform.spotlight_count -= 1;
+        self.write_light_uniform();
+
+        Ok(())
+    }
+
+    //the font draw_text uses (the raw bytes of a .ttf or .otf file, e.g. from resources::load_binary)
+    pub fn set_font(&mut self, font_data: Vec<u8>) -> anyhow::Result<()> {
+        self.text_overlay.set_font(font_data)
+    }
+
+    //draw white text with its top left corner at (x, y) pixels from the top left of the window - only lasts for the next frame, so call it every frame
+    pub fn draw_text(&mut self, text: &str, x: f32, y: f32) {
+        self.text_overlay.queue(text, x, y, [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    //makes a texture available to draw_sprite, returning the index to draw it with - e.g. an icon, or a sheet of them
+    pub fn add_sprite_texture(&mut self, texture: texture::Texture) -> usize {
+        self.sprite_overlay.add_texture(&self.device, texture)
+    }
+
+    //draw part of a sprite texture over the scene for the next frame - rect is x, y, width and height in pixels from the top left of the window, and uv is the min u, min v, max u and max v of the part of the texture to show ([0.0, 0.0, 1.0, 1.0] for all of it)
+    //sprites are drawn in the order they are queued (so later ones go on top), under any text - errors if the texture hasn't been added
+    pub fn draw_sprite(
+        &mut self,
+        texture: usize,
+        rect: [f32; 4],
+        uv: [f32; 4],
+    ) -> anyhow::Result<()> {
+        self.sprite_overlay.queue(texture, rect, uv)
+    }
+
+    //the field of view of the camera - vertical unless set_fov_axis says otherwise
+    pub fn set_fov<F: Into<cgmath::Rad<f32>>>(&mut self, fov: F) -> anyhow::Result<()> {
+        self.projection.set_fov(fov)
+    }
+
+    //whether the field of view stays fixed vertically (the default) or horizontally as the window's aspect ratio changes
+    pub fn set_fov_axis(&mut self, fov_axis: camera::FovAxis) {
+        self.projection.set_fov_axis(fov_axis);
+    }
+
+    //how close to the camera things can be before they are cut off (has to be above 0)
+    pub fn set_znear(&mut self, znear: f32) -> anyhow::Result<()> {
+        self.projection.set_znear(znear)?;
+        self.depth_view
+            .set_planes(&self.queue, self.projection.znear(), self.projection.zfar());
+        Ok(())
+    }
+
+    //how far from the camera things can be before they are cut off (has to be beyond znear)
+    pub fn set_zfar(&mut self, zfar: f32) -> anyhow::Result<()> {
+        self.projection.set_zfar(zfar)?;
+        self.depth_view
+            .set_planes(&self.queue, self.projection.znear(), self.projection.zfar());
+        Ok(())
+    }
+
+    //tone down (or exaggerate) one of our model's normal maps - see model::Material::set_normal_strength
+    pub fn set_normal_strength(&mut self, material: usize, strength: f32) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.set_normal_strength(&self.queue, strength);
+        }
+    }
+
+    //switch one of our model's materials between blinn-phong and pbr lighting - see model::ShadingModel
+    pub fn set_material_shading_model(
+        &mut self,
+        material: usize,
+        shading_model: model::ShadingModel,
+    ) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.set_shading_model(&self.queue, shading_model);
+        }
+    }
+
+    //how one of our model's materials samples its textures - e.g. wgpu::AddressMode::Repeat for textures that tile across uvs above 1 (floors, walls ect)
+    pub fn set_material_sampler(&mut self, material: usize, options: texture::SamplerOptions) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.set_sampler_options(
+                &self.device,
+                &self.queue,
+                &self.texture_bind_group_layout,
+                options,
+            );
+        }
+    }
+
+    //how metallic and rough one of our model's (pbr) materials is - see model::Material::set_metallic_roughness
+    pub fn set_material_metallic_roughness(
+        &mut self,
+        material: usize,
+        metallic: f32,
+        roughness: f32,
+    ) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.set_metallic_roughness(&self.queue, metallic, roughness);
+        }
+    }
+
+    //how sharp and how bright one of our model's (blinn-phong) materials' specular highlight is - see model::Material::set_shininess and set_specular_strength
+    pub fn set_material_specular(&mut self, material: usize, shininess: f32, strength: f32) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.set_shininess(&self.queue, shininess);
+            material.set_specular_strength(&self.queue, strength);
+        }
+    }
+
+    //make one of our model's materials see-through - 1 is solid (but still blended if the material is transparent, e.g. for textures with alpha)
+    pub fn set_material_opacity(&mut self, material: usize, opacity: f32) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.set_opacity(&self.queue, opacity);
+        }
+    }
+
+    //draw one of our model's materials with (or without) alpha blending - needed for textures with see-through parts, like foliage
+    pub fn set_material_transparent(&mut self, material: usize, transparent: bool) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.transparent = transparent;
+        }
+    }
+
+    //draw both sides of one of our model's materials - see model::Material::set_double_sided
+    pub fn set_material_double_sided(&mut self, material: usize, double_sided: bool) {
+        if let Some(material) = self.obj_model.materials.get_mut(material) {
+            material.set_double_sided(double_sided);
+        }
+    }
+
+    //draw one of our model's meshes with a different one of its materials - fails if either index is out of range
+    pub fn set_mesh_material(&mut self, mesh: usize, material: usize) -> anyhow::Result<()> {
+        self.obj_model.set_mesh_material(mesh, material)
+    }
+
+    //show or hide one of our model's meshes - fails if there is no mesh with that index
+    pub fn set_mesh_visible(&mut self, mesh: usize, visible: bool) -> anyhow::Result<()> {
+        self.obj_model.set_mesh_visible(mesh, visible)
+    }
+
+    //draw the scene with smooth (blended) normals, or one normal per triangle for a faceted look
+    pub fn set_shading(&mut self, shading: ShadingMode) {
+        self.shading = shading;
+    }
+
+    pub fn shading(&self) -> ShadingMode {
+        self.shading
+    }
+
+    //choose what gets shown on the screen - DebugView::Normal is the scene as usual
+    pub fn set_debug_view(&mut self, view: debug_view::DebugView) {
+        self.debug_view = view;
+    }
+
+    //the fragment shader the model is drawn with - the shading mode's, unless normals are being shown instead
+    fn fragment_entry_point(&self) -> &'static str {
+        if self.debug_view == debug_view::DebugView::Normals {
+            NORMALS_ENTRY_POINT
+        } else {
+            self.shading.fragment_entry_point()
+        }
+    }
+
+    //which mouse button has to be held down to rotate the camera (left by default) - e.g. the right or middle button, to leave left clicks free for selecting things
+    pub fn set_rotate_button(&mut self, button: MouseButton) {
+        self.rotate_button = button;
+        //the old button might have been held down, and we'd never see it released
+        self.mouse_pressed = false;
+        self.pan_pressed = false;
+    }
+
+    //pushes the depth of the model's triangles back by constant (in the depth buffer's smallest steps) plus slope_scale times how steep the triangle is, at most clamp (0 is no limit)
+    //fixes flickering between coplanar surfaces (decals, overlapping meshes) - the main pipelines are made the first time a bias is used, so switching between a few is cheap but new ones shouldn't be set every frame
+    pub fn set_depth_bias(&mut self, depth_bias: wgpu::DepthBiasState) -> anyhow::Result<()> {
+        let old_depth_bias: wgpu::DepthBiasState =
+            std::mem::replace(&mut self.depth_bias, depth_bias);
+        //made now rather than at the next frame, so a bias the gpu won't take is returned as an error here
+        if let Err(e) = self.create_frame_pipelines() {
+            self.depth_bias = old_depth_bias;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn depth_bias(&self) -> wgpu::DepthBiasState {
+        self.depth_bias
+    }
+
+    //swap which controller is driving the camera
+    pub fn set_camera_mode(&mut self, mode: camera::CameraMode) {
+        //the orbit controller doesn't use the keyboard, so keys let go of while orbiting would never reach the free-fly controller - it would keep flying when switched back to
+        if self.camera_mode == camera::CameraMode::FreeFly && mode != camera::CameraMode::FreeFly {
+            self.camera_controller.stop();
+        }
+        if mode == camera::CameraMode::Orbit {
+            //start orbiting from wherever the camera currently is - turned to face the target, so it doesn't jump onto the side of the sphere it was looking along
+            self.orbit_controller.distance =
+                (self.camera.position - self.orbit_controller.target).magnitude();
+            self.camera.look_at(self.orbit_controller.target);
+        }
+        self.camera_mode = mode;
+    }
+
+    //how fast (in units per second) the free-fly camera moves
+    pub fn camera_speed(&self) -> f32 {
+        self.camera_controller.speed()
+    }
+
+    pub fn set_camera_speed(&mut self, speed: f32) {
+        self.camera_controller.set_speed(speed);
+    }
+
+    //what scrolling does while flying freely - moving the camera (the default), changing its speed or zooming
+    pub fn set_scroll_mode(&mut self, scroll_mode: ScrollMode) {
+        self.camera_controller.set_scroll_mode(scroll_mode);
+    }
+
+    pub fn scroll_mode(&self) -> ScrollMode {
+        self.camera_controller.scroll_mode()
+    }
+
+    //stops the free-fly camera from flying into the model's instances (each treated as the box around it), for walking through a scene rather than inspecting it
+    pub fn set_camera_collision(&mut self, enabled: bool) {
+        self.camera_collision = enabled;
+        if enabled {
+            self.update_instance_buffer();
+        } else {
+            self.camera_controller.set_colliders(Vec::new());
+        }
+    }
+
+    pub fn camera_collision(&self) -> bool {
+        self.camera_collision
+    }
+
+    //how fast the free-fly camera turns when the mouse moves
+    pub fn set_camera_sensitivity(&mut self, sensitivity: f32) {
+        self.camera_controller.set_sensitivity(sensitivity);
+    }
+
+    //how far scrolling moves the free-fly camera (in ScrollMode::Dolly) - separate from the mouse sensitivity
+    pub fn set_camera_dolly_speed(&mut self, dolly_speed: f32) {
+        self.camera_controller.set_dolly_speed(dolly_speed);
+    }
+
+    //while key is held the free-fly camera moves multiplier times faster - None turns sprinting off (see CameraController::set_sprint)
+    pub fn set_sprint_key(&mut self, key: Option<VirtualKeyCode>, multiplier: f32) {
+        self.camera_controller.set_sprint(key, multiplier);
+    }
+
+    //mouse movement rotates the camera with whichever controller is active
+    fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        match self.camera_mode {
+            camera::CameraMode::FreeFly => self.camera_controller.process_mouse(mouse_dx, mouse_dy),
+            camera::CameraMode::Orbit => self.orbit_controller.process_mouse(mouse_dx, mouse_dy),
+        }
+    }
+
+    //only the free-fly camera can pan - the orbit camera always looks at its target
+    fn process_pan(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        if self.camera_mode == camera::CameraMode::FreeFly {
+            self.camera_controller.process_pan(mouse_dx, mouse_dy);
+        }
+    }
+
+    //an inputs should return true if something changed, and false if nothing changed
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            //tab switches between flying freely and orbiting
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::Tab),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.set_camera_mode(match self.camera_mode {
+                    camera::CameraMode::FreeFly => camera::CameraMode::Orbit,
+                    camera::CameraMode::Orbit => camera::CameraMode::FreeFly,
+                });
+                true
+            }
+            //p pauses and unpauses the scene
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::P),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.set_paused(!self.paused);
+                true
+            }
+            //the orbit controller doesn't use the keyboard
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(key),
+                        state,
+                        ..
+                    },
+                ..
+            } => {
+                self.camera_mode == camera::CameraMode::FreeFly
+                    && self.camera_controller.process_keyboard(*key, *state)
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                match self.camera_mode {
+                    camera::CameraMode::FreeFly => self.camera_controller.process_scroll(delta),
+                    camera::CameraMode::Orbit => self.orbit_controller.process_scroll(delta),
+                }
+                true
+            }
+            WindowEvent::MouseInput { button, state, .. } if *button == self.rotate_button => {
+                self.mouse_pressed = *state == ElementState::Pressed;
+                true
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Middle,
+                state,
+                ..
+            } => {
+                self.pan_pressed = *state == ElementState::Pressed;
+                true
+            }
+            _ => false,
+        }
+    }
 
-            //tells wgpu what instances we have and how to draw them
-            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+    //move everything (camera, light, animations) on by dt - or by the fixed timestep, if there is one
+    pub fn update(&mut self, dt: instant::Duration) {
+        let dt: instant::Duration = self.fixed_timestep.unwrap_or(dt);
+        match self.camera_mode {
+            camera::CameraMode::FreeFly => {
+                self.camera_controller.update_camera(&mut self.camera, dt)
+            }
+            camera::CameraMode::Orbit => self.orbit_controller.update_camera(&mut self.camera, dt),
+        }
+        //scrolling in ScrollMode::Zoom - kept between 1 and 120 degrees so it can't flip or fill the screen with one pixel
+        let zoom: f32 = self.camera_controller.take_zoom();
+        if zoom != 1.0 {
+            let fov: f32 =
+                (self.projection.fov().0 * zoom).clamp(1f32.to_radians(), 120f32.to_radians());
+            self.projection
+                .set_fov(cgmath::Rad(fov))
+                .expect("the zoomed field of view is clamped to a valid range");
+        }
+        self.camera_uniform
+            .update_view_proj(&self.camera, &self.projection);
+        //write to the buffer with our updated data
+        self.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera_uniform]),
+        );
 
-            {
-                use crate::model::DrawLight;
-                render_pass.set_pipeline(&self.light_render_pipeline);
-                render_pass.draw_light_model(
-                    &self.obj_model,
-                    &self.camera_bind_group,
-                    &self.light_bind_group,
-                );
+        //spin the light around the y axis (unless it has been turned off with set_light_orbit)
+        if self.light_orbit {
+            let old_position: cgmath::Vector3<_> = self.light_uniform.position.into();
+            self.light_uniform.position = (cgmath::Quaternion::from_axis_angle(
+                (0.0, 1.0, 0.0).into(),
+                self.light_orbit_speed * dt.as_secs_f32(),
+            ) * old_position)
+                .into();
+
+            self.write_light_uniform();
+        }
+
+        self.update_animation(dt);
+
+        if let Some(particles) = &self.particles {
+            particles.update(&self.device, &self.queue, dt);
+        }
+
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        if self.asset_watcher.changed() {
+            self.reload_model();
+        }
+    }
+
+    //re-loads our model from res/ - if it fails (e.g. a file was only half saved) the old model is kept
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    fn reload_model(&mut self) {
+        match pollster::block_on(resources::load_obj_model(
+            OBJ_MODEL_FILE,
+            &self.device,
+            &self.queue,
+            &self.texture_bind_group_layout,
+            &self.obj_load_options,
+        )) {
+            Ok(model) => {
+                log::info!("reloaded {}", OBJ_MODEL_FILE);
+                self.obj_model = model;
             }
+            Err(e) => log::warn!("failed to reload {}: {e}", OBJ_MODEL_FILE),
+        }
+    }
 
-            render_pass.set_pipeline(&self.render_pipeline);
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        //there's nothing to see while the window has no size (e.g. minimized), and the surface is still configured for the old size
+        if self.size.width == 0 || self.size.height == 0 {
+            return Ok(());
+        }
 
-            {
-                use model::DrawModel;
-                render_pass.draw_model_instanced(
-                    &self.obj_model,
-                    0..self.instances.len() as u32,
-                    &self.camera_bind_group,
-                    &self.light_bind_group,
-                );
+        //pick up the gpu times of an earlier frame, if they've been read back yet
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.poll(&self.device);
+        }
+
+        //wait for the surface to produce a new texture that we will render to
+        let output: wgpu::SurfaceTexture = self.surface.get_current_texture()?;
+
+        //creates a TextureView with default settings to control how the render code interacts with the textures
+        let view: wgpu::TextureView = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.debug_lines
+            .upload(&self.device, &self.queue, &mut self.buffer_pool);
+        self.prepare_culling();
+        self.render_stats.set(RenderStats::default());
+        //anything that needed a pipeline that couldn't be made is left out of the frame
+        if let Err(e) = self.create_frame_pipelines() {
+            log::error!("{e:?}");
+        }
+
+        //creates a command buffer (which most modern gpu's expect to recieve) that we can then send to the gpu
+        let mut encoder: wgpu::CommandEncoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+
+        if self.debug_view == debug_view::DebugView::Depth {
+            //render the scene as usual to fill the depth texture, then draw the depth texture to the screen
+            //the scene pipelines draw in the scene format (which isn't the surface's with hdr), so it has to go into the post-processing target
+            self.render_scene(
+                &mut encoder,
+                &self.post_process.target.color.view,
+                &self.post_process.target.depth.view,
+                self.post_process.target.width,
+                self.post_process.target.height,
+            );
+            self.depth_view.render(&mut encoder, &view);
+        } else if self.post_processing() {
+            //render the scene off-screen first, then draw it to the screen with the effect applied
+            self.render_scene(
+                &mut encoder,
+                &self.post_process.target.color.view,
+                &self.post_process.target.depth.view,
+                self.post_process.target.width,
+                self.post_process.target.height,
+            );
+            match &self.fxaa {
+                //fxaa goes last, so it smooths the colours that end up on the screen
+                Some(fxaa) => {
+                    self.post_process.render(&mut encoder, &fxaa.texture.view);
+                    fxaa.render(&mut encoder, &view);
+                }
+                None => self.post_process.render(&mut encoder, &view),
             }
+        } else {
+            self.render_scene(
+                &mut encoder,
+                &view,
+                &self.depth_texture.view,
+                self.config.width,
+                self.config.height,
+            );
+        }
+
+        //sprites and text go on top of everything, after any effects
+        self.sprite_overlay.render(
+            &self.device,
+            &self.queue,
+            &mut self.buffer_pool,
+            &mut encoder,
+            &view,
+        );
+        self.text_overlay
+            .render(&self.device, &self.queue, &mut encoder, &view);
+
+        if let Some(gpu_timer) = &self.gpu_timer {
+            gpu_timer.resolve(&mut encoder);
         }
 
         //tells wgpu to finish the command buffer and submit it to the render queue
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
 
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.map();
+        }
+        let stats: RenderStats = self.render_stats.get();
+        log::trace!(
+            "drew {} triangles in {} draw calls",
+            stats.triangles,
+            stats.draw_calls
+        );
+        //debug lines (and models drawn at a transform) only last for one frame
+        self.debug_lines.clear();
+        self.model_transforms.clear();
+
         //if all of this completes, return an Ok enum
         Ok(())
     }
+
+    //the same as render(), but draws into an off-screen texture instead of the window
+    //the scene's pipelines are built for the scene format (the surface's format, or HDR_FORMAT in hdr), so the target has to match it
+    pub fn render_to(&mut self, target: &render_target::RenderTarget) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            target.format == self.scene_format,
+            "render target format {:?} doesn't match the scene pipelines' format {:?}",
+            target.format,
+            self.scene_format
+        );
+
+        self.debug_lines
+            .upload(&self.device, &self.queue, &mut self.buffer_pool);
+        self.prepare_culling();
+        self.render_stats.set(RenderStats::default());
+        self.create_frame_pipelines()?;
+
+        let mut encoder: wgpu::CommandEncoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render To Texture Encoder"),
+                });
+
+        self.render_scene(
+            &mut encoder,
+            &target.color.view,
+            &target.depth.view,
+            target.width,
+            target.height,
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        Ok(())
+    }
+
+    //the culler's frustum is the main camera's, so with several viewports (each with their own camera) everything is drawn instead (it's also skipped while only the first instance is drawn)
+    fn culling_active(&self) -> Option<&culling::InstanceCuller> {
+        self.culler
+            .as_ref()
+            .filter(|_| self.viewports.is_empty() && !self.single_instance)
+    }
+
+    //the key of the main pipeline our model's meshes in a material pass are drawn with, with the current shading, topology and depth bias
+    fn main_pipeline_key(&self, pass: model::MaterialPass) -> pipeline_cache::PipelineKey {
+        main_pipeline_key(
+            pass,
+            self.fragment_entry_point(),
+            self.obj_model.topology,
+            self.scene_format,
+            self.projection.reverse_z(),
+            self.depth_bias,
+        )
+    }
+
+    //makes the main pipelines the next frame will draw with, if they haven't been already - there are too many combinations of material, shading, topology and depth bias to make them all up front
+    //has to happen before the frame's render pass, as pipelines can't be created while it borrows them
+    fn create_frame_pipelines(&mut self) -> anyhow::Result<()> {
+        let at_shader: bool = self.pipelines.has_shader(MAIN_AT_SHADER);
+        let at: bool = at_shader && self.model_transforms.iter().any(|(_, overlay)| !*overlay);
+        let overlay: bool = at_shader && self.model_transforms.iter().any(|(_, overlay)| *overlay);
+        for pass in model::MaterialPass::ALL {
+            if self.obj_model.meshes_in_pass(pass).next().is_none() {
+                continue;
+            }
+            let key: pipeline_cache::PipelineKey = self.main_pipeline_key(pass);
+            self.pipelines.get_or_create(&self.device, key)?;
+            if at {
+                self.pipelines
+                    .get_or_create(&self.device, main_at_pipeline_key(key))?;
+            }
+            if overlay {
+                self.pipelines.get_or_create(
+                    &self.device,
+                    overlay_pipeline_key(main_at_pipeline_key(key)),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    //resets this frame's gpu culling to the current instances and camera
+    fn prepare_culling(&mut self) {
+        if !self.viewports.is_empty() {
+            return;
+        }
+        if let Some(culler) = &mut self.culler {
+            culler.prepare(
+                &self.device,
+                &self.queue,
+                &self.instance_buffer,
+                self.num_instances,
+                &self.obj_model,
+                self.camera_uniform.view_proj.into(),
+            );
+        }
+    }
+
+    //draws the meshes of our model whose material needs this pass's pipeline
+    fn draw_material_pass<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pass: model::MaterialPass,
+        camera_bind_group: &'a wgpu::BindGroup,
+    ) {
+        use model::DrawModel;
+        //missing if create_frame_pipelines couldn't make it (or the pass has nothing in it)
+        let Some(pipeline) = self.pipelines.get(&self.main_pipeline_key(pass)) else {
+            return;
+        };
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(3, &self.joint_palette.bind_group, &[]);
+        let instances: u32 = if self.single_instance {
+            self.num_instances.min(1)
+        } else {
+            self.num_instances
+        };
+        if instances > 0 {
+            self.count_draws(
+                self.obj_model.meshes_in_pass(pass),
+                instances,
+                self.obj_model.topology,
+            );
+        }
+        if let Some(culler) = self.culling_active() {
+            culler.draw_pass(
+                render_pass,
+                &self.obj_model,
+                pass,
+                camera_bind_group,
+                &self.light_bind_group,
+            );
+            //everything else (like the outline) indexes into the full list of instances
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            return;
+        }
+        if self.single_instance {
+            if self.num_instances > 0 {
+                render_pass.draw_model_pass(
+                    &self.obj_model,
+                    pass,
+                    camera_bind_group,
+                    &self.light_bind_group,
+                );
+            }
+            return;
+        }
+        render_pass.draw_model_pass_instanced(
+            &self.obj_model,
+            pass,
+            0..self.num_instances,
+            camera_bind_group,
+            &self.light_bind_group,
+        );
+    }
+
+    //draws the meshes of our model whose material needs this pass's pipeline at each of the transforms from draw_model_at (either the overlays or the rest)
+    fn draw_material_pass_at<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pass: model::MaterialPass,
+        overlay: bool,
+        camera_bind_group: &'a wgpu::BindGroup,
+    ) {
+        use model::DrawModel;
+        let mut transforms = self
+            .model_transforms
+            .iter()
+            .filter(|(_, is_overlay)| *is_overlay == overlay)
+            .map(|(transform, _)| transform)
+            .peekable();
+        if transforms.peek().is_none() {
+            return;
+        }
+        let key: pipeline_cache::PipelineKey = main_at_pipeline_key(self.main_pipeline_key(pass));
+        let key: pipeline_cache::PipelineKey = if overlay {
+            overlay_pipeline_key(key)
+        } else {
+            key
+        };
+        //missing if create_frame_pipelines couldn't make it
+        let Some(pipeline) = self.pipelines.get(&key) else {
+            return;
+        };
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(3, &self.joint_palette.bind_group, &[]);
+        for transform in transforms {
+            self.count_draws(
+                self.obj_model.meshes_in_pass(pass),
+                1,
+                self.obj_model.topology,
+            );
+            render_pass.draw_model_pass_at(
+                &self.obj_model,
+                pass,
+                *transform,
+                camera_bind_group,
+                &self.light_bind_group,
+            );
+        }
+    }
+
+    //records a pass drawing the whole scene into the given colour and depth views
+    fn render_scene(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        color_view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        //the culling has to be finished before the pass drawing with its results begins
+        if let Some(culler) = self.culling_active() {
+            culler.dispatch(encoder);
+        }
+
+        //contains all the methods to actually draw to the window
+        let mut render_pass: wgpu::RenderPass =
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                //can be anything
+                label: Some("Render Pass"),
+                //black box config for setting up colours properly
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    //tells wgpu what texture to save the colours to
+                    view: color_view,
+                    //only used if multi-sampling is enabled (its not)
+                    resolve_target: None,
+                    //tells wgpu what to do with the colours on the screen
+                    ops: wgpu::Operations {
+                        //tells wgpu how to handle colours stored from the previous frame (currently just clearing the screen with the clear colour) - this is compairable to a default background?
+                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        //whether we should store our rendered results to the Texture from the TextureView
+                        store: true,
+                    },
+                })],
+                //actually uses the depth texture
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        //the far plane - 1.0, or 0.0 with reverse-z
+                        load: wgpu::LoadOp::Clear(texture::Texture::depth_clear_value(
+                            self.projection.reverse_z(),
+                        )),
+                        store: true,
+                    }),
+                    //only using depth, no stensil yet
+                    stencil_ops: None,
+                }),
+            });
+
+        //tells wgpu what instances we have and how to draw them
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+        if self.viewports.is_empty() {
+            self.draw_view(&mut render_pass, &self.camera_bind_group);
+        } else {
+            //each viewport is drawn with its own camera uniform, as its aspect ratio (and maybe camera) differs from the screen's
+            for view in &self.viewports {
+                let camera: &camera::Camera = match view.camera {
+                    0 => &self.camera,
+                    index => &self.cameras[index - 1],
+                };
+                view.write_camera(&self.queue, camera, &self.projection, width, height);
+
+                let (x, y, viewport_width, viewport_height): (f32, f32, f32, f32) =
+                    view.viewport.to_pixels(width, height);
+                render_pass.set_viewport(x, y, viewport_width, viewport_height, 0.0, 1.0);
+                self.draw_view(&mut render_pass, &view.bind_group);
+            }
+        }
+
+        if let Some(gpu_timer) = &self.gpu_timer {
+            gpu_timer.timestamp(&mut render_pass, gpu_timer::SCENE_END);
+        }
+    }
+
+    //draws everything in the scene from one camera
+    fn draw_view<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera_bind_group: &'a wgpu::BindGroup,
+    ) {
+        //each view writes the same timestamps, so with several viewports the gpu times are the last viewport's
+        if let Some(gpu_timer) = &self.gpu_timer {
+            gpu_timer.timestamp(render_pass, gpu_timer::LIGHT_START);
+        }
+
+        //the outline pipeline only draws triangles
+        let triangles: bool = self.obj_model.topology == wgpu::PrimitiveTopology::TriangleList;
+
+        //a directional light has no position to mark
+        if self.light_marker_visible && self.light_uniform.directional == 0 {
+            use crate::model::DrawLight;
+            render_pass.set_pipeline(
+                &self.pipelines
+                    [&light_pipeline_key(self.scene_format, self.projection.reverse_z())],
+            );
+            self.count_draws(
+                self.light_marker.meshes.iter().filter(|mesh| mesh.visible),
+                1,
+                self.light_marker.topology,
+            );
+            render_pass.draw_light_model(
+                &self.light_marker,
+                camera_bind_group,
+                &self.light_bind_group,
+            );
+        }
+
+        if let Some(gpu_timer) = &self.gpu_timer {
+            gpu_timer.timestamp(render_pass, gpu_timer::SCENE_START);
+        }
+
+        //everything opaque, one pipeline at a time
+        for pass in model::MaterialPass::ALL
+            .into_iter()
+            .filter(|pass| !pass.transparent)
+        {
+            self.draw_material_pass(render_pass, pass, camera_bind_group);
+            self.draw_material_pass_at(render_pass, pass, false, camera_bind_group);
+        }
+
+        //an instance that has since been removed just isn't outlined
+        if let Some(selected) = self
+            .selected
+            .filter(|selected| triangles && *selected < self.num_instances as usize)
+        {
+            self.count_draws(
+                self.obj_model.meshes.iter().filter(|mesh| mesh.visible),
+                1,
+                self.obj_model.topology,
+            );
+            self.outline.draw(
+                render_pass,
+                &self.obj_model,
+                selected as u32,
+                camera_bind_group,
+            );
+        }
+
+        self.debug_lines.draw(render_pass, camera_bind_group);
+
+        //drawn last, so it only covers the parts of the screen nothing else has been drawn to
+        if let Some(skybox) = &self.skybox {
+            skybox.draw(render_pass, camera_bind_group);
+        }
+
+        //see-through meshes are blended with whatever is behind them, so everything opaque (including the skybox) has to be drawn first
+        for pass in model::MaterialPass::ALL
+            .into_iter()
+            .filter(|pass| pass.transparent)
+        {
+            self.draw_material_pass(render_pass, pass, camera_bind_group);
+            self.draw_material_pass_at(render_pass, pass, false, camera_bind_group);
+        }
+
+        if let Some(particles) = &self.particles {
+            particles.draw(render_pass, camera_bind_group);
+        }
+
+        //see-through, so it goes over everything else
+        if self.grid_visible {
+            self.grid.draw(render_pass, camera_bind_group);
+        }
+
+        //overlays don't test depth, so they have to come after everything they should cover
+        for pass in model::MaterialPass::ALL {
+            self.draw_material_pass_at(render_pass, pass, true, camera_bind_group);
+        }
+    }
+}
+
+impl Drop for State {
+    //after this the fields are dropped in the order they are declared (instance, then surface, then device - with the queue and every buffer, texture and pipeline made from it after them) - but anything still queued on the gpu could be using our buffers, so we wait for it to finish before any of them are freed
+    fn drop(&mut self) {
+        self.device.poll(wgpu::Maintain::Wait);
+        //pooled buffers aren't referenced by anything else, so they can be freed straight away rather than waiting on the pool's drop
+        self.buffer_pool.clear();
+    }
+}
+
+//options for how run_with_config sets up the window and renderer
+#[derive(Debug, Default, Clone)]
+pub struct RunConfig {
+    //the encoded image (png, jpeg ect) shown as the window's icon - None uses the os' default icon (and it is ignored on wasm, which has no window icon)
+    pub icon: Option<Vec<u8>>,
+    //the most frames drawn per second - None draws as fast as the present mode allows (which pins the cpu and gpu when vsync is off)
+    pub max_fps: Option<u32>,
+    //where the camera starts - None uses EngineBuilder's default
+    pub camera: Option<CameraState>,
+}
+
+impl RunConfig {
+    pub fn with_icon(mut self, icon: Vec<u8>) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    //sleep between frames so no more than max_fps are drawn each second (e.g. 60 or 144) - saves power for scenes that don't need uncapped frames
+    pub fn with_max_fps(mut self, max_fps: u32) -> Self {
+        self.max_fps = Some(max_fps);
+        self
+    }
+
+    pub fn with_camera(mut self, camera: CameraState) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+}
+
+//decode an image into the raw rgba pixels winit needs for an icon
+#[cfg(not(target_arch = "wasm32"))]
+fn load_icon(data: &[u8]) -> anyhow::Result<winit::window::Icon> {
+    let image: image::RgbaImage = image::load_from_memory(data)?.to_rgba8();
+    let (width, height): (u32, u32) = image.dimensions();
+    Ok(winit::window::Icon::from_rgba(
+        image.into_raw(),
+        width,
+        height,
+    )?)
+}
+
+//the size the canvas should be drawn at - the size css gives the wasm-div element (or the whole browser window, if it hasn't been given one), in physical pixels
+//scale_factor is the browser's devicePixelRatio, so the canvas stays sharp on high-dpi screens
+#[cfg(target_arch = "wasm32")]
+fn web_canvas_size(win: &web_sys::Window, scale_factor: f64) -> winit::dpi::PhysicalSize<u32> {
+    let container: Option<(f64, f64)> = win
+        .document()
+        .and_then(|doc| doc.get_element_by_id("wasm-div"))
+        .map(|div| (div.client_width() as f64, div.client_height() as f64))
+        .filter(|(width, height)| *width > 0.0 && *height > 0.0);
+    let (width, height): (f64, f64) = container.unwrap_or_else(|| {
+        (
+            win.inner_width()
+                .ok()
+                .and_then(|width| width.as_f64())
+                .unwrap_or(1.0),
+            win.inner_height()
+                .ok()
+                .and_then(|height| height.as_f64())
+                .unwrap_or(1.0),
+        )
+    });
+    //a surface can't be 0x0
+    winit::dpi::LogicalSize::new(width.max(1.0), height.max(1.0)).to_physical(scale_factor)
+}
+
+//the entry point on the web - wasm_bindgen can only hand errors to javascript as JsValues, so anything run() fails with is turned into one (and shows up in the console)
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub async fn start() -> Result<(), JsValue> {
+    run()
+        .await
+        .map_err(|e| JsValue::from_str(&format!("{e:?}")))
+}
+
+//lets the page pick where assets are loaded from (see resources::set_asset_base), so one build can be hosted anywhere - it has to be called straight after the module is initialised, before the model starts loading
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = setAssetBase)]
+pub fn set_asset_base_js(url: &str) -> Result<(), JsValue> {
+    set_asset_base(url).map_err(|e| JsValue::from_str(&format!("{e:?}")))
 }
 
-//tells wasm to run the run() function when wasm is initialised
-#[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 //run the rasterizer
 //needs to be async as State::new() is now async aswell
-pub async fn run() {
+//only returns if setting up the window or renderer fails (the event loop takes over the thread once it starts)
+pub async fn run() -> anyhow::Result<()> {
+    run_with_config(RunConfig::default()).await
+}
+
+//the same as run(), but with control over how the window is set up
+pub async fn run_with_config(config: RunConfig) -> anyhow::Result<()> {
+    use anyhow::Context;
+
     //checks if there is platform specific code being ran
     cfg_if::cfg_if! {
         //if its on wasm, use the web logger instead of normal env_logger
         if #[cfg(target_arch = "wasm32")] {
-            console_log::init_with_level(log::Level::Warn).expect("Couldn't initialize logger");
+            console_log::init_with_level(log::Level::Warn).context("couldn't initialize the logger")?;
             std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-            let win = web_sys::window().unwrap();
+            let win = web_sys::window().context("there is no browser window to draw in")?;
         } else {
             //wgpu doesn't use normal error logging, requires env_logger for its custom error messages
-            env_logger::init();
+            //an embedder may have set up its own logger already, which is fine to keep using
+            let _ = env_logger::try_init();
         }
     }
 
@@ -807,10 +3082,22 @@ pub async fn run() {
     }
 
     //a window that can be manipulated to draw on the screen - in init it gets added to the event loop by the window builder
-    let window: Window = WindowBuilder::new()
-        .with_title("unknown-engine")
+    let window_builder: WindowBuilder = WindowBuilder::new().with_title("unknown-engine");
+
+    //a broken icon isn't worth stopping the program for, so we just carry on without it
+    #[cfg(not(target_arch = "wasm32"))]
+    let window_builder: WindowBuilder = match config.icon.as_deref().map(load_icon) {
+        Some(Ok(icon)) => window_builder.with_window_icon(Some(icon)),
+        Some(Err(e)) => {
+            log::warn!("couldn't load the window icon: {e}");
+            window_builder
+        }
+        None => window_builder,
+    };
+
+    let window: Window = window_builder
         .build(&event_loop)
-        .unwrap();
+        .context("couldn't create the window")?;
 
     //fullscreening is not a thing on wasm
     #[cfg(not(target_arch = "wasm32"))]
@@ -819,47 +3106,62 @@ pub async fn run() {
     //code specific to wasm as it requires extra setup to get working
     #[cfg(target_arch = "wasm32")]
     {
-        //winit prevents sizing with CSS, so we have to set the size manually when on web
-        use winit::dpi::PhysicalSize;
-        // wgpu::SurfaceConfiguration
         //the winit window doesn't usually have canvas/web features on
         use winit::platform::web::WindowExtWebSys;
 
-        //[TODO] update on screen resize
-        //set window size to size of screen window
-        //[TODO] add fallbacks for 0 size windows
-        window.set_inner_size(PhysicalSize::new(
-            win.inner_width().unwrap().into_serde::<u32>().unwrap(),
-            win.inner_height().unwrap().into_serde::<u32>().unwrap(),
-        ));
-
         win.document()
             .and_then(|doc| {
                 //the element id corresponds to the element id in the html code for running the program
                 let dst = doc.get_element_by_id("wasm-div")?;
                 let canvas = web_sys::Element::from(window.canvas());
+                //an inline canvas leaves a gap under it, which would make the div (and so the canvas) grow a little every frame
+                canvas.set_attribute("style", "display: block").ok()?;
 
                 dst.append_child(&canvas).ok()?;
                 Some(())
             })
-            .expect("Couldn't append canvas to document body.");
+            .context("couldn't add the canvas to the page's wasm-div element")?;
+
+        //winit prevents sizing with CSS, so we have to set the size manually when on web (and keep it up to date - see the MainEventsCleared handler)
+        window.set_inner_size(web_canvas_size(&win, window.scale_factor()));
     }
 
     //the state of the everything related to the program - the window, device, buffers, textures, models, ect
-    let mut state: State = State::new(&window).await;
+    let mut builder: EngineBuilder = EngineBuilder::new();
+    if let Some(camera) = config.camera {
+        builder = builder.with_camera(Camera::from_state(camera));
+    }
+    let mut state: State = builder
+        .build(&window)
+        .await
+        .context("couldn't set up the renderer")?;
+    //the scene run() shows - 100 copies of the model in a 10x10 grid, each rotated based on an axis
+    state.set_instances(instances::grid(10, 10, 3.0));
     //when the program last rendered
     let mut last_render_time: instant::Instant = instant::Instant::now();
+    //how many frames in a row have failed to render
+    let mut surface_errors: u32 = 0;
+    //the shortest time allowed between frames (a max_fps of 0 is treated as no limit)
+    let frame_time: Option<instant::Duration> = config
+        .max_fps
+        .filter(|max_fps| *max_fps > 0)
+        .map(|max_fps| instant::Duration::from_secs_f64(1.0 / max_fps as f64));
+    state.set_frame_target(frame_time);
 
     //starts the event loop to handle device, program and user events
     event_loop.run(move |event, _, control_flow| {
-        //constantly re-renders and continues the scene even when not on the scene (useful for games)
-        *control_flow = ControlFlow::Poll;
+        //constantly re-renders and continues the scene even when not on the scene (useful for games) - with a frame limit, the loop is left to sleep until the next frame is due instead
+        if frame_time.is_none() {
+            *control_flow = ControlFlow::Poll;
+        }
         match event {
             Event::DeviceEvent {
                 event: DeviceEvent::MouseMotion{ delta },
                 .. // We're not using device_id currently
             } => if state.mouse_pressed {
-                state.camera_controller.process_mouse(delta.0, delta.1)
+                state.process_mouse(delta.0, delta.1)
+            } else if state.pan_pressed {
+                state.process_pan(delta.0, delta.1)
             },
             //if something changes related to the window
             Event::WindowEvent {
@@ -901,25 +3203,64 @@ pub async fn run() {
                 //update internal state
                 let now: instant::Instant = instant::Instant::now();
                 let dt: instant::Duration = now - last_render_time;
+                //kept up to date while paused too, so the time spent paused isn't all stepped through at once when unpausing
                 last_render_time = now;
+                state.record_frame(dt);
 
-                state.update(dt);
+                if !state.paused {
+                    state.update(dt);
+                }
 
                 //render these changes to the screen
                 match state.render() {
-                    Ok(_) => {}
-                    //reconfigure the surface if lost (if our swap chain (kinda the frame buffer) has been lost)
-                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                    Ok(_) => surface_errors = 0,
                     //the system is out of memory, so we should probably quit the program
                     Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
+                    //if the errors don't go away on their own, the surface is probably gone for good (e.g. a driver reset), so a new one is made
+                    Err(e) if surface_errors + 1 >= MAX_SURFACE_ERRORS => {
+                        eprintln!("{:?}", e);
+                        state.recover(&window);
+                        surface_errors = 0;
+                    }
+                    //reconfigure the surface if lost (if our swap chain (kinda the frame buffer) has been lost)
+                    Err(wgpu::SurfaceError::Lost) => {
+                        state.reconfigure_surface();
+                        surface_errors += 1;
+                    }
                     //all other errors (Outdated, Timeout) should be resolved by the next frame and should just be printed to the error log
-                    Err(e) => eprintln!("{:?}", e),
+                    Err(e) => {
+                        eprintln!("{:?}", e);
+                        surface_errors += 1;
+                    }
                 }
             }
             //when the redraw is about to begin (we have no more events to proccess on this frame)
             Event::MainEventsCleared => {
-                //redrawRequested will only trigger once, unless we manually request it
-                window.request_redraw();
+                //the browser doesn't tell winit when the page around the canvas changes size, so we check for it every frame
+                #[cfg(target_arch = "wasm32")]
+                {
+                    let size: winit::dpi::PhysicalSize<u32> =
+                        web_canvas_size(&win, window.scale_factor());
+                    if size != window.inner_size() {
+                        window.set_inner_size(size);
+                        state.resize(size);
+                    }
+                }
+
+                match frame_time {
+                    //redrawRequested will only trigger once, unless we manually request it
+                    None => window.request_redraw(),
+                    Some(frame_time) => {
+                        let next_frame: instant::Instant = last_render_time + frame_time;
+                        if instant::Instant::now() >= next_frame {
+                            window.request_redraw();
+                            *control_flow = ControlFlow::Poll;
+                        } else {
+                            //wake up (unless an event comes in first) when the next frame is due
+                            *control_flow = ControlFlow::WaitUntil(next_frame);
+                        }
+                    }
+                }
             }
             //all other events do nothing for now
             _ => {}
@@ -930,9 +3271,77 @@ pub async fn run() {
 //[TODO] create real tests for the program
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn normal_matrix_handles_non_uniform_scale() {
+        let instance: Instance = Instance {
+            position: cgmath::Vector3::new(1.0, 2.0, 3.0),
+            rotation: cgmath::Quaternion::from_axis_angle(
+                cgmath::Vector3::unit_z(),
+                cgmath::Deg(0.0),
+            ),
+            scale: cgmath::Vector3::new(2.0, 1.0, 1.0),
+        };
+        let raw: InstanceRaw = instance.to_raw();
+        //stretching along x should shrink the x component of normals, not stretch it
+        assert_eq!(
+            raw.normal,
+            [[0.5, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+        );
+        assert_eq!(raw.model[0][0], 2.0);
+        assert_eq!(raw.model[3], [1.0, 2.0, 3.0, 1.0]);
+
+        //squashed flat along y - the flat side's normal (+y) has to survive
+        let flat: InstanceRaw = Instance {
+            scale: cgmath::Vector3::new(1.0, 0.0, 1.0),
+            ..instance
+        }
+        .to_raw();
+        let up: cgmath::Vector3<f32> =
+            cgmath::Matrix3::from(flat.normal) * cgmath::Vector3::unit_y();
+        assert!(up.y > 0.0 && up.x == 0.0 && up.z == 0.0);
+    }
+
+    #[test]
+    fn unsupported_present_modes_fall_back() {
+        use wgpu::PresentMode;
+        let supported: [PresentMode; 2] = [PresentMode::Fifo, PresentMode::Mailbox];
+        assert_eq!(
+            closest_present_mode(PresentMode::Mailbox, &supported),
+            PresentMode::Mailbox
+        );
+        assert_eq!(
+            closest_present_mode(PresentMode::Immediate, &supported),
+            PresentMode::Mailbox
+        );
+        assert_eq!(
+            closest_present_mode(PresentMode::Immediate, &[PresentMode::Fifo]),
+            PresentMode::AutoNoVsync
+        );
+        assert_eq!(
+            closest_present_mode(PresentMode::FifoRelaxed, &supported),
+            PresentMode::AutoVsync
+        );
+        assert_eq!(
+            closest_present_mode(PresentMode::AutoNoVsync, &[]),
+            PresentMode::AutoNoVsync
+        );
+    }
+
+    #[test]
+    fn light_uniform_matches_shader_layout() {
+        //the Light struct in shader.wgsl is 64 bytes followed by an array of 48 byte SpotLights
+        assert_eq!(std::mem::size_of::<SpotLightUniform>(), 48);
+        assert_eq!(
+            std::mem::size_of::<LightUniform>(),
+            64 + 48 * MAX_SPOTLIGHTS
+        );
+    }
 }
@@ -1,5 +1,5 @@
 use unknown_engine::run;
 
-fn main() {
-    pollster::block_on(run());
+fn main() -> anyhow::Result<()> {
+    pollster::block_on(run())
 }
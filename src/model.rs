@@ -2,7 +2,9 @@
 
 use std::ops::Range;
 
-use crate::texture;
+use wgpu::util::DeviceExt;
+
+use crate::{animation, bounds, skin, texture};
 
 //only a trait as there can be many types of vertices, and this would still work
 pub trait Vertex {
@@ -22,6 +24,13 @@ pub struct ModelVertex {
     //to represent lighting via a coordinate system instead of a world system
     pub tangent: [f32; 3],
     pub bitangent: [f32; 3],
+    //per-vertex colour (rgba) that gets multiplied with the texture - white if the model doesn't have any
+    pub color: [f32; 4],
+    //a second set of texture coordinates, which the occlusion texture (or a baked lightmap) is sampled with - a copy of tex_coords if the model only has one set
+    pub tex_coords1: [f32; 2],
+    //the (up to) 4 joints of the model's skin that move this vertex, and how much each one does - all 0 weights means the vertex isn't skinned
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
 }
 
 impl Vertex for ModelVertex {
@@ -71,6 +80,30 @@ impl Vertex for ModelVertex {
                     shader_location: 4,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                //vertex colour - locations 5 to 11 are taken by the instance data, so this goes after them
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 14]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                //second texture coordinates
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 18]>() as wgpu::BufferAddress,
+                    shader_location: 13,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                //skin joints
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 20]>() as wgpu::BufferAddress,
+                    shader_location: 14,
+                    format: wgpu::VertexFormat::Uint32x4,
+                },
+                //skin weights
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 24]>() as wgpu::BufferAddress,
+                    shader_location: 15,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -80,14 +113,155 @@ impl Vertex for ModelVertex {
 pub struct Model {
     pub meshes: Vec<Mesh>,
     pub materials: Vec<Material>,
+    //keyframe animations that can be played on the scene graph (.obj files don't have any)
+    pub animations: Vec<animation::Animation>,
+    //the box around every mesh's vertices, in the model's own space
+    pub bounds: bounds::Aabb,
+    //what the meshes' indices make - triangles, unless the model is a point cloud or only lines (TriangleList, LineList or PointList) - each topology gets its own main pipelines, created on demand through PipelineCache::get_or_create
+    pub topology: wgpu::PrimitiveTopology,
+    //the joints that move the vertices of skinned meshes - None if the model isn't skinned
+    pub skin: Option<skin::Skin>,
+}
+
+//the per-material settings the shader reads alongside the material's textures
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MaterialUniform {
+    //how much of the normal map is applied - 0 is completely flat, 1 is the normal map as-is
+    normal_strength: f32,
+    //multiplied with the texture's alpha - only has an effect on transparent materials
+    opacity: f32,
+    //multiplied with the metallic (blue) and roughness (green) channels of the metallic/roughness texture - only used for ShadingModel::Pbr
+    metallic: f32,
+    roughness: f32,
+    //a ShadingModel as a number (bools and enums can't be put in uniforms)
+    shading_model: u32,
+    //the specular exponent - higher makes the highlight smaller and sharper (only used for ShadingModel::BlinnPhong)
+    shininess: f32,
+    //how bright the specular highlight is - 0 has none at all (only used for ShadingModel::BlinnPhong)
+    specular_strength: f32,
+    //added to the mip level each texture is sampled at (see texture::SamplerOptions::lod_bias)
+    lod_bias: f32,
+}
+
+//how a material reacts to light
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShadingModel {
+    //the classic diffuse + specular highlight - what .obj models are made for
+    BlinnPhong = 0,
+    //physically based (cook-torrance, metallic/roughness) lighting - what gltf models are made for
+    Pbr = 1,
+}
+
+impl Model {
+    //a model with nothing in it, which draws nothing
+    pub fn empty() -> Self {
+        Self {
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            animations: Vec::new(),
+            bounds: bounds::Aabb::from_points([]),
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            skin: None,
+        }
+    }
+
+    //the visible meshes whose material has to be drawn with the given pass's pipeline
+    pub fn meshes_in_pass(&self, pass: MaterialPass) -> impl Iterator<Item = &Mesh> {
+        self.meshes.iter().filter(move |mesh| {
+            mesh.visible
+                && self
+                    .materials
+                    .get(mesh.material)
+                    .is_some_and(|material| material.pass() == pass)
+        })
+    }
+
+    //show or hide one of the meshes (e.g. a character's helmet) - hidden meshes are skipped when drawing
+    pub fn set_mesh_visible(&mut self, mesh: usize, visible: bool) -> anyhow::Result<()> {
+        let mesh_count: usize = self.meshes.len();
+        self.meshes
+            .get_mut(mesh)
+            .ok_or_else(|| anyhow::anyhow!("mesh {mesh} is out of range (there are {mesh_count})"))?
+            .set_visible(visible);
+        Ok(())
+    }
+
+    //swap the material a mesh is drawn with (e.g. to a "selected" highlight material) - both are indices into meshes and materials
+    pub fn set_mesh_material(&mut self, mesh: usize, material: usize) -> anyhow::Result<()> {
+        let material_count: usize = self.materials.len();
+        let mesh_count: usize = self.meshes.len();
+        let mesh: &mut Mesh = self.meshes.get_mut(mesh).ok_or_else(|| {
+            anyhow::anyhow!("mesh {mesh} is out of range (there are {mesh_count})")
+        })?;
+        mesh.set_material(material, material_count)
+    }
+}
+
+//the settings of a material that need a different pipeline to draw it with
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialPass {
+    pub transparent: bool,
+    pub double_sided: bool,
 }
 
-//just the texture and its name (for debug)
+impl MaterialPass {
+    //every pass, with the opaque ones first (as they have to be drawn before anything see-through)
+    pub const ALL: [MaterialPass; 4] = [
+        MaterialPass {
+            transparent: false,
+            double_sided: false,
+        },
+        MaterialPass {
+            transparent: false,
+            double_sided: true,
+        },
+        MaterialPass {
+            transparent: true,
+            double_sided: false,
+        },
+        MaterialPass {
+            transparent: true,
+            double_sided: true,
+        },
+    ];
+}
+
+//how normals are found across a triangle - each is drawn with its own pipelines, created on demand through PipelineCache::get_or_create the first frame a mode is used
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ShadingMode {
+    //normals are blended between the vertices (and normal mapped), so curved surfaces look smooth
+    Smooth,
+    //every triangle is lit with its own face's normal (normal maps are ignored) - for a low-poly look, or seeing the actual geometry
+    Flat,
+}
+
+impl ShadingMode {
+    //the fragment shader in shader.wgsl that shades this way
+    pub(crate) fn fragment_entry_point(self) -> &'static str {
+        match self {
+            ShadingMode::Smooth => "fs_main",
+            ShadingMode::Flat => "fs_flat",
+        }
+    }
+}
+
+//the textures (and settings) of a material and its name (for debug)
 pub struct Material {
     pub label: String,
     pub diffuse_texture: texture::Texture,
     pub normal_texture: texture::Texture,
+    //roughness in the green channel and metallic in the blue channel (like gltf) - plain white if the material doesn't have one
+    pub metallic_roughness_texture: texture::Texture,
+    //ambient occlusion in the red channel (like gltf, so it can share a texture with metallic/roughness) - plain white if the material doesn't have one
+    pub occlusion_texture: texture::Texture,
+    uniform: MaterialUniform,
+    uniform_buffer: wgpu::Buffer,
     pub bind_group: wgpu::BindGroup,
+    //see-through materials are blended with what's behind them, and drawn after everything opaque
+    pub transparent: bool,
+    //whether the backs of triangles are drawn too (see set_double_sided)
+    double_sided: bool,
 }
 
 impl Material {
@@ -96,38 +270,236 @@ impl Material {
         label: &str,
         diffuse_texture: texture::Texture,
         normal_texture: texture::Texture, // NEW!
+        metallic_roughness_texture: texture::Texture,
+        occlusion_texture: texture::Texture,
         layout: &wgpu::BindGroupLayout,
     ) -> Self {
-        let bind_group: wgpu::BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let uniform: MaterialUniform = MaterialUniform {
+            normal_strength: 1.0,
+            opacity: 1.0,
+            //a non-metal that is fairly rough - the least surprising look for materials that don't say
+            metallic: 0.0,
+            roughness: 0.5,
+            shading_model: ShadingModel::BlinnPhong as u32,
+            shininess: 32.0,
+            specular_strength: 1.0,
+            lod_bias: 0.0,
+        };
+        let uniform_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{label} (Material Buffer)")),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let bind_group: wgpu::BindGroup = Self::create_bind_group(
+            device,
+            label,
+            layout,
+            [
+                &diffuse_texture,
+                &normal_texture,
+                &metallic_roughness_texture,
+                &occlusion_texture,
+            ],
+            &uniform_buffer,
+        );
+
+        Self {
+            label: String::from(label),
+            diffuse_texture,
+            normal_texture,
+            metallic_roughness_texture,
+            occlusion_texture,
+            uniform,
+            uniform_buffer,
+            bind_group,
+            transparent: false,
+            double_sided: false,
+        }
+    }
+
+    //the textures are the diffuse, normal, metallic/roughness and occlusion textures, in that order
+    fn create_bind_group(
+        device: &wgpu::Device,
+        label: &str,
+        layout: &wgpu::BindGroupLayout,
+        [diffuse, normal, metallic_roughness, occlusion]: [&texture::Texture; 4],
+        uniform_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some(label),
             layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                    resource: wgpu::BindingResource::TextureView(&diffuse.view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+                    resource: wgpu::BindingResource::Sampler(&diffuse.sampler),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&normal_texture.view),
+                    resource: wgpu::BindingResource::TextureView(&normal.view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 3,
-                    resource: wgpu::BindingResource::Sampler(&normal_texture.sampler),
+                    resource: wgpu::BindingResource::Sampler(&normal.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&metallic_roughness.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::Sampler(&metallic_roughness.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::TextureView(&occlusion.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::Sampler(&occlusion.sampler),
                 },
             ],
-        });
+        })
+    }
 
-        Self {
-            label: String::from(label),
-            diffuse_texture,
-            normal_texture,
-            bind_group,
+    //change how all of the material's textures are sampled (e.g. SamplerOptions::default().with_wrap_mode(wgpu::AddressMode::Repeat) to tile them) - layout is the one the material was created with
+    pub fn set_sampler_options(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        options: texture::SamplerOptions,
+    ) {
+        self.uniform.lod_bias = options.lod_bias;
+        self.write_uniform(queue);
+        for texture in [
+            &mut self.diffuse_texture,
+            &mut self.normal_texture,
+            &mut self.metallic_roughness_texture,
+            &mut self.occlusion_texture,
+        ] {
+            texture.set_sampler(device, options);
+        }
+        //bind groups can't be changed, so a new one is needed to use the new samplers
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.label,
+            layout,
+            [
+                &self.diffuse_texture,
+                &self.normal_texture,
+                &self.metallic_roughness_texture,
+                &self.occlusion_texture,
+            ],
+            &self.uniform_buffer,
+        );
+    }
+
+    pub fn normal_strength(&self) -> f32 {
+        self.uniform.normal_strength
+    }
+
+    //tone down (or exaggerate) the normal map - 0 is completely flat, 1 is the normal map as it was made
+    pub fn set_normal_strength(&mut self, queue: &wgpu::Queue, strength: f32) {
+        self.uniform.normal_strength = strength.max(0.0);
+        self.write_uniform(queue);
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.uniform.opacity
+    }
+
+    //how see-through the whole material is (1 is solid) - anything less than 1 makes the material transparent
+    pub fn set_opacity(&mut self, queue: &wgpu::Queue, opacity: f32) {
+        self.uniform.opacity = opacity.clamp(0.0, 1.0);
+        if self.uniform.opacity < 1.0 {
+            self.transparent = true;
+        }
+        self.write_uniform(queue);
+    }
+
+    pub fn shading_model(&self) -> ShadingModel {
+        if self.uniform.shading_model == ShadingModel::Pbr as u32 {
+            ShadingModel::Pbr
+        } else {
+            ShadingModel::BlinnPhong
+        }
+    }
+
+    pub fn set_shading_model(&mut self, queue: &wgpu::Queue, shading_model: ShadingModel) {
+        self.uniform.shading_model = shading_model as u32;
+        self.write_uniform(queue);
+    }
+
+    pub fn metallic(&self) -> f32 {
+        self.uniform.metallic
+    }
+
+    pub fn roughness(&self) -> f32 {
+        self.uniform.roughness
+    }
+
+    //how metallic (0 to 1) and how rough (0 is a mirror, 1 is completely matte) the material is - multiplied with the metallic/roughness texture, and only used by ShadingModel::Pbr
+    pub fn set_metallic_roughness(&mut self, queue: &wgpu::Queue, metallic: f32, roughness: f32) {
+        self.uniform.metallic = metallic.clamp(0.0, 1.0);
+        self.uniform.roughness = roughness.clamp(0.0, 1.0);
+        self.write_uniform(queue);
+    }
+
+    pub fn shininess(&self) -> f32 {
+        self.uniform.shininess
+    }
+
+    //the specular exponent (32 by default, like .mtl's Ns) - high values give polished and metallic surfaces a small, sharp highlight, low values spread it out
+    pub fn set_shininess(&mut self, queue: &wgpu::Queue, shininess: f32) {
+        //pow(x, 0) is 1 everywhere, which would light the whole surface as a highlight
+        self.uniform.shininess = shininess.max(1.0);
+        self.write_uniform(queue);
+    }
+
+    pub fn specular_strength(&self) -> f32 {
+        self.uniform.specular_strength
+    }
+
+    //how bright the specular highlight is (1 by default) - 0 turns it off for matte surfaces
+    pub fn set_specular_strength(&mut self, queue: &wgpu::Queue, strength: f32) {
+        self.uniform.specular_strength = strength.max(0.0);
+        self.write_uniform(queue);
+    }
+
+    pub fn double_sided(&self) -> bool {
+        self.double_sided
+    }
+
+    //draw both sides of this material's triangles - for thin things like leaves, or models whose faces aren't all wound the same way
+    pub fn set_double_sided(&mut self, double_sided: bool) {
+        self.double_sided = double_sided;
+    }
+
+    //which pipeline this material has to be drawn with
+    pub fn pass(&self) -> MaterialPass {
+        MaterialPass {
+            transparent: self.transparent,
+            double_sided: self.double_sided,
         }
     }
+
+    fn write_uniform(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+    }
 }
 
 //all the vertices and indices data of the model
@@ -140,8 +512,51 @@ pub struct Mesh {
     pub index_buffer: wgpu::Buffer,
     //how many elements there are
     pub num_elements: u32,
+    //whether the index buffer holds u16's or u32's - small meshes use u16's to halve their size
+    pub index_format: wgpu::IndexFormat,
     //the list index of the material texture for our elements
     pub material: usize,
+    //hidden meshes are skipped when the model is drawn (so parts of modular models can be turned on and off)
+    pub visible: bool,
+}
+
+impl Mesh {
+    //point the mesh at a different one of its model's materials - material_count is how many materials the model has
+    pub fn set_material(&mut self, material: usize, material_count: usize) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            material < material_count,
+            "material {material} is out of range (there are {material_count})"
+        );
+        self.material = material;
+        Ok(())
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+}
+
+//the transform of a model drawn without instances, as it is pushed to the shader (see DrawModel::draw_model_at)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ModelTransform {
+    model: [[f32; 4]; 4],
+    //each column of a mat3x3 takes up 16 bytes in the shader, so they are padded to 4 floats
+    normal: [[f32; 4]; 3],
+}
+
+impl ModelTransform {
+    //how many bytes of push constants the transform takes up
+    pub const SIZE: u32 = std::mem::size_of::<Self>() as u32;
+
+    pub fn new(transform: cgmath::Matrix4<f32>) -> Self {
+        //the same normal matrix instances get, so models drawn either way are lit the same
+        let normal: cgmath::Matrix3<f32> = crate::normal_matrix(transform);
+        Self {
+            model: transform.into(),
+            normal: [normal.x, normal.y, normal.z].map(|column| column.extend(0.0).into()),
+        }
+    }
 }
 
 //components needed to render our models to the screen
@@ -174,6 +589,36 @@ pub trait DrawModel<'a> {
         camera_bind_group: &'a wgpu::BindGroup,
         light_bind_group: &'a wgpu::BindGroup,
     );
+    fn draw_model_pass(
+        &mut self,
+        model: &'a Model,
+        pass: MaterialPass,
+        camera_bind_group: &'a wgpu::BindGroup,
+        light_bind_group: &'a wgpu::BindGroup,
+    );
+    fn draw_model_pass_instanced(
+        &mut self,
+        model: &'a Model,
+        pass: MaterialPass,
+        instances: Range<u32>,
+        camera_bind_group: &'a wgpu::BindGroup,
+        light_bind_group: &'a wgpu::BindGroup,
+    );
+    fn draw_model_at(
+        &mut self,
+        model: &'a Model,
+        transform: cgmath::Matrix4<f32>,
+        camera_bind_group: &'a wgpu::BindGroup,
+        light_bind_group: &'a wgpu::BindGroup,
+    );
+    fn draw_model_pass_at(
+        &mut self,
+        model: &'a Model,
+        pass: MaterialPass,
+        transform: cgmath::Matrix4<f32>,
+        camera_bind_group: &'a wgpu::BindGroup,
+        light_bind_group: &'a wgpu::BindGroup,
+    );
 }
 
 impl<'a, 'b> DrawModel<'b> for wgpu::RenderPass<'a>
@@ -201,7 +646,7 @@ where
         //tells wgpu what slice of the vertex buffer to use - here it's .. which means all of it
         self.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
         //tells wgpu where our index buffer is and what parts of it to use
-        self.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
         //tells wgu how to access textures
         self.set_bind_group(0, &material.bind_group, &[]);
         //tells wgu how to use apply the camera matrix
@@ -227,8 +672,44 @@ where
         camera_bind_group: &'b wgpu::BindGroup,
         light_bind_group: &'b wgpu::BindGroup,
     ) {
-        for mesh in &model.meshes {
-            let material: &Material = &model.materials[mesh.material];
+        //meshes pointing at a material that doesn't exist are skipped rather than panicking mid-frame
+        for mesh in model.meshes.iter().filter(|mesh| mesh.visible) {
+            let Some(material) = model.materials.get(mesh.material) else {
+                continue;
+            };
+            self.draw_mesh_instanced(
+                mesh,
+                material,
+                instances.clone(),
+                camera_bind_group,
+                light_bind_group,
+            );
+        }
+    }
+
+    //only draws the meshes whose material needs the pass's pipeline (which should already be set) - see MaterialPass
+    fn draw_model_pass(
+        &mut self,
+        model: &'b Model,
+        pass: MaterialPass,
+        camera_bind_group: &'b wgpu::BindGroup,
+        light_bind_group: &'b wgpu::BindGroup,
+    ) {
+        self.draw_model_pass_instanced(model, pass, 0..1, camera_bind_group, light_bind_group);
+    }
+
+    fn draw_model_pass_instanced(
+        &mut self,
+        model: &'b Model,
+        pass: MaterialPass,
+        instances: Range<u32>,
+        camera_bind_group: &'b wgpu::BindGroup,
+        light_bind_group: &'b wgpu::BindGroup,
+    ) {
+        for mesh in model.meshes_in_pass(pass) {
+            let Some(material) = model.materials.get(mesh.material) else {
+                continue;
+            };
             self.draw_mesh_instanced(
                 mesh,
                 material,
@@ -238,6 +719,38 @@ where
             );
         }
     }
+
+    //draws the model at the transform instead of at instances - the pipeline has to be one made for it (with vs_at from model_at.wgsl, and push constants in its layout)
+    fn draw_model_at(
+        &mut self,
+        model: &'b Model,
+        transform: cgmath::Matrix4<f32>,
+        camera_bind_group: &'b wgpu::BindGroup,
+        light_bind_group: &'b wgpu::BindGroup,
+    ) {
+        self.set_push_constants(
+            wgpu::ShaderStages::VERTEX,
+            0,
+            bytemuck::bytes_of(&ModelTransform::new(transform)),
+        );
+        self.draw_model(model, camera_bind_group, light_bind_group);
+    }
+
+    fn draw_model_pass_at(
+        &mut self,
+        model: &'b Model,
+        pass: MaterialPass,
+        transform: cgmath::Matrix4<f32>,
+        camera_bind_group: &'b wgpu::BindGroup,
+        light_bind_group: &'b wgpu::BindGroup,
+    ) {
+        self.set_push_constants(
+            wgpu::ShaderStages::VERTEX,
+            0,
+            bytemuck::bytes_of(&ModelTransform::new(transform)),
+        );
+        self.draw_model_pass(model, pass, camera_bind_group, light_bind_group);
+    }
 }
 pub trait DrawLight<'a> {
     fn draw_light_mesh(
@@ -290,7 +803,7 @@ where
         light_bind_group: &'b wgpu::BindGroup,
     ) {
         self.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-        self.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
         self.set_bind_group(0, camera_bind_group, &[]);
         self.set_bind_group(1, light_bind_group, &[]);
         self.draw_indexed(0..mesh.num_elements, 0, instances);
@@ -311,7 +824,7 @@ where
         camera_bind_group: &'b wgpu::BindGroup,
         light_bind_group: &'b wgpu::BindGroup,
     ) {
-        for mesh in &model.meshes {
+        for mesh in model.meshes.iter().filter(|mesh| mesh.visible) {
             self.draw_light_mesh_instanced(
                 mesh,
                 instances.clone(),
@@ -0,0 +1,135 @@
+//for drawing an outline around a selected instance, so it stands out from the rest
+
+use wgpu::util::DeviceExt;
+
+use crate::{model, model::Vertex, texture};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct OutlineUniform {
+    color: [f32; 4],
+    //how far (in world units) the outline sticks out from the model
+    width: f32,
+    //rounds the struct up to a whole 16 bytes (the size of color) - wgsl does the same to the struct, so without this the buffer would be smaller than the shader expects
+    _padding: [f32; 3],
+}
+
+//draws the back faces of a model pushed out along its normals in a flat colour - the front of the model covers the middle, leaving only a rim around its silhouette
+pub struct Outline {
+    uniform: OutlineUniform,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Outline {
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        reverse_z: bool,
+    ) -> anyhow::Result<Self> {
+        //an orange that stands out against most scenes
+        let uniform: OutlineUniform = OutlineUniform {
+            color: [1.0, 0.6, 0.1, 1.0],
+            width: 0.03,
+            _padding: [0.0; 3],
+        };
+        let uniform_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Outline Buffer"),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("outline_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let bind_group: wgpu::BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("outline_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Outline Pipeline Layout"),
+                bind_group_layouts: &[camera_bind_group_layout, &bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let shader = wgpu::ShaderModuleDescriptor {
+            label: Some("Outline Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/outline.wgsl").into()),
+        };
+
+        Ok(Self {
+            uniform,
+            uniform_buffer,
+            bind_group,
+            //front faces are culled, so only the (pushed out) back faces are left to peek out from behind the model
+            pipeline: crate::create_render_pipeline(
+                device,
+                &layout,
+                color_format,
+                Some(texture::Texture::DEPTH_FORMAT),
+                &[model::ModelVertex::desc(), crate::InstanceRaw::desc()],
+                crate::PipelineOptions {
+                    cull_mode: Some(wgpu::Face::Front),
+                    depth_compare: texture::Texture::depth_compare(reverse_z),
+                    ..Default::default()
+                },
+                shader,
+            )?,
+        })
+    }
+
+    pub fn set_color(&mut self, queue: &wgpu::Queue, color: [f32; 4]) {
+        self.uniform.color = color;
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+    }
+
+    pub fn set_width(&mut self, queue: &wgpu::Queue, width: f32) {
+        self.uniform.width = width;
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+    }
+
+    //draws the outline of one instance of the model - the instance buffer has to already be set in vertex slot 1
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        model: &'a model::Model,
+        instance: u32,
+        camera_bind_group: &'a wgpu::BindGroup,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        for mesh in model.meshes.iter().filter(|mesh| mesh.visible) {
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+            render_pass.draw_indexed(0..mesh.num_elements, 0, instance..instance + 1);
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//for effects made of lots of small, short lived sprites (sparks, smoke, dust) - the particles live in a gpu buffer that a compute shader moves on each frame, so the cpu only touches them when they are spawned
+
+use wgpu::util::DeviceExt;
+
+use crate::texture;
+
+//how many particles can be alive at once - spawning more than this replaces the oldest ones
+pub const MAX_PARTICLES: u32 = 16384;
+//how many particles each workgroup of the compute shader moves (has to match @workgroup_size in particles_update.wgsl)
+const WORKGROUP_SIZE: u32 = 64;
+
+//one particle, as the compute shader and vertex shader see it - 64 bytes, which lines up with wgsl's vec3 alignment
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Particle {
+    position: [f32; 3],
+    //seconds left until it disappears - dead particles have 0 or less, and aren't moved or drawn
+    life: f32,
+    velocity: [f32; 3],
+    //the life it started with, so it can fade out as it gets older
+    max_life: f32,
+    //added to the velocity every second (e.g. gravity)
+    acceleration: [f32; 3],
+    size: f32,
+    color: [f32; 4],
+}
+
+impl Particle {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x4,
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Particle>() as wgpu::BufferAddress,
+            //every particle is a quad of 6 vertices, all reading the same particle
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+//a burst of particles to spawn with State::spawn_particles - they start at the same place, each moving in a random direction around the velocity
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ParticleEmitter {
+    pub position: cgmath::Point3<f32>,
+    pub count: u32,
+    //the average velocity the particles start with
+    pub velocity: cgmath::Vector3<f32>,
+    //how fast (at most) each particle moves away from the average, in a random direction
+    pub spread: f32,
+    //how many seconds the particles last - each lasts somewhere between half of this and all of it, so they don't all vanish at once
+    pub lifetime: f32,
+    pub acceleration: cgmath::Vector3<f32>,
+    pub color: [f32; 4],
+    //how wide each particle is, in world units
+    pub size: f32,
+}
+
+impl ParticleEmitter {
+    //by default, white sparks thrown upwards that fall back down under gravity
+    pub fn new(position: cgmath::Point3<f32>, count: u32) -> Self {
+        Self {
+            position,
+            count,
+            velocity: cgmath::Vector3::new(0.0, 3.0, 0.0),
+            spread: 1.5,
+            lifetime: 2.0,
+            acceleration: cgmath::Vector3::new(0.0, -9.81, 0.0),
+            color: [1.0, 1.0, 1.0, 1.0],
+            size: 0.05,
+        }
+    }
+
+    pub fn with_velocity(mut self, velocity: cgmath::Vector3<f32>) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    //e.g. gravity for sparks, or a slight upwards drift for smoke
+    pub fn with_acceleration(mut self, acceleration: cgmath::Vector3<f32>) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    //the particles this emitter spawns - seed picks the random directions and lifetimes, so different bursts don't all look the same
+    fn particles(&self, seed: u32) -> Vec<Particle> {
+        //xorshift - plenty random enough for particles, without needing a dependency
+        let mut state: u32 = seed.max(1);
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            //between 0 and 1
+            (state >> 8) as f32 / (1 << 24) as f32
+        };
+
+        (0..self.count.min(MAX_PARTICLES))
+            .map(|_| {
+                //a random point in a cube is good enough - the particles end up in a fuzzy ball around the velocity
+                let offset: cgmath::Vector3<f32> = cgmath::Vector3::new(
+                    random() * 2.0 - 1.0,
+                    random() * 2.0 - 1.0,
+                    random() * 2.0 - 1.0,
+                ) * self.spread;
+                let life: f32 = self.lifetime * (0.5 + random() * 0.5);
+                Particle {
+                    position: self.position.into(),
+                    life,
+                    velocity: (self.velocity + offset).into(),
+                    max_life: life,
+                    acceleration: self.acceleration.into(),
+                    size: self.size,
+                    color: self.color,
+                }
+            })
+            .collect()
+    }
+}
+
+//the particle buffer, and the pipelines that move and draw it
+pub struct ParticleSystem {
+    //every particle slot, dead or alive - new particles overwrite the slots after the last ones spawned
+    buffer: wgpu::Buffer,
+    //the slot the next spawned particle goes in
+    next: u32,
+    //how many bursts have been spawned, to seed each one differently
+    spawned: u32,
+    //the time step for the compute shader
+    uniform_buffer: wgpu::Buffer,
+    compute_bind_group: wgpu::BindGroup,
+    compute_pipeline: wgpu::ComputePipeline,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl ParticleSystem {
+    //compute shaders aren't available on webgl
+    pub const DOWNLEVEL_FLAGS: wgpu::DownlevelFlags = wgpu::DownlevelFlags::COMPUTE_SHADERS;
+
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        reverse_z: bool,
+    ) -> anyhow::Result<Self> {
+        //every slot starts dead (with a life of 0)
+        let buffer: wgpu::Buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Buffer"),
+            size: (MAX_PARTICLES as usize * std::mem::size_of::<Particle>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        //the time step, padded out to the 16 bytes uniforms need
+        let uniform_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Particle Uniform Buffer"),
+                contents: bytemuck::cast_slice(&[0.0f32; 4]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let compute_bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("particle_compute_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let compute_bind_group: wgpu::BindGroup =
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("particle_compute_bind_group"),
+                layout: &compute_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+        let compute_layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Particle Compute Pipeline Layout"),
+                bind_group_layouts: &[&compute_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        //the same as create_render_pipeline, a shader that doesn't compile is returned as an error instead of panicking
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let compute_shader: wgpu::ShaderModule =
+            device.create_shader_module(wgpu::include_wgsl!("shaders/particles_update.wgsl"));
+        let compute_pipeline: wgpu::ComputePipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Particle Compute Pipeline"),
+                layout: Some(&compute_layout),
+                module: &compute_shader,
+                entry_point: "cs_main",
+            });
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!("couldn't create the particle pipeline: {error}");
+        }
+
+        let render_layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Particle Render Pipeline Layout"),
+                bind_group_layouts: &[camera_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let shader = wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/particles.wgsl").into()),
+        };
+        //blended like the see-through materials, and facing the camera, so their backs are never seen
+        let render_pipeline: wgpu::RenderPipeline = crate::create_render_pipeline(
+            device,
+            &render_layout,
+            color_format,
+            Some(texture::Texture::DEPTH_FORMAT),
+            &[Particle::desc()],
+            crate::PipelineOptions {
+                blend: wgpu::BlendState::ALPHA_BLENDING,
+                depth_write_enabled: false,
+                cull_mode: None,
+                depth_compare: texture::Texture::depth_compare(reverse_z),
+                ..Default::default()
+            },
+            shader,
+        )?;
+
+        Ok(Self {
+            buffer,
+            next: 0,
+            spawned: 0,
+            uniform_buffer,
+            compute_bind_group,
+            compute_pipeline,
+            render_pipeline,
+        })
+    }
+
+    //adds the emitter's particles after the last ones spawned, wrapping around to overwrite the oldest when the buffer is full
+    pub fn spawn(&mut self, queue: &wgpu::Queue, emitter: &ParticleEmitter) {
+        self.spawned = self.spawned.wrapping_add(1);
+        let particles: Vec<Particle> = emitter.particles(self.spawned.wrapping_mul(0x9e37_79b9));
+        let particle_size: wgpu::BufferAddress =
+            std::mem::size_of::<Particle>() as wgpu::BufferAddress;
+
+        //split into the part that fits before the end of the buffer, and the part that wraps around to the start
+        let (before_end, after_wrap): (&[Particle], &[Particle]) =
+            particles.split_at(((MAX_PARTICLES - self.next) as usize).min(particles.len()));
+        queue.write_buffer(
+            &self.buffer,
+            self.next as wgpu::BufferAddress * particle_size,
+            bytemuck::cast_slice(before_end),
+        );
+        if !after_wrap.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(after_wrap));
+        }
+        self.next = (self.next + particles.len() as u32) % MAX_PARTICLES;
+    }
+
+    //kills every particle
+    pub fn clear(&mut self, queue: &wgpu::Queue) {
+        let dead: Vec<Particle> = vec![bytemuck::Zeroable::zeroed(); MAX_PARTICLES as usize];
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&dead));
+        self.next = 0;
+    }
+
+    //moves every particle on by dt seconds on the gpu
+    pub fn update(&self, device: &wgpu::Device, queue: &wgpu::Queue, dt: instant::Duration) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[dt.as_secs_f32(), 0.0, 0.0, 0.0]),
+        );
+
+        let mut encoder: wgpu::CommandEncoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Particle Encoder"),
+            });
+        {
+            let mut compute_pass: wgpu::ComputePass =
+                encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Particle Pass"),
+                });
+            compute_pass.set_pipeline(&self.compute_pipeline);
+            compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
+            compute_pass.dispatch_workgroups(MAX_PARTICLES.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    //draws every living particle as a square facing the camera - the particle buffer is left in vertex slot 0
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera_bind_group: &'a wgpu::BindGroup,
+    ) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+        //dead particles are still drawn, but the vertex shader collapses them to nothing
+        render_pass.draw(0..6, 0..MAX_PARTICLES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emitted_particles_stay_within_the_emitters_ranges() {
+        let emitter: ParticleEmitter =
+            ParticleEmitter::new(cgmath::Point3::new(1.0, 2.0, 3.0), 100)
+                .with_velocity(cgmath::Vector3::new(0.0, 5.0, 0.0))
+                .with_spread(1.0)
+                .with_lifetime(4.0);
+        let particles: Vec<Particle> = emitter.particles(7);
+        assert_eq!(particles.len(), 100);
+        for particle in &particles {
+            assert_eq!(particle.position, [1.0, 2.0, 3.0]);
+            assert!(particle.life >= 2.0 && particle.life <= 4.0);
+            assert!(particle.velocity[1] >= 4.0 && particle.velocity[1] <= 6.0);
+            assert!(particle.velocity[0].abs() <= 1.0 && particle.velocity[2].abs() <= 1.0);
+        }
+        //a different seed gives different particles
+        assert_ne!(particles[0].velocity, emitter.particles(8)[0].velocity);
+    }
+}
@@ -0,0 +1,123 @@
+//creates each render pipeline once and hands back the same one whenever it is asked for again - pipelines made from the same shader share its module, and ones with the same layout share that too
+//only the main and light pipelines go through it, as they're the ones with many variants - the other passes (outline, grid, skybox, post-processing ect) each have one fixed pipeline of their own
+
+use std::collections::HashMap;
+
+use crate::PipelineOptions;
+
+//everything that makes one cached pipeline different from another - shader and layout are the names they were added to the cache with
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub shader: &'static str,
+    pub layout: &'static str,
+    pub color_format: wgpu::TextureFormat,
+    pub depth_format: Option<wgpu::TextureFormat>,
+    pub options: PipelineOptions,
+}
+
+//a pipeline layout, and the vertex buffers the pipelines made with it read
+struct Layout {
+    layout: wgpu::PipelineLayout,
+    vertex_layouts: Vec<wgpu::VertexBufferLayout<'static>>,
+}
+
+pub struct PipelineCache {
+    shaders: HashMap<&'static str, wgpu::ShaderModule>,
+    layouts: HashMap<&'static str, Layout>,
+    pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self {
+            shaders: HashMap::new(),
+            layouts: HashMap::new(),
+            pipelines: HashMap::new(),
+        }
+    }
+
+    //compiles a shader for pipelines to use - a shader that doesn't compile is returned as an error instead of panicking
+    pub fn add_shader(
+        &mut self,
+        device: &wgpu::Device,
+        name: &'static str,
+        shader: wgpu::ShaderModuleDescriptor,
+    ) -> anyhow::Result<()> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module: wgpu::ShaderModule = device.create_shader_module(shader);
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!("couldn't compile the {name} shader: {error}");
+        }
+        self.shaders.insert(name, module);
+        Ok(())
+    }
+
+    pub fn has_shader(&self, name: &str) -> bool {
+        self.shaders.contains_key(name)
+    }
+
+    pub fn add_layout(
+        &mut self,
+        name: &'static str,
+        layout: wgpu::PipelineLayout,
+        vertex_layouts: Vec<wgpu::VertexBufferLayout<'static>>,
+    ) {
+        self.layouts.insert(
+            name,
+            Layout {
+                layout,
+                vertex_layouts,
+            },
+        );
+    }
+
+    //the pipeline for the key, which is only created if it hasn't been already
+    pub fn get_or_create(
+        &mut self,
+        device: &wgpu::Device,
+        key: PipelineKey,
+    ) -> anyhow::Result<&wgpu::RenderPipeline> {
+        if !self.pipelines.contains_key(&key) {
+            let shader: &wgpu::ShaderModule = self
+                .shaders
+                .get(key.shader)
+                .ok_or_else(|| anyhow::anyhow!("there is no shader called {}", key.shader))?;
+            let layout: &Layout = self.layouts.get(key.layout).ok_or_else(|| {
+                anyhow::anyhow!("there is no pipeline layout called {}", key.layout)
+            })?;
+
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let pipeline: wgpu::RenderPipeline = crate::build_render_pipeline(
+                device,
+                &layout.layout,
+                key.color_format,
+                key.depth_format,
+                &layout.vertex_layouts,
+                key.options,
+                shader,
+            );
+            if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+                anyhow::bail!(
+                    "couldn't create a pipeline from the {} shader: {error}",
+                    key.shader
+                );
+            }
+            self.pipelines.insert(key, pipeline);
+        }
+        Ok(&self.pipelines[&key])
+    }
+
+    //the pipeline for the key, if get_or_create has already made it
+    pub fn get(&self, key: &PipelineKey) -> Option<&wgpu::RenderPipeline> {
+        self.pipelines.get(key)
+    }
+}
+
+//pipelines are looked up while drawing, when they can't be created any more - so the ones a frame needs have to have been made with get_or_create beforehand
+impl std::ops::Index<&PipelineKey> for PipelineCache {
+    type Output = wgpu::RenderPipeline;
+
+    fn index(&self, key: &PipelineKey) -> &Self::Output {
+        &self.pipelines[key]
+    }
+}
@@ -0,0 +1,234 @@
+//for applying full screen effects to the scene after it has been rendered
+
+use wgpu::util::DeviceExt;
+
+use crate::{fullscreen, render_target};
+
+//the effect applied to the scene before it is shown on the screen
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PostEffect {
+    //render straight to the screen, skipping the post-processing pass entirely
+    None,
+    //go through the post-processing pass without changing anything (useful for testing)
+    Passthrough,
+    Invert,
+    Grayscale,
+    //reinhard tonemapping, so bright values don't just clip to white
+    Tonemap,
+}
+
+impl PostEffect {
+    //the value the shader uses to pick the effect
+    fn shader_index(self) -> u32 {
+        match self {
+            PostEffect::None | PostEffect::Passthrough => 0,
+            PostEffect::Invert => 1,
+            PostEffect::Grayscale => 2,
+            PostEffect::Tonemap => 3,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniform {
+    effect: u32,
+    //multiplies the scene's colour before the effect (and tonemapping) is applied
+    exposure: f32,
+    //the display gamma - the surface already encodes for 2.2, so only the difference from that is applied
+    gamma: f32,
+    //the three fields above are 12 bytes - webgl needs uniform buffers to be a multiple of 16
+    _padding: u32,
+}
+
+//the default gamma leaves the colours exactly as the (srgb) surface would show them
+pub const DEFAULT_GAMMA: f32 = 2.2;
+
+//everything needed to draw the scene texture onto the screen with an effect applied
+pub struct PostProcess {
+    //the scene is rendered into this first, and then sampled from in the post-processing pass
+    pub target: render_target::RenderTarget,
+    //the format of what the pass draws to (the surface)
+    output_format: wgpu::TextureFormat,
+    //the size of the target compared to the surface - below 1.0 the scene is rendered smaller and upscaled by this pass
+    render_scale: f32,
+    effect: PostEffect,
+    exposure: f32,
+    gamma: f32,
+    uniform_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    //has to be re-created whenever the target is, as it points at the target's texture
+    bind_group: wgpu::BindGroup,
+    pass: fullscreen::FullscreenPass,
+}
+
+impl PostProcess {
+    //scene_format is what the scene gets rendered in - if it is different to the surface's format (e.g. hdr), the pass always has to run to convert it
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        scene_format: wgpu::TextureFormat,
+    ) -> anyhow::Result<Self> {
+        let target: render_target::RenderTarget =
+            render_target::RenderTarget::new(device, config.width, config.height, scene_format);
+
+        let effect: PostEffect = PostEffect::None;
+        let uniform_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Post Process Buffer"),
+                contents: bytemuck::cast_slice(&[PostUniform {
+                    effect: effect.shader_index(),
+                    exposure: 1.0,
+                    gamma: DEFAULT_GAMMA,
+                    _padding: 0,
+                }]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        //the scene texture, its sampler and which effect to use
+        let [texture_entry, sampler_entry]: [wgpu::BindGroupLayoutEntry; 2] =
+            fullscreen::texture_layout_entries();
+        let bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("post_process_bind_group_layout"),
+                entries: &[
+                    texture_entry,
+                    sampler_entry,
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let bind_group: wgpu::BindGroup =
+            Self::create_bind_group(device, &bind_group_layout, &target, &uniform_buffer);
+
+        let pass: fullscreen::FullscreenPass = fullscreen::FullscreenPass::new(
+            device,
+            "Post Process Pass",
+            &bind_group_layout,
+            config.format,
+            wgpu::ShaderModuleDescriptor {
+                label: Some("Post Process Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/postprocess.wgsl").into()),
+            },
+        )?;
+
+        Ok(Self {
+            target,
+            output_format: config.format,
+            render_scale: 1.0,
+            effect,
+            exposure: 1.0,
+            gamma: DEFAULT_GAMMA,
+            uniform_buffer,
+            bind_group_layout,
+            bind_group,
+            pass,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        target: &render_target::RenderTarget,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post_process_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target.color.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&target.color.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    pub fn effect(&self) -> PostEffect {
+        self.effect
+    }
+
+    //whether the scene needs to be rendered into the target rather than straight to the screen
+    pub fn is_enabled(&self) -> bool {
+        self.effect != PostEffect::None
+            || self.target.format != self.output_format
+            || self.render_scale != 1.0
+            || self.exposure != 1.0
+            || self.gamma != DEFAULT_GAMMA
+    }
+
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    //render the scene at a fraction (or multiple) of the surface's size - width and height are the surface's size
+    pub fn set_render_scale(&mut self, device: &wgpu::Device, scale: f32, width: u32, height: u32) {
+        self.render_scale = scale;
+        self.resize(device, width, height);
+    }
+
+    pub fn set_effect(&mut self, queue: &wgpu::Queue, effect: PostEffect) {
+        self.effect = effect;
+        self.write_uniform(queue);
+    }
+
+    pub fn set_exposure(&mut self, queue: &wgpu::Queue, exposure: f32) {
+        self.exposure = exposure;
+        self.write_uniform(queue);
+    }
+
+    pub fn set_gamma(&mut self, queue: &wgpu::Queue, gamma: f32) {
+        self.gamma = gamma;
+        self.write_uniform(queue);
+    }
+
+    fn write_uniform(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[PostUniform {
+                effect: self.effect.shader_index(),
+                exposure: self.exposure,
+                gamma: self.gamma,
+                _padding: 0,
+            }]),
+        );
+    }
+
+    //the target has to stay the same size as the screen (times the render scale)
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.target.resize(
+            device,
+            (width as f32 * self.render_scale).round() as u32,
+            (height as f32 * self.render_scale).round() as u32,
+        );
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.target,
+            &self.uniform_buffer,
+        );
+    }
+
+    //draws the target onto the output view with the current effect applied
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        self.pass.render(encoder, output, &self.bind_group);
+    }
+}
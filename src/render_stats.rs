@@ -0,0 +1,53 @@
+//for counting how much the gpu is asked to draw each frame, to see why a scene is slow
+
+use crate::model;
+
+//what the last frame asked the gpu to draw - only models are counted (our model and its instances, models drawn at a transform, the light's marker and the selection outline), not the skybox, grid, particles, debug lines or text
+//these are upper bounds rather than exact counts: they're added up next to each draw rather than by the draws themselves, and anything the gpu throws away (instances culled by gpu culling, triangles facing away or off screen) is still counted
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    //every instance of every mesh drawn, including the ones gpu culling skips (the cpu never finds out how many survived)
+    pub triangles: u64,
+}
+
+impl RenderStats {
+    //one draw call of elements indices, instances times - only triangle lists count towards the triangles
+    pub(crate) fn add_draw(
+        &mut self,
+        elements: u32,
+        instances: u32,
+        topology: wgpu::PrimitiveTopology,
+    ) {
+        self.draw_calls += 1;
+        if topology == wgpu::PrimitiveTopology::TriangleList {
+            self.triangles += (elements / 3) as u64 * instances as u64;
+        }
+    }
+
+    //a draw call for each of the meshes
+    pub(crate) fn add_meshes<'a>(
+        &mut self,
+        meshes: impl IntoIterator<Item = &'a model::Mesh>,
+        instances: u32,
+        topology: wgpu::PrimitiveTopology,
+    ) {
+        for mesh in meshes {
+            self.add_draw(mesh.num_elements, instances, topology);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_triangle_lists_count_triangles() {
+        let mut stats: RenderStats = RenderStats::default();
+        stats.add_draw(36, 100, wgpu::PrimitiveTopology::TriangleList);
+        stats.add_draw(24, 1, wgpu::PrimitiveTopology::LineList);
+        assert_eq!(stats.draw_calls, 2);
+        assert_eq!(stats.triangles, 1200);
+    }
+}
@@ -0,0 +1,52 @@
+//for rendering into a texture instead of straight to the window (for mirrors, minimaps, post-processing ect)
+
+use crate::texture;
+
+//an off-screen colour texture and its matching depth texture
+pub struct RenderTarget {
+    //what gets rendered into - can be sampled from like any other texture afterwards
+    pub color: texture::Texture,
+    //how depth is percieved when rendering into this target
+    pub depth: texture::Texture,
+    //the format of the colour texture - pipelines drawing into this target have to use the same format
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RenderTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        //a texture can't have a size of 0, so we make sure it is at least 1x1
+        let width: u32 = width.max(1);
+        let height: u32 = height.max(1);
+
+        Self {
+            color: texture::Texture::create_render_texture(
+                device,
+                width,
+                height,
+                format,
+                "render_target_color",
+            ),
+            depth: texture::Texture::create_depth_texture_with_size(
+                device,
+                width,
+                height,
+                "render_target_depth",
+            ),
+            format,
+            width,
+            height,
+        }
+    }
+
+    //re-creates the textures at a new size (the old contents are lost)
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        *self = Self::new(device, width, height, self.format);
+    }
+}
@@ -2,41 +2,103 @@
 
 use std::io::{BufReader, Cursor};
 
+use anyhow::Context;
 use cfg_if::cfg_if;
 use wgpu::util::DeviceExt;
 
-use crate::{model, texture};
+use crate::{animation, bounds, model, scene, skin, texture};
+
+//where assets are loaded from on the web, if set_asset_base has been called
+#[cfg(target_arch = "wasm32")]
+static ASSET_BASE: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+//load assets from url instead of the res path baked in at compile time (RES_PATH) - either a full url, or one relative to the page
+//it can only be set once, and has to be before anything is loaded
+#[cfg(target_arch = "wasm32")]
+pub fn set_asset_base(url: &str) -> anyhow::Result<()> {
+    ASSET_BASE
+        .set(url.trim_end_matches('/').to_string())
+        .map_err(|_| anyhow::anyhow!("the asset base has already been set"))
+}
 
 //on wasm only
 #[cfg(target_arch = "wasm32")]
 //get the url and search for the res directory
-fn format_url(file_name: &str) -> reqwest::Url {
-    let window = web_sys::window().unwrap();
+fn format_url(file_name: &str) -> anyhow::Result<reqwest::Url> {
+    let window = web_sys::window().ok_or_else(|| anyhow::anyhow!("there is no browser window"))?;
     let location = window.location();
-    let base = reqwest::Url::parse(&format!(
-        "{}/{}/",
-        location.href().unwrap(),
-        option_env!("RES_PATH").unwrap_or("res"),
-    ))
-    .unwrap();
-    base.join(file_name).unwrap()
+    let href: String = location
+        .href()
+        .map_err(|e| anyhow::anyhow!("couldn't get the page's url: {e:?}"))?;
+    let base: reqwest::Url = match ASSET_BASE.get() {
+        //joining keeps a full url as-is, and resolves anything else against the page
+        Some(base) => reqwest::Url::parse(&href)?.join(&format!("{base}/"))?,
+        None => reqwest::Url::parse(&format!(
+            "{}/{}/",
+            href,
+            option_env!("RES_PATH").unwrap_or("res"),
+        ))?,
+    };
+    Ok(base.join(file_name)?)
+}
+
+//where assets are loaded from on native - the copy of res/ build.rs makes, or with the hot-reload feature, the source res/ directory itself (so edited assets are picked up without a rebuild)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn res_dir() -> std::path::PathBuf {
+    cfg_if! {
+        if #[cfg(feature = "hot-reload")] {
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("res")
+        } else {
+            std::path::Path::new(env!("OUT_DIR")).join("res")
+        }
+    }
+}
+
+//every file in res/, generated by build.rs with the embed-assets feature
+//hot-reloading (on native) reads res/ from disk instead, as embedded files can't change - so embedding is skipped when both features are on
+#[cfg(all(
+    feature = "embed-assets",
+    not(all(feature = "hot-reload", not(target_arch = "wasm32")))
+))]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+    //the contents of a file in res/ that was baked into the binary
+    pub fn get(file_name: &str) -> Option<&'static [u8]> {
+        //file names can come from joining paths together (see sibling_file), so they may use backslashes or start with ./
+        let file_name: String = file_name.replace('\\', "/");
+        let file_name: &str = file_name.trim_start_matches("./");
+        ASSETS
+            .iter()
+            .find(|(name, _)| *name == file_name)
+            .map(|(_, data)| *data)
+    }
 }
 
 //get the text data from a file location (res/* )
 pub async fn load_string(file_name: &str) -> anyhow::Result<String> {
+    #[cfg(all(
+        feature = "embed-assets",
+        not(all(feature = "hot-reload", not(target_arch = "wasm32")))
+    ))]
+    if let Some(data) = embedded::get(file_name) {
+        return Ok(std::str::from_utf8(data)?.to_string());
+    }
+
     cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
-            let url = format_url(file_name);
-            let txt = reqwest::get(url)
-                .await?
+            let url = format_url(file_name)?;
+            let txt = reqwest::get(url.clone())
+                .await
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("couldn't fetch {url}"))?
                 .text()
                 .await?;
         } else {
-            let path = std::path::Path::new(env!("OUT_DIR"))
-                .join("res")
-                .join(&file_name);
+            let path = res_dir().join(file_name);
             // println!("str: {:?}", path);
-            let txt = std::fs::read_to_string(path)?;
+            let txt = std::fs::read_to_string(&path)
+                .with_context(|| format!("couldn't read {}", path.display()))?;
         }
     }
 
@@ -45,42 +107,158 @@ pub async fn load_string(file_name: &str) -> anyhow::Result<String> {
 
 //get the byte data from a file location (res/* )
 pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
+    #[cfg(all(
+        feature = "embed-assets",
+        not(all(feature = "hot-reload", not(target_arch = "wasm32")))
+    ))]
+    if let Some(data) = embedded::get(file_name) {
+        return Ok(data.to_vec());
+    }
+
     cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
-            let url = format_url(file_name);
-            let data = reqwest::get(url)
-                .await?
+            let url = format_url(file_name)?;
+            let data = reqwest::get(url.clone())
+                .await
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("couldn't fetch {url}"))?
                 .bytes()
                 .await?
                 .to_vec();
         } else {
-            let path = std::path::Path::new(env!("OUT_DIR"))
-                .join("res")
-                .join(&file_name);
+            let path = res_dir().join(file_name);
                 // println!("bin: {:?}", path);
-            let data = std::fs::read(path)?;
+            let data = std::fs::read(&path)
+                .with_context(|| format!("couldn't read {}", path.display()))?;
         }
     }
 
     Ok(data)
 }
 
+//the same as load_string, but from any path (e.g. one picked in a file dialog) rather than from res/ - on wasm there is no file system, so the path is treated as a url relative to res/ instead
+pub async fn load_string_path(path: &std::path::Path) -> anyhow::Result<String> {
+    cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            load_string(&path.to_string_lossy()).await
+        } else {
+            Ok(std::fs::read_to_string(path)?)
+        }
+    }
+}
+
+//the same as load_binary, but from any path rather than from res/ (see load_string_path)
+pub async fn load_binary_path(path: &std::path::Path) -> anyhow::Result<Vec<u8>> {
+    cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            load_binary(&path.to_string_lossy()).await
+        } else {
+            Ok(std::fs::read(path)?)
+        }
+    }
+}
+
+//the name of a file referenced by another (like an .obj's .mtl, or a .mtl's textures), which is relative to the directory the referencing file is in
+fn sibling_file(file_name: &str, referenced: &str) -> String {
+    std::path::Path::new(file_name)
+        .with_file_name(referenced)
+        .to_string_lossy()
+        .into_owned()
+}
+
 //load a texture into a specified queue on a device from a filename (res/* ) - a replacement for include_bytes!() macro, as it requires us to know the filename when compiling
 pub async fn load_texture(
     file_name: &str,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    is_normal_map: bool,
+    color_space: texture::ColorSpace,
+    sampler: texture::SamplerOptions,
 ) -> anyhow::Result<texture::Texture> {
+    //compressed textures are created with the default sampler, so get the asked for one afterwards
+    let with_sampler = |mut texture: texture::Texture| {
+        texture.set_sampler(device, sampler);
+        texture
+    };
+
+    let path: &std::path::Path = std::path::Path::new(file_name);
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "ktx2")
+    {
+        let data: Vec<u8> = load_binary(file_name).await?;
+        return texture::Texture::from_ktx2(device, queue, &data, file_name).map(with_sampler);
+    }
+
+    if path.extension().is_some_and(|extension| extension == "dds") {
+        let data: Vec<u8> = load_binary(file_name).await?;
+        return texture::Texture::from_dds(device, queue, &data, file_name, color_space)
+            .map(with_sampler);
+    }
+
+    //high dynamic range images get loaded into a float texture instead of being squashed into 8 bits
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "hdr" || extension == "exr")
+    {
+        let data: Vec<u8> = load_binary(file_name).await?;
+        return texture::Texture::from_hdr_bytes(device, queue, &data, file_name);
+    }
+
+    //compressed textures use far less vram, so if the gpu supports them, prefer a .ktx2 version of the texture sitting next to the original (if there is one)
+    #[cfg(feature = "ktx2-siblings")]
+    if device
+        .features()
+        .contains(wgpu::Features::TEXTURE_COMPRESSION_BC)
+    {
+        if let Some(ktx2_name) = path.with_extension("ktx2").to_str() {
+            if let Ok(data) = load_binary(ktx2_name).await {
+                return texture::Texture::from_ktx2(device, queue, &data, ktx2_name)
+                    .map(with_sampler);
+            }
+        }
+    }
+
     let data: Vec<u8> = load_binary(file_name).await?;
-    texture::Texture::from_bytes(device, queue, &data, file_name, is_normal_map)
+    texture::Texture::from_bytes(device, queue, &data, file_name, color_space, sampler)
 }
 
+//load six images (res/* ) into a cubemap - in the order +x, -x, +y, -y, +z, -z
+pub async fn load_cubemap(
+    file_names: [&str; 6],
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> anyhow::Result<texture::Texture> {
+    let mut faces: Vec<Vec<u8>> = Vec::with_capacity(6);
+    for file_name in file_names {
+        faces.push(load_binary(file_name).await?);
+    }
+    texture::Texture::from_cubemap(
+        device,
+        queue,
+        [
+            &faces[0], &faces[1], &faces[2], &faces[3], &faces[4], &faces[5],
+        ],
+        file_names[0],
+    )
+}
+
+//how .obj files are read unless told otherwise - faces are split into triangles, with one index per vertex (which is what the renderer draws)
+pub fn default_obj_load_options() -> tobj::LoadOptions {
+    tobj::LoadOptions {
+        //turns points and lines into zero area triangles
+        triangulate: true,
+        single_index: true,
+        ..Default::default()
+    }
+}
+
+//options can be anything tobj accepts - meshes that still have polygons, or separate normal/uv indices, are turned into single indexed triangles after loading
 pub async fn load_obj_model(
     file_name: &str,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     layout: &wgpu::BindGroupLayout,
+    options: &tobj::LoadOptions,
 ) -> anyhow::Result<model::Model> {
     let obj_text: String = load_string(file_name).await?;
     let obj_cursor: Cursor<String> = Cursor::new(obj_text);
@@ -89,70 +267,185 @@ pub async fn load_obj_model(
 
     //models: a list of the models that will be imported from our .obj file
     //obj_materials: a list of the textures that will be imported from the references in the .mtl file
-    let (models, obj_materials) = tobj::load_obj_buf_async(
-        &mut obj_reader,
-        &tobj::LoadOptions {
-            //turns points and lines into zero area triangles
-            triangulate: true,
-            single_index: true,
-            ..Default::default()
-        },
-        |p| async move {
-            let mat_text: String = load_string(&p).await.unwrap();
+    let (models, obj_materials) =
+        tobj::load_obj_buf_async(&mut obj_reader, options, |p| async move {
+            //tobj's errors can't carry why the .mtl couldn't be loaded, so that is logged instead
+            let mat_text: String = match load_string(&sibling_file(file_name, &p)).await {
+                Ok(mat_text) => mat_text,
+                Err(e) => {
+                    log::error!("{e:?}");
+                    return Err(tobj::LoadError::OpenFileFailed);
+                }
+            };
             //loads the texture material data from the models .mtl file
             tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text)))
-        },
-    )
-    .await?;
+        })
+        .await
+        .with_context(|| format!("couldn't parse {file_name}"))?;
 
     let mut materials: Vec<model::Material> = Vec::new();
     //consatruct the actual texture materials from the file and index references in the .mtl file
-    for mat in obj_materials? {
-        let diffuse_texture: texture::Texture =
-            load_texture(&mat.diffuse_texture, device, queue, true).await?;
+    for mat in
+        obj_materials.with_context(|| format!("couldn't load the materials of {file_name}"))?
+    {
+        let diffuse_texture: texture::Texture = load_texture(
+            &sibling_file(file_name, &mat.diffuse_texture),
+            device,
+            queue,
+            texture::ColorSpace::Srgb,
+            texture::SamplerOptions::default(),
+        )
+        .await?;
+
+        let normal_texture: texture::Texture = load_texture(
+            &sibling_file(file_name, &mat.normal_texture),
+            device,
+            queue,
+            texture::ColorSpace::Linear,
+            texture::SamplerOptions::default(),
+        )
+        .await?;
+
+        //.obj files don't have metallic/roughness maps, so the factors below are all there is
+        let metallic_roughness_texture: texture::Texture = texture::Texture::from_color(
+            device,
+            queue,
+            [255; 4],
+            &format!("{} (Metallic Roughness)", mat.name),
+        )?;
 
-        let normal_texture: texture::Texture =
-            load_texture(&mat.normal_texture, device, queue, true).await?;
+        //.mtl has no occlusion map of its own, so it is either given as a map_ao extension, or exported as the ambient map (map_Ka)
+        let occlusion_file: Option<&String> = mat
+            .unknown_param
+            .get("map_ao")
+            .or(Some(&mat.ambient_texture))
+            .filter(|file| !file.is_empty());
+        let occlusion_texture: texture::Texture = match occlusion_file {
+            //occlusion is data rather than a colour, so it is loaded linear like a normal map
+            Some(file) => {
+                load_texture(
+                    &sibling_file(file_name, file),
+                    device,
+                    queue,
+                    texture::ColorSpace::Linear,
+                    texture::SamplerOptions::default(),
+                )
+                .await?
+            }
+            None => texture::Texture::from_color(
+                device,
+                queue,
+                [255; 4],
+                &format!("{} (Occlusion)", mat.name),
+            )?,
+        };
 
-        materials.push(model::Material::new(
+        let mut material: model::Material = model::Material::new(
             device,
             &mat.name,
             diffuse_texture,
             normal_texture,
+            metallic_roughness_texture,
+            occlusion_texture,
             layout,
-        ));
+        );
+        //the pbr extension to .mtl adds Pm (metallic) and Pr (roughness) - if a material has either, it was made for pbr lighting
+        let metallic: Option<f32> = mat.unknown_param.get("Pm").and_then(|v| v.parse().ok());
+        let roughness: Option<f32> = mat.unknown_param.get("Pr").and_then(|v| v.parse().ok());
+        if metallic.is_some() || roughness.is_some() {
+            material.set_metallic_roughness(
+                queue,
+                metallic.unwrap_or(0.0),
+                roughness.unwrap_or(0.5),
+            );
+            material.set_shading_model(queue, model::ShadingModel::Pbr);
+        }
+        //Ns is the specular exponent - tobj leaves it at 0 when it is missing, so only a real one replaces the default
+        if mat.shininess > 0.0 {
+            material.set_shininess(queue, mat.shininess);
+        }
+        //d (dissolve) is how solid the whole material is, and a map_d texture means parts of it are see-through
+        if mat.dissolve < 1.0 {
+            material.set_opacity(queue, mat.dissolve);
+        }
+        if !mat.dissolve_texture.is_empty() {
+            material.transparent = true;
+        }
+        materials.push(material);
     }
 
+    let bounds: bounds::Aabb = bounds::Aabb::from_points(models.iter().flat_map(|m| {
+        m.mesh
+            .positions
+            .chunks_exact(3)
+            .map(|position| cgmath::Point3::new(position[0], position[1], position[2]))
+    }));
+
+    //models made of nothing but points or lines (which tobj only keeps when triangulate is off) are drawn as them instead of triangles
+    let topology: wgpu::PrimitiveTopology = model_topology(&models);
+
     let meshes: Vec<model::Mesh> = models
         .into_iter()
-        .map(|mat| {
+        .map(|mut mat| {
+            mat.mesh = single_indexed(mat.mesh, &mat.name, topology);
+            //without normals everything would be lit black
+            fill_missing_normals(&mut mat.mesh, topology);
+
             // println!("{}", mat.mesh.texcoords.len() / 2);
             // println!("{}", mat.mesh.positions.len() / 3);
 
             //divide the mesh positions from the .obj file into groups of 3 f32 for the ModelVertex struct (as they are flattened and must be re-grouped into their 3d space positions)
             let mut vertices: Vec<model::ModelVertex> = (0..mat.mesh.positions.len() / 3)
-                .map(|i| model::ModelVertex {
-                    position: [
-                        //as they are in groups of 3, the i * 3 is needed to ensure we are skipping properly over positions
-                        mat.mesh.positions[i * 3],
-                        mat.mesh.positions[i * 3 + 1],
-                        mat.mesh.positions[i * 3 + 2],
-                    ],
-                    //same as position but only i * 2 as textures are 2d
-                    tex_coords: [mat.mesh.texcoords[i * 2], mat.mesh.texcoords[i * 2 + 1]],
-                    //the normal texture mappings are 3d, as they are how the entire object is lit
-                    normal: [
-                        mat.mesh.normals[i * 3],
-                        mat.mesh.normals[i * 3 + 1],
-                        mat.mesh.normals[i * 3 + 2],
-                    ],
-                    // We'll calculate these later
-                    tangent: [0.0; 3],
-                    bitangent: [0.0; 3],
+                .map(|i| {
+                    //same as position but only i * 2 as textures are 2d - models without uvs just sample the corner of the texture
+                    let tex_coords: [f32; 2] = if mat.mesh.texcoords.is_empty() {
+                        [0.0; 2]
+                    } else {
+                        [mat.mesh.texcoords[i * 2], mat.mesh.texcoords[i * 2 + 1]]
+                    };
+                    model::ModelVertex {
+                        position: [
+                            //as they are in groups of 3, the i * 3 is needed to ensure we are skipping properly over positions
+                            mat.mesh.positions[i * 3],
+                            mat.mesh.positions[i * 3 + 1],
+                            mat.mesh.positions[i * 3 + 2],
+                        ],
+                        tex_coords,
+                        //the normal texture mappings are 3d, as they are how the entire object is lit
+                        normal: [
+                            mat.mesh.normals[i * 3],
+                            mat.mesh.normals[i * 3 + 1],
+                            mat.mesh.normals[i * 3 + 2],
+                        ],
+                        // We'll calculate these later
+                        tangent: [0.0; 3],
+                        bitangent: [0.0; 3],
+                        //.obj vertex colours are rgb only, and most models don't have any - default to opaque white so the texture is left unchanged
+                        color: if mat.mesh.vertex_color.is_empty() {
+                            [1.0; 4]
+                        } else {
+                            [
+                                mat.mesh.vertex_color[i * 3],
+                                mat.mesh.vertex_color[i * 3 + 1],
+                                mat.mesh.vertex_color[i * 3 + 2],
+                                1.0,
+                            ]
+                        },
+                        //.obj files only have one set of texture coordinates
+                        tex_coords1: tex_coords,
+                        //.obj files can't be skinned
+                        joints: [0; 4],
+                        weights: [0.0; 4],
+                    }
                 })
                 .collect::<Vec<_>>();
 
-            let indices: &Vec<u32> = &mat.mesh.indices;
+            //points and lines have no surface to solve the uvs across, so all of their vertices get fallback tangents
+            let indices: &[u32] = if topology == wgpu::PrimitiveTopology::TriangleList {
+                &mat.mesh.indices
+            } else {
+                &[]
+            };
             let mut triangles_included: Vec<i32> = vec![0; vertices.len()];
 
             //calculate tangents and bitangets - we're going to use the triangles, so we need to loop through the indices in chunks of 3
@@ -183,7 +476,15 @@ pub async fn load_obj_model(
                 //solving the following system of equations will give us the tangent and bitangent.
                 //    delta_pos1 = delta_uv1.x * T + delta_u.y * B
                 //    delta_pos2 = delta_uv2.x * T + delta_uv2.y * B
-                let r: f32 = 1.0 / (delta_uv1.x * delta_uv2.y - delta_uv1.y * delta_uv2.x);
+                let determinant: f32 = delta_uv1.x * delta_uv2.y - delta_uv1.y * delta_uv2.x;
+                //missing or degenerate uvs (all the same, or in a line) can't be solved and would give NaN tangents - those vertices get a fallback tangent below instead
+                //the determinant is the uv edges' cross product, so it's compared against their lengths - a fixed threshold would also throw away small but fine uvs (a tiny part of an atlas)
+                let uv_scale: f32 = cgmath::InnerSpace::magnitude(delta_uv1)
+                    * cgmath::InnerSpace::magnitude(delta_uv2);
+                if !determinant.is_finite() || determinant.abs() <= uv_scale * f32::EPSILON {
+                    continue;
+                }
+                let r: f32 = 1.0 / determinant;
                 let tangent: cgmath::Vector3<f32> =
                     (delta_pos1 * delta_uv2.y - delta_pos2 * delta_uv1.y) * r;
                 // We flip the bitangent to enable right-handed normal
@@ -213,38 +514,734 @@ pub async fn load_obj_model(
 
             //average the tangents/bitangents
             for (i, n) in triangles_included.into_iter().enumerate() {
-                let denom: f32 = 1.0 / n as f32;
                 let mut v: &mut model::ModelVertex = &mut vertices[i];
-                v.tangent = (cgmath::Vector3::from(v.tangent) * denom).into();
+                let tangent: cgmath::Vector3<f32> = cgmath::Vector3::from(v.tangent);
+                //vertices with no solvable triangles (or whose tangents cancelled out) still need some tangent space for normal mapping
+                if n == 0 || cgmath::InnerSpace::magnitude2(tangent) < f32::EPSILON {
+                    let (tangent, bitangent) = fallback_tangents(v.normal.into());
+                    v.tangent = tangent.into();
+                    v.bitangent = bitangent.into();
+                    continue;
+                }
+                let denom: f32 = 1.0 / n as f32;
+                v.tangent = (tangent * denom).into();
                 v.bitangent = (cgmath::Vector3::from(v.bitangent) * denom).into();
             }
 
-            //a buffer to store the vertex data we want to draw (so we don't have to expensively recomplie the shader on every update)
-            let vertex_buffer: wgpu::Buffer =
-                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("{:?} (Vertex Buffer)", file_name)),
-                    //cast to &[u8] as that is how gpu buffers typically expect buffer data
-                    contents: bytemuck::cast_slice(&vertices),
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-
-            //means that we don't have duplicate vertices, and instead just have a list of their positions that we then render (which saves memory)
-            let index_buffer: wgpu::Buffer =
-                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("{:?} (Index Buffer)", file_name)),
-                    contents: bytemuck::cast_slice(&mat.mesh.indices),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
-
-            model::Mesh {
-                label: file_name.to_string(),
-                vertex_buffer,
-                index_buffer,
-                num_elements: mat.mesh.indices.len() as u32,
-                material: mat.mesh.material_id.unwrap_or(0),
-            }
+            create_mesh(
+                device,
+                file_name,
+                &vertices,
+                &mat.mesh.indices,
+                mat.mesh.material_id.unwrap_or(0),
+            )
         })
         .collect::<Vec<_>>();
 
-    Ok(model::Model { meshes, materials })
+    Ok(model::Model {
+        meshes,
+        materials,
+        animations: Vec::new(),
+        bounds,
+        topology,
+        skin: None,
+    })
+}
+
+//puts the vertices and indices into buffers on the gpu
+fn create_mesh(
+    device: &wgpu::Device,
+    label: &str,
+    vertices: &[model::ModelVertex],
+    indices: &[u32],
+    material: usize,
+) -> model::Mesh {
+    //a buffer to store the vertex data we want to draw (so we don't have to expensively recomplie the shader on every update)
+    let vertex_buffer: wgpu::Buffer =
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} (Vertex Buffer)", label)),
+            //cast to &[u8] as that is how gpu buffers typically expect buffer data
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+    //means that we don't have duplicate vertices, and instead just have a list of their positions that we then render (which saves memory)
+    //if every index fits in a u16 we store them as u16's instead, which halves the buffer's size (and the bandwidth used reading it)
+    let (index_data, index_format): (Vec<u8>, wgpu::IndexFormat) =
+        if indices.iter().all(|&index| index <= u16::MAX as u32) {
+            let indices: Vec<u16> = indices.iter().map(|&index| index as u16).collect();
+            (
+                bytemuck::cast_slice(&indices).to_vec(),
+                wgpu::IndexFormat::Uint16,
+            )
+        } else {
+            (
+                bytemuck::cast_slice(indices).to_vec(),
+                wgpu::IndexFormat::Uint32,
+            )
+        };
+    let index_buffer: wgpu::Buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{:?} (Index Buffer)", label)),
+        contents: &index_data,
+        usage: wgpu::BufferUsages::INDEX,
+    });
+
+    model::Mesh {
+        label: label.to_string(),
+        vertex_buffer,
+        index_buffer,
+        num_elements: indices.len() as u32,
+        index_format,
+        material,
+        visible: true,
+    }
+}
+
+//points if every face of every mesh is a single point, lines if they are all lines, and otherwise triangles (where any points and lines are left out)
+fn model_topology(models: &[tobj::Model]) -> wgpu::PrimitiveTopology {
+    let all_faces_are = |arity: u32| {
+        !models.is_empty()
+            && models.iter().all(|m| {
+                !m.mesh.face_arities.is_empty()
+                    && m.mesh.face_arities.iter().all(|face| *face == arity)
+            })
+    };
+    if all_faces_are(1) {
+        wgpu::PrimitiveTopology::PointList
+    } else if all_faces_are(2) {
+        wgpu::PrimitiveTopology::LineList
+    } else {
+        wgpu::PrimitiveTopology::TriangleList
+    }
+}
+
+//a copy of the mesh as a list of the topology (see model_topology) with one index per vertex - what the rest of the loader expects, but not what tobj gives without triangulate and single_index
+//every corner of every face gets its own vertex, so shared vertices are duplicated (a mesh that already is one is returned as-is)
+fn single_indexed(mesh: tobj::Mesh, name: &str, topology: wgpu::PrimitiveTopology) -> tobj::Mesh {
+    if mesh.face_arities.is_empty()
+        && mesh.normal_indices.is_empty()
+        && mesh.texcoord_indices.is_empty()
+    {
+        return mesh;
+    }
+
+    //no face_arities means every face is already a triangle
+    let arities: Vec<u32> = if mesh.face_arities.is_empty() {
+        vec![3; mesh.indices.len() / 3]
+    } else {
+        mesh.face_arities.clone()
+    };
+
+    //each polygon is split into a fan of triangles around its first corner - points and lines have no area to draw, so are left out
+    let mut corners: Vec<usize> = Vec::new();
+    let mut skipped: usize = 0;
+    let mut start: usize = 0;
+    for arity in arities {
+        let arity: usize = arity as usize;
+        //a point or line model's faces are all points or lines already
+        if topology != wgpu::PrimitiveTopology::TriangleList {
+            corners.extend(start..start + arity);
+            start += arity;
+            continue;
+        }
+        if arity < 3 {
+            skipped += 1;
+        }
+        for i in 1..arity.saturating_sub(1) {
+            corners.extend([start, start + i, start + i + 1]);
+        }
+        start += arity;
+    }
+    if skipped > 0 {
+        log::warn!("{name}: skipped {skipped} points/lines, as only triangles can be drawn");
+    }
+
+    //without their own indices, normals and uvs share the positions' ones
+    let copy = |values: &[f32], indices: &[u32], size: usize| -> Vec<f32> {
+        if values.is_empty() {
+            return Vec::new();
+        }
+        corners
+            .iter()
+            .flat_map(|&corner| {
+                let index: usize = *indices.get(corner).unwrap_or(&mesh.indices[corner]) as usize;
+                values[index * size..index * size + size].iter().copied()
+            })
+            .collect()
+    };
+
+    tobj::Mesh {
+        positions: copy(&mesh.positions, &mesh.indices, 3),
+        vertex_color: copy(&mesh.vertex_color, &mesh.indices, 3),
+        normals: copy(&mesh.normals, &mesh.normal_indices, 3),
+        texcoords: copy(&mesh.texcoords, &mesh.texcoord_indices, 2),
+        indices: (0..corners.len() as u32).collect(),
+        material_id: mesh.material_id,
+        ..Default::default()
+    }
+}
+
+//gives vertices without a (non-zero) normal the average of the normals of the triangles they are part of - if the mesh has no normals at all, every vertex gets one
+fn fill_missing_normals(mesh: &mut tobj::Mesh, topology: wgpu::PrimitiveTopology) {
+    use cgmath::InnerSpace;
+
+    let missing = |normal: &[f32]| normal.iter().all(|n| *n == 0.0);
+    if mesh.normals.len() == mesh.positions.len() && !mesh.normals.chunks(3).any(missing) {
+        return;
+    }
+    if mesh.normals.len() != mesh.positions.len() {
+        mesh.normals = vec![0.0; mesh.positions.len()];
+    }
+
+    let position = |i: u32| -> cgmath::Vector3<f32> {
+        let i: usize = i as usize * 3;
+        cgmath::Vector3::new(
+            mesh.positions[i],
+            mesh.positions[i + 1],
+            mesh.positions[i + 2],
+        )
+    };
+    let mut normals: Vec<cgmath::Vector3<f32>> =
+        vec![cgmath::Vector3::new(0.0, 0.0, 0.0); mesh.positions.len() / 3];
+
+    //points and lines aren't part of any triangles
+    let triangles: &[u32] = if topology == wgpu::PrimitiveTopology::TriangleList {
+        &mesh.indices
+    } else {
+        &[]
+    };
+
+    //the cross product of two edges is perpendicular to the triangle - it isn't normalised, so bigger triangles count for more in the average
+    for c in triangles.chunks_exact(3) {
+        let pos0: cgmath::Vector3<f32> = position(c[0]);
+        let pos1: cgmath::Vector3<f32> = position(c[1]);
+        let pos2: cgmath::Vector3<f32> = position(c[2]);
+        let normal: cgmath::Vector3<f32> = (pos1 - pos0).cross(pos2 - pos0);
+
+        normals[c[0] as usize] += normal;
+        normals[c[1] as usize] += normal;
+        normals[c[2] as usize] += normal;
+    }
+
+    //average the normals, only replacing the missing ones
+    for (i, normal) in normals.into_iter().enumerate() {
+        let existing: &mut [f32] = &mut mesh.normals[i * 3..i * 3 + 3];
+        if !missing(existing) {
+            continue;
+        }
+        //vertices that aren't part of any triangle (or whose triangles cancelled out) just point up
+        let normal: cgmath::Vector3<f32> = if normal.magnitude2() < f32::EPSILON {
+            cgmath::Vector3::unit_y()
+        } else {
+            normal.normalize()
+        };
+        existing.copy_from_slice(&[normal.x, normal.y, normal.z]);
+    }
+}
+
+//an arbitrary tangent and bitangent perpendicular to the normal, for when the uvs can't give us real ones
+fn fallback_tangents(normal: cgmath::Vector3<f32>) -> (cgmath::Vector3<f32>, cgmath::Vector3<f32>) {
+    use cgmath::InnerSpace;
+
+    //a normal of 0 has no perpendicular, so just pretend it points along z
+    let normal: cgmath::Vector3<f32> = if normal.magnitude2() < f32::EPSILON {
+        cgmath::Vector3::unit_z()
+    } else {
+        normal.normalize()
+    };
+    //gram-schmidt - take any axis that isn't (nearly) parallel to the normal, and remove the part of it along the normal
+    let axis: cgmath::Vector3<f32> = if normal.x.abs() < 0.9 {
+        cgmath::Vector3::unit_x()
+    } else {
+        cgmath::Vector3::unit_y()
+    };
+    let tangent: cgmath::Vector3<f32> = (axis - normal * normal.dot(axis)).normalize();
+    (tangent, normal.cross(tangent))
+}
+
+//the same as load_obj_model, but from any path rather than from res/ - its .mtl and textures are loaded from the same directory as it
+pub async fn load_obj_model_path(
+    path: &std::path::Path,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    options: &tobj::LoadOptions,
+) -> anyhow::Result<model::Model> {
+    //joining an absolute path onto res/ gives back the absolute path, so the res/ loaders can be re-used for everything the model references
+    #[cfg(not(target_arch = "wasm32"))]
+    let path: std::path::PathBuf = std::env::current_dir()?.join(path);
+    load_obj_model(&path.to_string_lossy(), device, queue, layout, options).await
+}
+
+//the raw data a gltf's meshes and animations are stored in - either inside the .glb itself, or in files next to the .gltf
+async fn load_gltf_buffers(gltf: &gltf::Gltf, file_name: &str) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+    for buffer in gltf.buffers() {
+        buffers.push(match buffer.source() {
+            gltf::buffer::Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("{file_name} has no binary chunk"))?,
+            gltf::buffer::Source::Uri(uri) => {
+                anyhow::ensure!(
+                    !uri.starts_with("data:"),
+                    "{file_name}: embedded (data uri) buffers aren't supported"
+                );
+                let path = std::path::Path::new(file_name).with_file_name(uri);
+                load_binary(&path.to_string_lossy()).await?
+            }
+        });
+    }
+    Ok(buffers)
+}
+
+//load every mesh of a gltf (.gltf or .glb) file into one model, along with its first skin - materials only use their colour and metallic/roughness factors (textures aren't loaded yet)
+//the scene graph that places it, and the animations that move its joints, come from load_gltf_animations
+pub async fn load_gltf_model(
+    file_name: &str,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+) -> anyhow::Result<model::Model> {
+    let gltf: gltf::Gltf = gltf::Gltf::from_slice(&load_binary(file_name).await?)?;
+    let buffers: Vec<Vec<u8>> = load_gltf_buffers(&gltf, file_name).await?;
+
+    //primitives without a material use the one after the file's own
+    let mut materials: Vec<model::Material> = Vec::new();
+    for gltf_material in gltf.materials().map(Some).chain(std::iter::once(None)) {
+        let name: String = gltf_material
+            .as_ref()
+            .and_then(|m| m.name())
+            .unwrap_or("default")
+            .to_string();
+        let pbr = gltf_material.as_ref().map(|m| m.pbr_metallic_roughness());
+        let color: [f32; 4] = pbr
+            .as_ref()
+            .map(|pbr| pbr.base_color_factor())
+            .unwrap_or([1.0; 4]);
+        let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        let mut material: model::Material = model::Material::new(
+            device,
+            &name,
+            texture::Texture::from_color(
+                device,
+                queue,
+                color.map(to_byte),
+                &format!("{name} (Diffuse)"),
+            )?,
+            //pointing straight out of the surface
+            texture::Texture::from_color(
+                device,
+                queue,
+                [128, 128, 255, 255],
+                &format!("{name} (Normal)"),
+            )?,
+            texture::Texture::from_color(
+                device,
+                queue,
+                [255; 4],
+                &format!("{name} (Metallic Roughness)"),
+            )?,
+            texture::Texture::from_color(device, queue, [255; 4], &format!("{name} (Occlusion)"))?,
+            layout,
+        );
+        if let (Some(gltf_material), Some(pbr)) = (&gltf_material, &pbr) {
+            material.set_shading_model(queue, model::ShadingModel::Pbr);
+            material.set_metallic_roughness(queue, pbr.metallic_factor(), pbr.roughness_factor());
+            material.set_double_sided(gltf_material.double_sided());
+            material.transparent = gltf_material.alpha_mode() == gltf::material::AlphaMode::Blend;
+        }
+        materials.push(material);
+    }
+    let default_material: usize = materials.len() - 1;
+
+    let mut meshes: Vec<model::Mesh> = Vec::new();
+    let mut points: Vec<cgmath::Point3<f32>> = Vec::new();
+    for gltf_mesh in gltf.meshes() {
+        let label: String = format!(
+            "{file_name}: {}",
+            gltf_mesh.name().unwrap_or(&gltf_mesh.index().to_string())
+        );
+        for primitive in gltf_mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                log::warn!("{label}: skipped a primitive that isn't a triangle list");
+                continue;
+            }
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let positions: Vec<[f32; 3]> = match reader.read_positions() {
+                Some(positions) => positions.collect(),
+                None => continue,
+            };
+            let count: usize = positions.len();
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..count as u32).collect(),
+            };
+
+            //the normals are filled in (and checked) the same way as an .obj's
+            let mut mesh: tobj::Mesh = tobj::Mesh {
+                positions: positions.iter().flatten().copied().collect(),
+                normals: reader
+                    .read_normals()
+                    .map(|normals| normals.flatten().collect())
+                    .unwrap_or_default(),
+                indices,
+                ..Default::default()
+            };
+            fill_missing_normals(&mut mesh, wgpu::PrimitiveTopology::TriangleList);
+
+            let tex_coords: Vec<[f32; 2]> = reader
+                .read_tex_coords(0)
+                .map(|tex_coords| tex_coords.into_f32().collect())
+                .unwrap_or_else(|| vec![[0.0; 2]; count]);
+            let tex_coords1: Vec<[f32; 2]> = reader
+                .read_tex_coords(1)
+                .map(|tex_coords| tex_coords.into_f32().collect())
+                .unwrap_or_else(|| tex_coords.clone());
+            let colors: Vec<[f32; 4]> = reader
+                .read_colors(0)
+                .map(|colors| colors.into_rgba_f32().collect())
+                .unwrap_or_else(|| vec![[1.0; 4]; count]);
+            let joints: Vec<[u16; 4]> = reader
+                .read_joints(0)
+                .map(|joints| joints.into_u16().collect())
+                .unwrap_or_else(|| vec![[0; 4]; count]);
+            let weights: Vec<[f32; 4]> = reader
+                .read_weights(0)
+                .map(|weights| weights.into_f32().collect())
+                .unwrap_or_else(|| vec![[0.0; 4]; count]);
+
+            let vertices: Vec<model::ModelVertex> = (0..count)
+                .map(|i| {
+                    let normal: [f32; 3] = [
+                        mesh.normals[i * 3],
+                        mesh.normals[i * 3 + 1],
+                        mesh.normals[i * 3 + 2],
+                    ];
+                    //without a normal map there is nothing for the tangents to line up with
+                    let (tangent, bitangent) = fallback_tangents(normal.into());
+                    model::ModelVertex {
+                        position: positions[i],
+                        tex_coords: tex_coords[i],
+                        normal,
+                        tangent: tangent.into(),
+                        bitangent: bitangent.into(),
+                        color: colors[i],
+                        tex_coords1: tex_coords1[i],
+                        joints: joints[i].map(u32::from),
+                        weights: weights[i],
+                    }
+                })
+                .collect();
+
+            points.extend(positions.iter().map(|&p| cgmath::Point3::from(p)));
+            meshes.push(create_mesh(
+                device,
+                &label,
+                &vertices,
+                &mesh.indices,
+                primitive.material().index().unwrap_or(default_material),
+            ));
+        }
+    }
+
+    //only one skin is supported - it is whichever the file lists first
+    let skin: Option<skin::Skin> = gltf.skins().next().map(|gltf_skin| {
+        let joints: Vec<usize> = gltf_skin.joints().map(|joint| joint.index()).collect();
+        let inverse_bind_matrices: Vec<cgmath::Matrix4<f32>> = gltf_skin
+            .reader(|buffer| Some(&buffers[buffer.index()]))
+            .read_inverse_bind_matrices()
+            .map(|matrices| matrices.map(cgmath::Matrix4::from).collect())
+            //the joints were bound where they are
+            .unwrap_or_default();
+        let node: Option<usize> = gltf
+            .nodes()
+            .find(|node| node.skin().map(|s| s.index()) == Some(gltf_skin.index()))
+            .map(|node| node.index());
+        if joints.len() > skin::MAX_JOINTS {
+            log::warn!(
+                "{file_name}: the skin has {} joints, only the first {} are used",
+                joints.len(),
+                skin::MAX_JOINTS
+            );
+        }
+        skin::Skin {
+            joints,
+            inverse_bind_matrices,
+            node,
+        }
+    });
+
+    Ok(model::Model {
+        meshes,
+        materials,
+        animations: Vec::new(),
+        bounds: bounds::Aabb::from_points(points),
+        topology: wgpu::PrimitiveTopology::TriangleList,
+        skin,
+    })
+}
+
+//load the node hierarchy and the animations of a gltf (.gltf or .glb) file - nodes with a mesh are given model 0, and each node's id is its gltf index (which is what the animations refer to)
+pub async fn load_gltf_animations(
+    file_name: &str,
+) -> anyhow::Result<(scene::Node, Vec<animation::Animation>)> {
+    let gltf: gltf::Gltf = gltf::Gltf::from_slice(&load_binary(file_name).await?)?;
+    let buffers: Vec<Vec<u8>> = load_gltf_buffers(&gltf, file_name).await?;
+
+    //the scene graph - one root holding each of the scene's top level nodes
+    fn load_node(node: gltf::Node) -> scene::Node {
+        let mut loaded: scene::Node = scene::Node::new(node.transform().matrix().into());
+        loaded.id = Some(node.index());
+        if node.mesh().is_some() {
+            loaded.model = Some(0);
+        }
+        loaded.children = node.children().map(load_node).collect();
+        loaded
+    }
+    let mut root: scene::Node = scene::Node::new(cgmath::Matrix4::from_scale(1.0));
+    if let Some(gltf_scene) = gltf.default_scene().or_else(|| gltf.scenes().next()) {
+        root.children = gltf_scene.nodes().map(load_node).collect();
+    }
+
+    let mut animations: Vec<animation::Animation> = Vec::new();
+    for gltf_animation in gltf.animations() {
+        //gltf stores translation, rotation and scale as separate channels - we group them by node, starting each property at the node's resting value
+        let mut channels: Vec<animation::Channel> = Vec::new();
+        let mut duration: f32 = 0.0;
+
+        for gltf_channel in gltf_animation.channels() {
+            let target: gltf::Node = gltf_channel.target().node();
+            let index: usize = match channels.iter().position(|c| c.node == target.index()) {
+                Some(index) => index,
+                None => {
+                    let (translation, rotation, scale) = target.transform().decomposed();
+                    channels.push(animation::Channel {
+                        node: target.index(),
+                        translation: animation::Track::constant(translation.into()),
+                        rotation: animation::Track::constant(cgmath::Quaternion::new(
+                            rotation[3],
+                            rotation[0],
+                            rotation[1],
+                            rotation[2],
+                        )),
+                        scale: animation::Track::constant(scale.into()),
+                    });
+                    channels.len() - 1
+                }
+            };
+
+            let reader = gltf_channel.reader(|buffer| Some(&buffers[buffer.index()]));
+            let times: Vec<f32> = match reader.read_inputs() {
+                Some(inputs) => inputs.collect(),
+                None => continue,
+            };
+            duration = duration.max(times.last().copied().unwrap_or(0.0));
+
+            //cubic spline keyframes are stored as (in tangent, value, out tangent) - we only use the values and interpolate linearly
+            let cubic: bool = gltf_channel.sampler().interpolation()
+                == gltf::animation::Interpolation::CubicSpline;
+            fn keyframe_values<T: Copy>(values: Vec<T>, cubic: bool) -> Vec<T> {
+                if cubic {
+                    values.chunks(3).filter_map(|c| c.get(1).copied()).collect()
+                } else {
+                    values
+                }
+            }
+
+            let channel: &mut animation::Channel = &mut channels[index];
+            match reader.read_outputs() {
+                Some(gltf::animation::util::ReadOutputs::Translations(translations)) => {
+                    channel.translation = animation::Track {
+                        times,
+                        values: keyframe_values(translations.map(Into::into).collect(), cubic),
+                    };
+                }
+                Some(gltf::animation::util::ReadOutputs::Rotations(rotations)) => {
+                    channel.rotation = animation::Track {
+                        times,
+                        values: keyframe_values(
+                            rotations
+                                .into_f32()
+                                .map(|[x, y, z, w]| cgmath::Quaternion::new(w, x, y, z))
+                                .collect(),
+                            cubic,
+                        ),
+                    };
+                }
+                Some(gltf::animation::util::ReadOutputs::Scales(scales)) => {
+                    channel.scale = animation::Track {
+                        times,
+                        values: keyframe_values(scales.map(Into::into).collect(), cubic),
+                    };
+                }
+                //morph targets aren't supported (yet)
+                _ => {}
+            }
+        }
+
+        animations.push(animation::Animation {
+            name: gltf_animation.name().unwrap_or_default().to_string(),
+            channels,
+            duration,
+        });
+    }
+
+    Ok((root, animations))
+}
+
+//the vertices and (counter-clockwise) triangles of a sphere with a radius of 1 - rings goes from pole to pole, and segments around the middle
+fn uv_sphere(rings: u32, segments: u32) -> (Vec<model::ModelVertex>, Vec<u32>) {
+    use std::f32::consts::{PI, TAU};
+
+    //the seam (and each pole) has a vertex for every segment, so the texture coordinates can wrap around
+    let vertices: Vec<model::ModelVertex> = (0..=rings)
+        .flat_map(|ring| (0..=segments).map(move |segment| (ring, segment)))
+        .map(|(ring, segment)| {
+            let theta: f32 = PI * ring as f32 / rings as f32;
+            let phi: f32 = TAU * segment as f32 / segments as f32;
+            let normal: cgmath::Vector3<f32> = cgmath::Vector3::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            );
+            let tangent: cgmath::Vector3<f32> = cgmath::Vector3::new(-phi.sin(), 0.0, phi.cos());
+            model::ModelVertex {
+                position: normal.into(),
+                tex_coords: [segment as f32 / segments as f32, ring as f32 / rings as f32],
+                normal: normal.into(),
+                tangent: tangent.into(),
+                bitangent: normal.cross(tangent).into(),
+                color: [1.0; 4],
+                tex_coords1: [segment as f32 / segments as f32, ring as f32 / rings as f32],
+                joints: [0; 4],
+                weights: [0.0; 4],
+            }
+        })
+        .collect();
+
+    let indices: Vec<u32> = (0..rings)
+        .flat_map(|ring| (0..segments).map(move |segment| (ring, segment)))
+        .flat_map(|(ring, segment)| {
+            //a is on this ring and b is the one below it
+            let a: u32 = ring * (segments + 1) + segment;
+            let b: u32 = a + segments + 1;
+            [a, a + 1, b, a + 1, b + 1, b]
+        })
+        .collect();
+
+    (vertices, indices)
+}
+
+//a small sphere with nothing else to it - what the light is shown as by default
+pub fn sphere_model(device: &wgpu::Device) -> model::Model {
+    let (vertices, indices) = uv_sphere(12, 24);
+    model::Model {
+        meshes: vec![create_mesh(device, "sphere", &vertices, &indices, 0)],
+        ..model::Model::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+
+    #[test]
+    fn sphere_triangles_face_outwards() {
+        let (vertices, indices) = uv_sphere(4, 8);
+        assert_eq!(vertices.len(), 5 * 9);
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c]: [cgmath::Vector3<f32>; 3] =
+                [0, 1, 2].map(|i| vertices[triangle[i] as usize].position.into());
+            //the triangles touching the poles have two corners in the same place, so they have no size
+            let normal: cgmath::Vector3<f32> = (b - a).cross(c - a);
+            if normal.magnitude() > 1e-6 {
+                assert!(normal.dot(a + b + c) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn fallback_tangents_are_perpendicular_to_the_normal() {
+        for normal in [
+            cgmath::Vector3::new(0.0, 1.0, 0.0),
+            cgmath::Vector3::new(1.0, 0.0, 0.0),
+            cgmath::Vector3::new(0.3, -0.5, 0.8),
+            cgmath::Vector3::new(0.0, 0.0, 0.0),
+        ] {
+            let (tangent, bitangent) = fallback_tangents(normal);
+            assert!((tangent.magnitude() - 1.0).abs() < 1e-5);
+            assert!((bitangent.magnitude() - 1.0).abs() < 1e-5);
+            assert!(tangent.dot(normal).abs() < 1e-5);
+            assert!(bitangent.dot(tangent).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn missing_normals_are_averaged_from_the_triangles() {
+        //two triangles folded along the edge from vertex 0 to 1, one facing +z and one facing +y - the last normal is given, so is kept
+        let mut mesh: tobj::Mesh = tobj::Mesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, -1.0],
+            normals: vec![0.0; 9].into_iter().chain([1.0, 0.0, 0.0]).collect(),
+            indices: vec![0, 1, 2, 0, 1, 3],
+            ..Default::default()
+        };
+        fill_missing_normals(&mut mesh, wgpu::PrimitiveTopology::TriangleList);
+        let shared: f32 = std::f32::consts::FRAC_1_SQRT_2;
+        let expected: [f32; 12] = [
+            0.0, shared, shared, 0.0, shared, shared, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0,
+        ];
+        for (normal, expected) in mesh.normals.iter().zip(expected) {
+            assert!((normal - expected).abs() < 1e-5);
+        }
+
+        //with no normals at all, every vertex gets one
+        mesh.normals.clear();
+        fill_missing_normals(&mut mesh, wgpu::PrimitiveTopology::TriangleList);
+        assert_eq!(mesh.normals.len(), mesh.positions.len());
+        assert!((mesh.normals[9..12].iter().map(|n| n * n).sum::<f32>() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn polygons_with_separate_indices_become_triangles() {
+        //a quad whose corners all share one normal, as tobj gives it without triangulate or single_index
+        let quad: tobj::Mesh = tobj::Mesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0],
+            indices: vec![0, 1, 2, 3],
+            normal_indices: vec![0, 0, 0, 0],
+            face_arities: vec![4],
+            ..Default::default()
+        };
+        let mesh: tobj::Mesh = single_indexed(quad, "quad", wgpu::PrimitiveTopology::TriangleList);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(mesh.positions.len(), 6 * 3);
+        assert_eq!(mesh.normals.len(), 6 * 3);
+        //the second triangle is the first corner, then the last two
+        assert_eq!(
+            &mesh.positions[9..18],
+            &[0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]
+        );
+        assert!(mesh.texcoords.is_empty());
+    }
+
+    #[test]
+    fn line_only_models_are_kept_as_lines() {
+        //two lines sharing a corner, as tobj gives them without triangulate
+        let lines: tobj::Mesh = tobj::Mesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0],
+            indices: vec![0, 1, 1, 2],
+            face_arities: vec![2, 2],
+            ..Default::default()
+        };
+        let models: Vec<tobj::Model> = vec![tobj::Model::new(lines, "lines".to_string())];
+        let topology: wgpu::PrimitiveTopology = model_topology(&models);
+        assert_eq!(topology, wgpu::PrimitiveTopology::LineList);
+
+        let mesh: tobj::Mesh = single_indexed(models[0].mesh.clone(), "lines", topology);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 3]);
+        assert_eq!(&mesh.positions[6..9], &[1.0, 0.0, 0.0]);
+    }
 }
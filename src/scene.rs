@@ -0,0 +1,89 @@
+//a lightweight scene graph - nodes with transforms relative to their parent, so moving a parent moves all of its children
+
+use cgmath::{Matrix4, SquareMatrix};
+
+use crate::InstanceRaw;
+
+//a point in the scene graph, optionally drawing a model
+pub struct Node {
+    //where this node is relative to its parent
+    pub transform: Matrix4<f32>,
+    //nodes that move along with this one (e.g. a turret on a tank)
+    pub children: Vec<Node>,
+    //the index of the model drawn at this node (if any)
+    pub model: Option<usize>,
+    //an identifier animations use to find the node (the node's index when loaded from gltf)
+    pub id: Option<usize>,
+}
+
+impl Node {
+    pub fn new(transform: Matrix4<f32>) -> Self {
+        Self {
+            transform,
+            children: Vec::new(),
+            model: None,
+            id: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: usize) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    pub fn with_child(mut self, child: Node) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    //visits this node and all of its children (depth first), passing each one its world transform
+    pub fn traverse<F: FnMut(&Node, Matrix4<f32>)>(&self, parent: Matrix4<f32>, f: &mut F) {
+        //the parent's transform is applied after ours, so we move with it
+        let world: Matrix4<f32> = parent * self.transform;
+        f(self, world);
+        for child in &self.children {
+            child.traverse(world, f);
+        }
+    }
+
+    //visits this node and all of its children (depth first), allowing them to be changed
+    pub fn visit_mut<F: FnMut(&mut Node)>(&mut self, f: &mut F) {
+        f(self);
+        for child in &mut self.children {
+            child.visit_mut(f);
+        }
+    }
+
+    //the instance data for every node in this graph that draws the given model
+    pub(crate) fn collect_instances(&self, model: usize) -> Vec<InstanceRaw> {
+        let mut instances: Vec<InstanceRaw> = Vec::new();
+        self.traverse(Matrix4::identity(), &mut |node, world| {
+            if node.model == Some(model) {
+                instances.push(InstanceRaw::from_matrix(world));
+            }
+        });
+        instances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Vector3;
+
+    #[test]
+    fn children_inherit_parent_transform() {
+        let root: Node = Node::new(Matrix4::from_translation(Vector3::new(1.0, 0.0, 0.0)))
+            .with_model(0)
+            .with_child(
+                Node::new(Matrix4::from_translation(Vector3::new(0.0, 2.0, 0.0))).with_model(0),
+            )
+            .with_child(Node::new(Matrix4::from_scale(2.0)).with_model(1));
+
+        let instances: Vec<InstanceRaw> = root.collect_instances(0);
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].model[3], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(instances[1].model[3], [1.0, 2.0, 0.0, 1.0]);
+        assert_eq!(root.collect_instances(1).len(), 1);
+    }
+}
@@ -0,0 +1,63 @@
+//tests each instance against the camera's frustum, copying the visible ones into a packed buffer and counting them in the draw arguments
+
+struct Cull {
+    //(normal, distance) - a point is in front of a plane when dot(normal, point) + distance >= 0
+    planes: array<vec4<f32>, 6>,
+    instance_count: u32,
+    radius: f32,
+    mesh_count: u32,
+};
+
+//the arguments of one draw_indexed_indirect call
+struct DrawArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> cull: Cull;
+//InstanceRaw is a mat4x4 and a mat3x3 of f32's (25 in a row) - wgsl would pad the mat3x3's columns, so they are read as plain floats instead
+@group(0) @binding(1)
+var<storage, read> instances: array<f32>;
+@group(0) @binding(2)
+var<storage, read_write> culled: array<f32>;
+@group(0) @binding(3)
+var<storage, read_write> draws: array<DrawArgs>;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let instance_size = 25u;
+    if (id.x >= cull.instance_count) {
+        return;
+    }
+    let start = id.x * instance_size;
+
+    //the model matrix is stored column by column - the first 3 columns hold the rotation and scale, the 4th the position
+    let x_axis = vec3<f32>(instances[start], instances[start + 1u], instances[start + 2u]);
+    let y_axis = vec3<f32>(instances[start + 4u], instances[start + 5u], instances[start + 6u]);
+    let z_axis = vec3<f32>(instances[start + 8u], instances[start + 9u], instances[start + 10u]);
+    let position = vec3<f32>(instances[start + 12u], instances[start + 13u], instances[start + 14u]);
+    //the biggest scale on any axis, so the sphere still contains a stretched model
+    let radius = cull.radius * max(length(x_axis), max(length(y_axis), length(z_axis)));
+
+    for (var i = 0u; i < 6u; i = i + 1u) {
+        let plane = cull.planes[i];
+        if (dot(plane.xyz, position) + plane.w < -radius) {
+            return;
+        }
+    }
+
+    //every mesh is drawn at the same instances, so each mesh's count goes up by one - the first mesh's count gives this instance its place in the packed buffer
+    let slot = atomicAdd(&draws[0].instance_count, 1u);
+    for (var mesh = 1u; mesh < cull.mesh_count; mesh = mesh + 1u) {
+        atomicAdd(&draws[mesh].instance_count, 1u);
+    }
+
+    let destination = slot * instance_size;
+    for (var i = 0u; i < instance_size; i = i + 1u) {
+        culled[destination + i] = instances[start + i];
+    }
+}
@@ -0,0 +1,44 @@
+// depth.wgsl
+
+//the near and far planes of the projection the depth buffer was rendered with
+struct DepthUniform {
+    znear: f32,
+    zfar: f32,
+    //1 if the depth buffer is reversed (near is 1.0 and far is 0.0)
+    reverse_z: u32,
+    //the size of the depth texture compared to the screen
+    render_scale: f32,
+};
+
+@group(0) @binding(0)
+var t_depth: texture_depth_2d;
+@group(0) @binding(1)
+var<uniform> planes: DepthUniform;
+
+//vertex shader
+
+//there is no vertex buffer - a single triangle big enough to cover the whole screen is generated from the vertex index (see postprocess.wgsl)
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let uv: vec2<f32> = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+//fragment shader
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    //the fragment position is in pixels, so scaling it by the render scale gives the matching depth texel (kept inside the texture, as the scaled size is rounded)
+    let texel: vec2<i32> = min(vec2<i32>(position.xy * planes.render_scale), vec2<i32>(textureDimensions(t_depth)) - 1);
+    var depth: f32 = textureLoad(t_depth, texel, 0);
+    //flip reversed depth back around, so the maths below works the same either way
+    if (planes.reverse_z != 0u) {
+        depth = 1.0 - depth;
+    }
+
+    //undo the perspective divide - depth is 0 at znear and 1 at zfar, but most of that range is used up close to the camera
+    let linear_depth: f32 = planes.znear * planes.zfar / (planes.zfar - depth * (planes.zfar - planes.znear));
+    let gray: f32 = (linear_depth - planes.znear) / (planes.zfar - planes.znear);
+
+    return vec4<f32>(vec3<f32>(gray), 1.0);
+}
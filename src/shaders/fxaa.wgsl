@@ -0,0 +1,78 @@
+// fxaa.wgsl
+
+//fast approximate anti-aliasing - finds edges by how much the brightness changes around each pixel, and blurs along them (see fxaa.rs)
+
+@group(0) @binding(0)
+var t_color: texture_2d<f32>;
+@group(0) @binding(1)
+var s_color: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+//vertex shader
+
+//there is no vertex buffer - a single triangle big enough to cover the whole screen is generated from the vertex index (see postprocess.wgsl)
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv: vec2<f32> = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    //texture coordinates start at the top left, whereas clip space starts at the bottom left
+    out.tex_coords = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+//fragment shader
+
+//how bright a colour looks - our eyes are most sensitive to green
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    //the furthest (in pixels) an edge is blurred along, and how much the blur direction is kept from getting too big in dark and flat areas
+    let span_max: f32 = 8.0;
+    let reduce_mul: f32 = 1.0 / 8.0;
+    let reduce_min: f32 = 1.0 / 128.0;
+
+    let texel: vec2<f32> = 1.0 / vec2<f32>(textureDimensions(t_color));
+    let center: vec4<f32> = textureSample(t_color, s_color, in.tex_coords);
+
+    //the brightness of the pixel and its 4 diagonal neighbours
+    let luma_m: f32 = luma(center.rgb);
+    let luma_nw: f32 = luma(textureSample(t_color, s_color, in.tex_coords + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne: f32 = luma(textureSample(t_color, s_color, in.tex_coords + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw: f32 = luma(textureSample(t_color, s_color, in.tex_coords + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se: f32 = luma(textureSample(t_color, s_color, in.tex_coords + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_min: f32 = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max: f32 = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    //the edge runs across the way the brightness changes the most
+    var dir: vec2<f32> = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let dir_reduce: f32 = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * reduce_mul, reduce_min);
+    let rcp_dir_min: f32 = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2<f32>(-span_max), vec2<f32>(span_max)) * texel;
+
+    //a short blur along the edge, and a longer one
+    let rgb_a: vec3<f32> = 0.5 * (
+        textureSample(t_color, s_color, in.tex_coords + dir * (1.0 / 3.0 - 0.5)).rgb
+        + textureSample(t_color, s_color, in.tex_coords + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let rgb_b: vec3<f32> = rgb_a * 0.5 + 0.25 * (
+        textureSample(t_color, s_color, in.tex_coords + dir * -0.5).rgb
+        + textureSample(t_color, s_color, in.tex_coords + dir * 0.5).rgb
+    );
+
+    //the longer blur went past the edge if it picked up a brightness that isn't around the pixel, so the short one is used instead
+    let luma_b: f32 = luma(rgb_b);
+    let result: vec3<f32> = select(rgb_b, rgb_a, luma_b < luma_min || luma_b > luma_max);
+    return vec4<f32>(result, center.a);
+}
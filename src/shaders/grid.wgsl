@@ -0,0 +1,84 @@
+// grid.wgsl
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_proj: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    //where on the screen (from -1 to 1) this fragment is
+    @location(0) screen_position: vec2<f32>,
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    //the grid is drawn on a fullscreen triangle, so its depth has to come from where it hits the ground instead
+    @builtin(frag_depth) depth: f32,
+};
+
+//vertex shader
+
+//a single triangle covering the whole screen, generated from the vertex index - no vertex buffer needed
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv: vec2<f32> = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.screen_position = uv * 2.0 - 1.0;
+    out.clip_position = vec4<f32>(out.screen_position, 0.0, 1.0);
+    return out;
+}
+
+//fragment shader
+
+//turns a point on the screen (at a given depth) back into a point in the world
+fn unproject(screen_position: vec2<f32>, depth: f32) -> vec3<f32> {
+    let view_position: vec4<f32> = camera.inv_proj * vec4<f32>(screen_position, depth, 1.0);
+    let world_position: vec4<f32> = camera.inv_view * (view_position / view_position.w);
+    return world_position.xyz;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    //follow the ray from the camera through this fragment, and find where it crosses the ground (y = 0)
+    //any depth between the planes gives a point on the ray - 0.5 works whichever way around the depth buffer is (with or without reverse-z)
+    let origin: vec3<f32> = camera.view_pos.xyz;
+    let direction: vec3<f32> = unproject(in.screen_position, 0.5) - origin;
+    let t: f32 = -origin.y / direction.y;
+    let world_position: vec3<f32> = origin + t * direction;
+
+    //how far (in grid cells) this fragment is from the nearest line, scaled by how much the position changes between pixels so lines are always about a pixel wide
+    let coord: vec2<f32> = world_position.xz;
+    let derivative: vec2<f32> = fwidth(coord);
+    let grid: vec2<f32> = abs(fract(coord - 0.5) - 0.5) / derivative;
+    let nearest_line: f32 = min(grid.x, grid.y);
+
+    //lines along the x axis are red and along the z axis are blue, like most editors
+    var color: vec3<f32> = vec3<f32>(0.5);
+    if (abs(world_position.z) < derivative.y) {
+        color = vec3<f32>(0.9, 0.2, 0.2);
+    }
+    if (abs(world_position.x) < derivative.x) {
+        color = vec3<f32>(0.2, 0.2, 0.9);
+    }
+
+    //how far away (from the camera) the grid starts to fade out, and where it is gone completely
+    let fade_start: f32 = 10.0;
+    let fade_end: f32 = 60.0;
+    let fade: f32 = 1.0 - smoothstep(fade_start, fade_end, distance(world_position.xz, camera.view_pos.xz));
+    //the ray only hits the ground if it is going towards it (t > 0) - anything above the horizon is left alone
+    let visible: f32 = select(0.0, 1.0, t > 0.0);
+
+    let clip_position: vec4<f32> = camera.view_proj * vec4<f32>(world_position, 1.0);
+
+    var out: FragmentOutput;
+    out.color = vec4<f32>(color, (1.0 - min(nearest_line, 1.0)) * fade * visible);
+    //fragments that miss the ground are completely see-through, so it doesn't matter what depth they end up with
+    out.depth = clamp(clip_position.z / clip_position.w, 0.0, 1.0);
+    return out;
+}
@@ -5,13 +5,33 @@
 struct Camera {
     view_pos: vec4<f32>,
     view_proj: mat4x4<f32>,
+    inv_proj: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
 }
 @group(0) @binding(0)
 var<uniform> camera: Camera;
 
+//a light shining in one direction, fading out between its inner and outer cone angles (stored as cosines)
+struct SpotLight {
+    position: vec3<f32>,
+    inner_cos: f32,
+    direction: vec3<f32>,
+    outer_cos: f32,
+    color: vec3<f32>,
+    //how far the light reaches
+    range: f32,
+}
+
 struct Light {
     position: vec3<f32>,
+    directional: u32,
     color: vec3<f32>,
+    ambient: vec3<f32>,
+    ambient_strength: f32,
+    spotlight_count: u32,
+    marker_size: f32,
+    //matches MAX_SPOTLIGHTS on the rust side
+    spotlights: array<SpotLight, 4>,
 }
 @group(1) @binding(0)
 var<uniform> light: Light;
@@ -29,9 +49,8 @@ struct VertexOutput {
 fn vs_main(
     model: VertexInput,
 ) -> VertexOutput {
-    let scale: f32 = 0.25;
     var out: VertexOutput;
-    out.clip_position = camera.view_proj * vec4<f32>(model.position * scale + light.position, 1.0);
+    out.clip_position = camera.view_proj * vec4<f32>(model.position * light.marker_size + light.position, 1.0);
     out.color = light.color;
     return out;
 }
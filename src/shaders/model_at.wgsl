@@ -0,0 +1,16 @@
+// model_at.wgsl
+
+//appended to shader.wgsl for models drawn at a transform given with push constants, instead of at instances (see DrawModel::draw_model_at)
+//a separate module, as shaders using push constants can only be created on devices with Features::PUSH_CONSTANTS
+
+//matches ModelTransform on the rust side
+struct ModelTransform {
+    model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x3<f32>,
+};
+var<push_constant> transform: ModelTransform;
+
+@vertex
+fn vs_at(model: VertexInput) -> VertexOutput {
+    return vertex(model, transform.model_matrix, transform.normal_matrix);
+}
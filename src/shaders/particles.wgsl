@@ -0,0 +1,68 @@
+//draws particles as soft round squares facing the camera, fading out as they get older (see particles.rs)
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_proj: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+//the particle, split into the vec4s the vertex buffer holds it as
+struct ParticleInput {
+    @location(0) position_life: vec4<f32>,
+    @location(1) velocity_max_life: vec4<f32>,
+    @location(2) acceleration_size: vec4<f32>,
+    @location(3) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    //where in the square the pixel is, from -1 to 1 on each axis
+    @location(1) corner: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, particle: ParticleInput) -> VertexOutput {
+    //the two triangles of a square
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner: vec2<f32> = corners[index];
+
+    var out: VertexOutput;
+    out.corner = corner;
+    let life: f32 = particle.position_life.w;
+    if (life <= 0.0) {
+        //outside of clip space, so dead particles aren't drawn
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        out.color = vec4<f32>(0.0);
+        return out;
+    }
+
+    //the camera's right and up directions, so the square always faces it
+    let right: vec3<f32> = camera.inv_view[0].xyz;
+    let up: vec3<f32> = camera.inv_view[1].xyz;
+    let half_size: f32 = particle.acceleration_size.w * 0.5;
+    let world_position: vec3<f32> = particle.position_life.xyz + (right * corner.x + up * corner.y) * half_size;
+    out.clip_position = camera.view_proj * vec4<f32>(world_position, 1.0);
+
+    //fades out as it gets older
+    let remaining: f32 = life / max(particle.velocity_max_life.w, 0.0001);
+    out.color = vec4<f32>(particle.color.rgb, particle.color.a * remaining);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    //round, with soft edges
+    let fade: f32 = 1.0 - smoothstep(0.5, 1.0, length(in.corner));
+    return vec4<f32>(in.color.rgb, in.color.a * fade);
+}
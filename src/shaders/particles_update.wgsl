@@ -0,0 +1,36 @@
+//moves every living particle on by one time step (see particles.rs)
+
+//matches Particle in particles.rs
+struct Particle {
+    position: vec3<f32>,
+    life: f32,
+    velocity: vec3<f32>,
+    max_life: f32,
+    acceleration: vec3<f32>,
+    size: f32,
+    color: vec4<f32>,
+};
+
+struct Params {
+    //seconds since the last update
+    dt: f32,
+};
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= arrayLength(&particles)) {
+        return;
+    }
+    var particle: Particle = particles[id.x];
+    if (particle.life <= 0.0) {
+        return;
+    }
+    particle.velocity = particle.velocity + particle.acceleration * params.dt;
+    particle.position = particle.position + particle.velocity * params.dt;
+    particle.life = particle.life - params.dt;
+    particles[id.x] = particle;
+}
@@ -0,0 +1,68 @@
+// postprocess.wgsl
+
+//which effect to apply - matches the PostEffect enum on the rust side
+struct PostUniform {
+    effect: u32,
+    exposure: f32,
+    gamma: f32,
+};
+
+@group(0) @binding(0)
+var t_scene: texture_2d<f32>;
+@group(0) @binding(1)
+var s_scene: sampler;
+@group(0) @binding(2)
+var<uniform> post: PostUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+//vertex shader
+
+//there is no vertex buffer - a single triangle big enough to cover the whole screen is generated from the vertex index (0, 1, 2 => (-1, -1), (3, -1), (-1, 3))
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv: vec2<f32> = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    //texture coordinates start at the top left, whereas clip space starts at the bottom left
+    out.tex_coords = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+//fragment shader
+
+//the surface already encodes for a gamma of 2.2, so a different gamma is applied relative to that (and 2.2 changes nothing)
+fn apply_gamma(color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(pow(max(color.rgb, vec3<f32>(0.0)), vec3<f32>(2.2 / post.gamma)), color.a);
+}
+
+fn apply_effect(tex_coords: vec2<f32>) -> vec4<f32> {
+    let sampled: vec4<f32> = textureSample(t_scene, s_scene, tex_coords);
+    let color: vec4<f32> = vec4<f32>(sampled.rgb * post.exposure, sampled.a);
+
+    //invert
+    if (post.effect == 1u) {
+        return vec4<f32>(1.0 - color.rgb, color.a);
+    }
+    //grayscale - weighted by how sensitive our eyes are to each colour
+    if (post.effect == 2u) {
+        let luminance: f32 = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+        return vec4<f32>(vec3<f32>(luminance), color.a);
+    }
+    //reinhard tonemapping - squashes bright values into 0..1 instead of letting them clip
+    if (post.effect == 3u) {
+        return vec4<f32>(color.rgb / (color.rgb + vec3<f32>(1.0)), color.a);
+    }
+
+    //passthrough
+    return color;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return apply_gamma(apply_effect(in.tex_coords));
+}
@@ -18,6 +18,8 @@ struct InstanceInput {
 struct Camera {
     view_pos: vec4<f32>,
     view_proj: mat4x4<f32>,
+    inv_proj: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
 };
 
 //determined by render_pipeline_layout - textures are listed first, so they are group 0, and the camera matrix is listed second, so its group 1
@@ -34,6 +36,13 @@ struct VertexInput {
     @location(2) normal: vec3<f32>,
     @location(3) tangent: vec3<f32>,
     @location(4) bitangent: vec3<f32>,
+    //the per-vertex colour, multiplied with the texture
+    @location(12) color: vec4<f32>,
+    //the second set of texture coordinates, for the occlusion texture
+    @location(13) tex_coords1: vec2<f32>,
+    //the skin joints that move the vertex, and how much each one does
+    @location(14) joints: vec4<u32>,
+    @location(15) weights: vec4<f32>,
 };
 
 //stores the output of our vertex shaders
@@ -43,52 +52,95 @@ struct VertexOutput {
     @location(1) tangent_position: vec3<f32>,
     @location(2) tangent_light_position: vec3<f32>,
     @location(3) tangent_view_position: vec3<f32>,
-    
+    @location(4) color: vec4<f32>,
+    //spotlights are lit in world space, as there can be too many to move them all into tangent space in the vertex shader
+    @location(5) world_position: vec3<f32>,
+    @location(6) world_tangent: vec3<f32>,
+    @location(7) world_bitangent: vec3<f32>,
+    @location(8) world_normal: vec3<f32>,
+    @location(9) tex_coords1: vec2<f32>,
 };
 
 //
+//a light shining in one direction, fading out between its inner and outer cone angles (stored as cosines)
+struct SpotLight {
+    position: vec3<f32>,
+    inner_cos: f32,
+    direction: vec3<f32>,
+    outer_cos: f32,
+    color: vec3<f32>,
+    //how far the light reaches
+    range: f32,
+}
+
 struct Light {
+    //the direction towards the light, if it is directional
     position: vec3<f32>,
+    directional: u32,
     color: vec3<f32>,
+    ambient: vec3<f32>,
+    ambient_strength: f32,
+    spotlight_count: u32,
+    marker_size: f32,
+    //matches MAX_SPOTLIGHTS on the rust side
+    spotlights: array<SpotLight, 4>,
 }
 
 @group(2) @binding(0)
 var<uniform> light: Light;
 
-@vertex
-fn vs_main(
+//the matrix of each joint of the model's skin (see skin.rs)
+struct Joints {
+    //matches MAX_JOINTS on the rust side
+    matrices: array<mat4x4<f32>, 128>,
+}
+
+@group(3) @binding(0)
+var<uniform> joints: Joints;
+
+//moves a vertex to where the model's matrix puts it - shared by vs_main (for instances) and vs_at (see model_at.wgsl)
+fn vertex(
     model: VertexInput,
-    instance: InstanceInput,
+    model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x3<f32>,
 ) -> VertexOutput {
-    //re-assemble our matrix
-    let model_matrix: mat4x4<f32> = mat4x4<f32>(
-        instance.model_matrix_0,
-        instance.model_matrix_1,
-        instance.model_matrix_2,
-        instance.model_matrix_3,
-    );
-    //only needs to be 3x3 as we only need the rotational data
-    let normal_matrix: mat3x3<f32> = mat3x3<f32>(
-        instance.normal_matrix_0,
-        instance.normal_matrix_1,
-        instance.normal_matrix_2,
+
+    //linear blend skinning - the vertex is moved by a weighted mix of its joints (vertices without any weights aren't skinned, and stay where they are)
+    var skin: mat4x4<f32> = mat4x4<f32>(
+        vec4<f32>(1.0, 0.0, 0.0, 0.0),
+        vec4<f32>(0.0, 1.0, 0.0, 0.0),
+        vec4<f32>(0.0, 0.0, 1.0, 0.0),
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
     );
+    if (dot(model.weights, vec4<f32>(1.0)) > 0.0) {
+        skin = joints.matrices[model.joints.x] * model.weights.x
+            + joints.matrices[model.joints.y] * model.weights.y
+            + joints.matrices[model.joints.z] * model.weights.z
+            + joints.matrices[model.joints.w] * model.weights.w;
+    }
+    let skin_normal: mat3x3<f32> = mat3x3<f32>(skin[0].xyz, skin[1].xyz, skin[2].xyz);
 
     //construct the tangent matrix
-    let world_normal: vec3<f32> = normalize(normal_matrix * model.normal);
-    let world_tangent: vec3<f32> = normalize(normal_matrix * model.tangent);
-    let world_bitangent: vec3<f32> = normalize(normal_matrix * model.bitangent);
+    let world_normal: vec3<f32> = normalize(normal_matrix * (skin_normal * model.normal));
+    let world_tangent: vec3<f32> = normalize(normal_matrix * (skin_normal * model.tangent));
+    let world_bitangent: vec3<f32> = normalize(normal_matrix * (skin_normal * model.bitangent));
     let tangent_matrix: mat3x3<f32> = transpose(mat3x3<f32>(
         world_tangent,
         world_bitangent,
         world_normal,
     ));
 
-    let world_position: vec4<f32> = model_matrix * vec4<f32>(model.position, 1.0);
+    let world_position: vec4<f32> = model_matrix * skin * vec4<f32>(model.position, 1.0);
 
     var out: VertexOutput;
     out.clip_position = camera.view_proj * world_position;
     out.tex_coords = model.tex_coords;
+    out.tex_coords1 = model.tex_coords1;
+    out.color = model.color;
+    out.world_position = world_position.xyz;
+    out.world_tangent = world_tangent;
+    out.world_bitangent = world_bitangent;
+    out.world_normal = world_normal;
 
     //when multiplying matrices, the vector goes on the right and matrices go on the left in order of importance
     out.tangent_position = tangent_matrix * world_position.xyz;
@@ -98,6 +150,27 @@ fn vs_main(
     return out;
 }
 
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    //re-assemble our matrix
+    let model_matrix: mat4x4<f32> = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    //only needs to be 3x3 as we only need the rotational data
+    let normal_matrix: mat3x3<f32> = mat3x3<f32>(
+        instance.normal_matrix_0,
+        instance.normal_matrix_1,
+        instance.normal_matrix_2,
+    );
+    return vertex(model, model_matrix, normal_matrix);
+}
+
 
 //fragment shader
 
@@ -111,30 +184,174 @@ var t_normal: texture_2d<f32>;
 @group(0) @binding(3)
 var s_normal: sampler;
 
-//@location(0) refers to the first colour target
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+//matches MaterialUniform in model.rs
+struct Material {
+    normal_strength: f32,
+    opacity: f32,
+    metallic: f32,
+    roughness: f32,
+    //0 for blinn-phong, 1 for pbr (see ShadingModel)
+    shading_model: u32,
+    //the blinn-phong highlight's exponent and brightness
+    shininess: f32,
+    specular_strength: f32,
+    //added to the mip level every texture is sampled at (see SamplerOptions::lod_bias)
+    lod_bias: f32,
+};
+@group(0) @binding(4)
+var<uniform> material: Material;
+//packed like gltf - roughness in green, metallic in blue
+@group(0) @binding(5)
+var t_metallic_roughness: texture_2d<f32>;
+@group(0) @binding(6)
+var s_metallic_roughness: sampler;
+//how much ambient light reaches each point, in red (like gltf)
+@group(0) @binding(7)
+var t_occlusion: texture_2d<f32>;
+@group(0) @binding(8)
+var s_occlusion: sampler;
 
-    let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
-    let object_normal: vec4<f32> = textureSample(t_normal, s_normal, in.tex_coords);
+//how many microfacets face along the half vector (the trowbridge-reitz / ggx distribution)
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let pi: f32 = 3.14159265359;
+    let a: f32 = roughness * roughness;
+    let a2: f32 = a * a;
+    let denom: f32 = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (pi * denom * denom);
+}
 
-    //we don't need (or want) much ambient light, so 0.1 is fine
-    let ambient_strength: f32 = 0.1;
-    let ambient_color: vec3<f32> = light.color * ambient_strength;
+//how many microfacets are hidden by others, towards both the light and the viewer (smith's method with schlick-ggx)
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let r: f32 = roughness + 1.0;
+    let k: f32 = r * r / 8.0;
+    let ggx_v: f32 = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let ggx_l: f32 = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return ggx_v * ggx_l;
+}
+
+//how much light is reflected rather than refracted, which goes up at glancing angles
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+//the light reflected towards the viewer from one light, with the material's shading model - every vector has to be in the same space
+fn light_contribution(
+    normal: vec3<f32>,
+    view_dir: vec3<f32>,
+    light_dir: vec3<f32>,
+    light_color: vec3<f32>,
+    albedo: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+) -> vec3<f32> {
+    let half_dir: vec3<f32> = normalize(view_dir + light_dir);
+    let n_dot_l: f32 = max(dot(normal, light_dir), 0.0);
+
+    if (material.shading_model == 0u) {
+        let diffuse_strength: f32 = n_dot_l;
+        let specular_strength: f32 = pow(max(dot(normal, half_dir), 0.0), material.shininess) * material.specular_strength;
+        return (diffuse_strength + specular_strength) * light_color * albedo;
+    }
+
+    //cook-torrance - non-metals reflect ~4% of light at every colour, metals reflect their albedo
+    let pi: f32 = 3.14159265359;
+    let n_dot_v: f32 = max(dot(normal, view_dir), 0.0001);
+    let f0: vec3<f32> = mix(vec3<f32>(0.04), albedo, metallic);
+    let fresnel: vec3<f32> = fresnel_schlick(max(dot(half_dir, view_dir), 0.0), f0);
+    let ndf: f32 = distribution_ggx(max(dot(normal, half_dir), 0.0), roughness);
+    let geometry: f32 = geometry_smith(n_dot_v, n_dot_l, roughness);
+    let specular: vec3<f32> = ndf * geometry * fresnel / (4.0 * n_dot_v * n_dot_l + 0.0001);
+
+    //energy conservation - whatever is reflected can't also be diffused, and metals don't diffuse at all
+    let diffuse_ratio: vec3<f32> = (vec3<f32>(1.0) - fresnel) * (1.0 - metallic);
+    return (diffuse_ratio * albedo / pi + specular) * light_color * n_dot_l;
+}
+
+//flat_shading lights every pixel of a triangle with the triangle's own normal, instead of one blended from its vertices
+fn shade(in: VertexOutput, flat_shading: bool) -> vec4<f32> {
+
+    let object_color: vec4<f32> = textureSampleBias(t_diffuse, s_diffuse, in.tex_coords, material.lod_bias) * in.color;
+    let object_normal: vec4<f32> = textureSampleBias(t_normal, s_normal, in.tex_coords, material.lod_bias);
+    let metallic_roughness: vec4<f32> = textureSampleBias(t_metallic_roughness, s_metallic_roughness, in.tex_coords, material.lod_bias);
+    //occlusion is often baked into its own (non-overlapping) uv layout, so it uses the second set of texture coordinates
+    let occlusion: f32 = textureSampleBias(t_occlusion, s_occlusion, in.tex_coords1, material.lod_bias).r;
+
+    let albedo: vec3<f32> = object_color.xyz;
+    let metallic: f32 = metallic_roughness.b * material.metallic;
+    //a perfectly smooth surface would make the specular highlight infinitely small (and bright)
+    let roughness: f32 = max(metallic_roughness.g * material.roughness, 0.04);
+
+    //a constant amount of light, so faces pointing away from the light aren't pure black - less of it gets into creases and corners
+    let ambient_color: vec3<f32> = light.ambient * light.ambient_strength * albedo * occlusion;
 
     //create the lighting vectors
-    let tangent_normal: vec3<f32> = object_normal.xyz * 2.0 - 1.0;
-    let light_dir: vec3<f32> = normalize(in.tangent_light_position - in.tangent_position);
+    //blend between a flat surface (straight out along the tangent space z axis) and the normal map
+    let tangent_normal: vec3<f32> = normalize(mix(vec3<f32>(0.0, 0.0, 1.0), object_normal.xyz * 2.0 - 1.0, material.normal_strength));
+    //a directional light is the same direction from everywhere
+    let light_dir: vec3<f32> = normalize(select(in.tangent_light_position - in.tangent_position, in.tangent_light_position, light.directional == 1u));
     let view_dir: vec3<f32> = normalize(in.tangent_view_position - in.tangent_position);
-    let half_dir: vec3<f32> = normalize(view_dir + light_dir);
+    let world_view_dir: vec3<f32> = normalize(camera.view_pos.xyz - in.world_position);
+
+    //how the world position changes between neighbouring pixels gives two directions along the triangle, so crossing them gives its normal (facing the camera, as y goes down the screen)
+    //derivatives have to be taken outside of any branches
+    let face_normal: vec3<f32> = normalize(cross(dpdy(in.world_position), dpdx(in.world_position)));
+
+    var light_color: vec3<f32>;
+    var world_normal: vec3<f32>;
+    if (flat_shading) {
+        //the face normal is in world space, so the light is too
+        world_normal = face_normal;
+        let world_light_dir: vec3<f32> = normalize(select(light.position - in.world_position, light.position, light.directional == 1u));
+        light_color = light_contribution(face_normal, world_view_dir, world_light_dir, light.color, albedo, metallic, roughness);
+    } else {
+        light_color = light_contribution(tangent_normal, view_dir, light_dir, light.color, albedo, metallic, roughness);
+        world_normal = normalize(mat3x3<f32>(
+            normalize(in.world_tangent),
+            normalize(in.world_bitangent),
+            normalize(in.world_normal),
+        ) * tangent_normal);
+    }
+
+    //spotlights - the same lighting as above, but in world space and only inside each light's cone
+    var spot_color: vec3<f32> = vec3<f32>(0.0);
+    for (var i: u32 = 0u; i < light.spotlight_count; i = i + 1u) {
+        let spot: SpotLight = light.spotlights[i];
+        let spot_dir: vec3<f32> = normalize(spot.position - in.world_position);
+        //1 inside the inner cone, 0 outside the outer cone, and a smooth falloff in between
+        let cos_angle: f32 = dot(-spot_dir, spot.direction);
+        let cone: f32 = smoothstep(spot.outer_cos, spot.inner_cos, cos_angle);
+        //fades smoothly from full strength at the light to nothing at its range, so there's no hard edge where it stops
+        let distance_ratio: f32 = distance(spot.position, in.world_position) / spot.range;
+        let falloff: f32 = clamp(1.0 - distance_ratio * distance_ratio * distance_ratio * distance_ratio, 0.0, 1.0);
+
+        spot_color = spot_color + cone * falloff * falloff * light_contribution(world_normal, world_view_dir, spot_dir, spot.color, albedo, metallic, roughness);
+    }
 
-    let diffuse_strength: f32 = max(dot(tangent_normal, light_dir), 0.0);
-    let diffuse_color: vec3<f32>  = light.color * diffuse_strength;
+    let result: vec3<f32> = ambient_color + light_color + spot_color;
 
-    let specular_strength: f32 = pow(max(dot(tangent_normal, half_dir), 0.0), 32.0);
-    let specular_color: vec3<f32> = specular_strength * light.color;
+    return vec4<f32>(result, object_color.a * material.opacity);
+}
 
-let result: vec3<f32> = (ambient_color + diffuse_color + specular_color) * object_color.xyz;
+//@location(0) refers to the first colour target
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in, false);
+}
 
-    return vec4<f32>(result, object_color.a);
+@fragment
+fn fs_flat(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in, true);
+}
+
+//no lighting - the world space normal, moved from -1..1 to 0..1 so it can be shown as a colour (see DebugView::Normals)
+@fragment
+fn fs_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    let object_normal: vec4<f32> = textureSampleBias(t_normal, s_normal, in.tex_coords, material.lod_bias);
+    let tangent_normal: vec3<f32> = normalize(mix(vec3<f32>(0.0, 0.0, 1.0), object_normal.xyz * 2.0 - 1.0, material.normal_strength));
+    let world_normal: vec3<f32> = normalize(mat3x3<f32>(
+        normalize(in.world_tangent),
+        normalize(in.world_bitangent),
+        normalize(in.world_normal),
+    ) * tangent_normal);
+    return vec4<f32>(world_normal * 0.5 + 0.5, 1.0);
 }
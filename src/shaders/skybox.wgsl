@@ -0,0 +1,62 @@
+// skybox.wgsl
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_proj: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+@group(1) @binding(0)
+var t_skybox: texture_cube<f32>;
+@group(1) @binding(1)
+var s_skybox: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    //where on the screen (from -1 to 1) this fragment is
+    @location(0) screen_position: vec2<f32>,
+};
+
+//vertex shader
+
+//a single triangle covering the whole screen at the given depth, generated from the vertex index - no vertex buffer needed
+fn fullscreen_triangle(vertex_index: u32, depth: f32) -> VertexOutput {
+    let uv: vec2<f32> = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.screen_position = uv * 2.0 - 1.0;
+    out.clip_position = vec4<f32>(out.screen_position, depth, 1.0);
+    return out;
+}
+
+//a depth of 1.0 puts the skybox as far away as possible, so it is only visible where nothing else has been drawn
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    return fullscreen_triangle(vertex_index, 1.0);
+}
+
+//with reverse-z, the far plane is at 0.0 instead
+@vertex
+fn vs_main_reverse_z(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    return fullscreen_triangle(vertex_index, 0.0);
+}
+
+//fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    //un-project the screen position to find which direction it is looking in - this is done per fragment as it doesn't interpolate linearly
+    let view_position: vec4<f32> = camera.inv_proj * vec4<f32>(in.screen_position, 1.0, 1.0);
+    //only the camera's rotation is used (no translation), so the skybox always looks infinitely far away
+    let inv_view_rotation: mat3x3<f32> = mat3x3<f32>(
+        camera.inv_view[0].xyz,
+        camera.inv_view[1].xyz,
+        camera.inv_view[2].xyz,
+    );
+    let direction: vec3<f32> = inv_view_rotation * (view_position.xyz / view_position.w);
+
+    return textureSample(t_skybox, s_skybox, direction);
+}
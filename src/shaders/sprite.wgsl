@@ -0,0 +1,57 @@
+// sprite.wgsl
+
+struct Screen {
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> screen: Screen;
+
+@group(1) @binding(0)
+var t_sprite: texture_2d<f32>;
+@group(1) @binding(1)
+var s_sprite: sampler;
+
+//one sprite - its rectangle on the screen (in pixels) and in its texture
+struct SpriteInput {
+    @location(0) min: vec2<f32>,
+    @location(1) max: vec2<f32>,
+    @location(2) tex_min: vec2<f32>,
+    @location(3) tex_max: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+//vertex shader
+
+//there is no vertex buffer - each sprite is an instance, and its 6 vertices (2 triangles) are picked from the corners of its rectangle (the same as text.wgsl)
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, sprite: SpriteInput) -> VertexOutput {
+    var corners: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner: vec2<f32> = corners[vertex_index];
+
+    let pixel: vec2<f32> = mix(sprite.min, sprite.max, corner);
+
+    var out: VertexOutput;
+    //pixels start at the top left, whereas clip space starts at the bottom left
+    out.clip_position = vec4<f32>(pixel.x / screen.size.x * 2.0 - 1.0, 1.0 - pixel.y / screen.size.y * 2.0, 0.0, 1.0);
+    out.tex_coords = mix(sprite.tex_min, sprite.tex_max, corner);
+    return out;
+}
+
+//fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_sprite, s_sprite, in.tex_coords);
+}
@@ -0,0 +1,61 @@
+// text.wgsl
+
+struct Screen {
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var t_atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var s_atlas: sampler;
+@group(0) @binding(2)
+var<uniform> screen: Screen;
+
+//one glyph - its rectangle on the screen (in pixels) and in the atlas
+struct GlyphInput {
+    @location(0) min: vec2<f32>,
+    @location(1) max: vec2<f32>,
+    @location(2) tex_min: vec2<f32>,
+    @location(3) tex_max: vec2<f32>,
+    @location(4) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+//vertex shader
+
+//there is no vertex buffer - each glyph is an instance, and its 6 vertices (2 triangles) are picked from the corners of its rectangle
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, glyph: GlyphInput) -> VertexOutput {
+    var corners: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner: vec2<f32> = corners[vertex_index];
+
+    let pixel: vec2<f32> = mix(glyph.min, glyph.max, corner);
+
+    var out: VertexOutput;
+    //pixels start at the top left, whereas clip space starts at the bottom left
+    out.clip_position = vec4<f32>(pixel.x / screen.size.x * 2.0 - 1.0, 1.0 - pixel.y / screen.size.y * 2.0, 0.0, 1.0);
+    out.tex_coords = mix(glyph.tex_min, glyph.tex_max, corner);
+    out.color = glyph.color;
+    return out;
+}
+
+//fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    //the atlas only stores how much of each pixel the glyph covers
+    let coverage: f32 = textureSample(t_atlas, s_atlas, in.tex_coords).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
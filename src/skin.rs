@@ -0,0 +1,138 @@
+//linear blend skinning - each vertex of a skinned mesh is moved by a weighted mix of up to 4 joints (nodes in the scene graph), so animating the joints bends the mesh
+
+use std::collections::HashMap;
+
+use cgmath::{Matrix4, SquareMatrix};
+use wgpu::util::DeviceExt;
+
+use crate::scene;
+
+//how many joints a skin can have - has to match the size of the joints array in shader.wgsl
+//the matrices are in a uniform rather than a storage buffer, as webgl can't read storage buffers in vertex shaders
+pub const MAX_JOINTS: usize = 128;
+
+//the joints a skinned model's vertices refer to (by their position in joints)
+pub struct Skin {
+    //the scene graph id of each joint's node
+    pub joints: Vec<usize>,
+    //moves a vertex from the model's space into the space of each joint, as it was when the mesh was bound to the skeleton
+    pub inverse_bind_matrices: Vec<Matrix4<f32>>,
+    //the id of the node the skinned mesh is drawn at - its transform is already applied by the instance, so it is taken back out of the joints
+    pub node: Option<usize>,
+}
+
+impl Skin {
+    //the matrix for each joint (in the same order as joints), with the scene graph's nodes where they currently are
+    pub fn joint_matrices(&self, scene: &scene::Node) -> Vec<[[f32; 4]; 4]> {
+        let mut world: HashMap<usize, Matrix4<f32>> = HashMap::new();
+        scene.traverse(Matrix4::identity(), &mut |node, transform| {
+            if let Some(id) = node.id {
+                world.insert(id, transform);
+            }
+        });
+
+        let inverse_node: Matrix4<f32> = self
+            .node
+            .and_then(|node| world.get(&node))
+            .and_then(|transform| transform.invert())
+            .unwrap_or_else(Matrix4::identity);
+
+        self.joints
+            .iter()
+            .take(MAX_JOINTS)
+            .enumerate()
+            .map(|(i, joint)| {
+                //joints missing from the scene stay at their bind pose
+                let joint_world: Matrix4<f32> =
+                    world.get(joint).copied().unwrap_or_else(Matrix4::identity);
+                let inverse_bind: Matrix4<f32> = self
+                    .inverse_bind_matrices
+                    .get(i)
+                    .copied()
+                    .unwrap_or_else(Matrix4::identity);
+                (inverse_node * joint_world * inverse_bind).into()
+            })
+            .collect()
+    }
+}
+
+//the joint matrices on the gpu, bound as group 3 of the main pipeline
+pub struct JointPalette {
+    buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl JointPalette {
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("joint_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    //every joint starts at the identity, so nothing moves until a skin's matrices are written
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> Self {
+        let identity: [[f32; 4]; 4] = Matrix4::<f32>::identity().into();
+        let buffer: wgpu::Buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Joint Buffer"),
+            contents: bytemuck::cast_slice(&[identity; MAX_JOINTS]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group: wgpu::BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("joint_bind_group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self { buffer, bind_group }
+    }
+
+    //joints past MAX_JOINTS are left out
+    pub fn write(&self, queue: &wgpu::Queue, matrices: &[[[f32; 4]; 4]]) {
+        let matrices: &[[[f32; 4]; 4]] = &matrices[..matrices.len().min(MAX_JOINTS)];
+        if !matrices.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(matrices));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Vector3;
+
+    #[test]
+    fn joint_matrices_undo_the_bind_pose_and_the_mesh_node() {
+        //the mesh's node is moved 1 along x, and its one joint 2 along y (bound when it was 1 along y)
+        let mut mesh_node: scene::Node =
+            scene::Node::new(Matrix4::from_translation(Vector3::new(1.0, 0.0, 0.0)));
+        mesh_node.id = Some(0);
+        let mut joint: scene::Node =
+            scene::Node::new(Matrix4::from_translation(Vector3::new(0.0, 2.0, 0.0)));
+        joint.id = Some(1);
+        let root: scene::Node = scene::Node::new(Matrix4::identity())
+            .with_child(mesh_node)
+            .with_child(joint);
+
+        let skin: Skin = Skin {
+            joints: vec![1],
+            inverse_bind_matrices: vec![Matrix4::from_translation(Vector3::new(0.0, -1.0, 0.0))],
+            node: Some(0),
+        };
+        let matrices: Vec<[[f32; 4]; 4]> = skin.joint_matrices(&root);
+        assert_eq!(matrices.len(), 1);
+        //the joint has moved 1 up from its bind pose, and the mesh node's 1 along x is taken back out
+        assert_eq!(matrices[0][3], [-1.0, 1.0, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,270 @@
+//for drawing 2d textured quads (icons, crosshairs, health bars ect) over the top of the rendered scene, positioned in pixels
+
+use wgpu::util::DeviceExt;
+
+use crate::{buffer_pool, text::ScreenUniform, texture};
+
+//one sprite's quad on the screen and the part of its texture it shows - drawn as an instance of a 6 vertex quad
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteInstance {
+    //in pixels, from the top left of the screen
+    min: [f32; 2],
+    max: [f32; 2],
+    //in texture coordinates
+    tex_min: [f32; 2],
+    tex_max: [f32; 2],
+}
+
+impl SpriteInstance {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x2,
+            1 => Float32x2,
+            2 => Float32x2,
+            3 => Float32x2,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+//queues up sprites during a frame and draws them all in one pass at the end, in the order they were queued
+pub struct SpriteOverlay {
+    screen_buffer: wgpu::Buffer,
+    screen_bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    //every texture sprites can be drawn with (see add_texture), and the bind group pointing at it
+    textures: Vec<(texture::Texture, wgpu::BindGroup)>,
+    //the sprites to draw this frame, and the index of the texture each is drawn with
+    queued: Vec<(usize, SpriteInstance)>,
+    //holds the queued sprites while they're drawn - swapped for a bigger one (from the pool) when they don't fit
+    instance_buffer: wgpu::Buffer,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl SpriteOverlay {
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        let screen_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Sprite Screen Buffer"),
+                contents: bytemuck::cast_slice(&[ScreenUniform::new(config.width, config.height)]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let screen_bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sprite_screen_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let screen_bind_group: wgpu::BindGroup =
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("sprite_screen_bind_group"),
+                layout: &screen_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: screen_buffer.as_entire_binding(),
+                }],
+            });
+
+        let texture_bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sprite_texture_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Sprite Pipeline Layout"),
+                bind_group_layouts: &[&screen_bind_group_layout, &texture_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let shader: wgpu::ShaderModule =
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Sprite Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/sprite.wgsl").into()),
+            });
+        //sprites blend with what's behind them, so icons can have see-through parts
+        let pipeline: wgpu::RenderPipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Sprite Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[SpriteInstance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    //quads are always facing the screen, so there's nothing to cull
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                //sprites are always drawn on top, so there's no depth
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+
+        Self {
+            screen_buffer,
+            screen_bind_group,
+            texture_bind_group_layout,
+            textures: Vec::new(),
+            queued: Vec::new(),
+            instance_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Sprite Instance Buffer"),
+                size: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            pipeline,
+        }
+    }
+
+    //makes a texture available to draw sprites with, returning the index to queue them with
+    pub fn add_texture(&mut self, device: &wgpu::Device, texture: texture::Texture) -> usize {
+        let bind_group: wgpu::BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sprite_texture_bind_group"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        });
+        self.textures.push((texture, bind_group));
+        self.textures.len() - 1
+    }
+
+    //queue a sprite to be drawn this frame - rect is its x, y (of the top left corner), width and height in pixels from the top left of the screen, and uv the min u, min v, max u and max v of the part of the texture it shows
+    pub fn queue(&mut self, texture: usize, rect: [f32; 4], uv: [f32; 4]) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            texture < self.textures.len(),
+            "there is no sprite texture {texture} (only {} have been added)",
+            self.textures.len()
+        );
+        let [x, y, width, height]: [f32; 4] = rect;
+        self.queued.push((
+            texture,
+            SpriteInstance {
+                min: [x, y],
+                max: [x + width, y + height],
+                tex_min: [uv[0], uv[1]],
+                tex_max: [uv[2], uv[3]],
+            },
+        ));
+        Ok(())
+    }
+
+    //sprites are positioned in pixels, so the projection has to follow the size of the screen
+    pub fn resize(&self, queue: &wgpu::Queue, width: u32, height: u32) {
+        queue.write_buffer(
+            &self.screen_buffer,
+            0,
+            bytemuck::cast_slice(&[ScreenUniform::new(width, height)]),
+        );
+    }
+
+    //draws (and then forgets) everything queued this frame over the top of the output view
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pool: &mut buffer_pool::BufferPool,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+    ) {
+        if self.queued.is_empty() {
+            return;
+        }
+
+        let instances: Vec<SpriteInstance> =
+            self.queued.iter().map(|(_, instance)| *instance).collect();
+        let data: &[u8] = bytemuck::cast_slice(&instances);
+        if data.len() as wgpu::BufferAddress > self.instance_buffer.size() {
+            let buffer: wgpu::Buffer = pool.acquire(
+                device,
+                "Sprite Instance Buffer",
+                data.len() as wgpu::BufferAddress,
+                wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            );
+            pool.release(std::mem::replace(&mut self.instance_buffer, buffer));
+        }
+        queue.write_buffer(&self.instance_buffer, 0, data);
+
+        {
+            let mut render_pass: wgpu::RenderPass =
+                encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Sprite Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: output,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            //sprites go on top of the scene, so we keep what's already there
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.screen_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+            //later sprites have to go over earlier ones, so only runs of sprites next to each other in the queue share a draw call
+            let mut start: usize = 0;
+            for run in self.queued.chunk_by(|a, b| a.0 == b.0) {
+                let end: usize = start + run.len();
+                render_pass.set_bind_group(1, &self.textures[run[0].0].1, &[]);
+                render_pass.draw(0..6, start as u32..end as u32);
+                start = end;
+            }
+        }
+
+        self.queued.clear();
+    }
+}
@@ -0,0 +1,56 @@
+//a directional light that moves across the sky with the time of day - rising in the east (+x), overhead at noon and setting in the west (-x)
+
+use cgmath::{InnerSpace, Vector3};
+
+//the colour of the sun as it touches the horizon, and at its highest
+const HORIZON_COLOR: [f32; 3] = [1.0, 0.5, 0.2];
+const NOON_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+//how far the sun's path leans towards +z, so it is never quite straight overhead
+const TILT: f32 = 0.3;
+
+pub struct Sun {
+    //which way the sun is from the scene (normalized)
+    pub direction: Vector3<f32>,
+    //black while the sun is below the horizon
+    pub color: [f32; 3],
+}
+
+impl Sun {
+    //time_of_day goes from 0 to 1 - 0 (and 1) is midnight, 0.25 is dawn, 0.5 is noon and 0.75 is dusk
+    pub fn at(time_of_day: f32) -> Self {
+        let angle: f32 = (time_of_day.rem_euclid(1.0) - 0.25) * std::f32::consts::TAU;
+        let direction: Vector3<f32> = Vector3::new(angle.cos(), angle.sin(), TILT).normalize();
+
+        //how high the sun is, from 0 on the horizon to 1 at noon
+        let height: f32 = angle.sin().max(0.0);
+        //fades in over the first part of the morning (and out at the end of the evening), instead of switching on at the horizon
+        let brightness: f32 = (height * 8.0).min(1.0);
+        let warmth: f32 = 1.0 - height.sqrt();
+        let color: [f32; 3] = [0, 1, 2]
+            .map(|i| (NOON_COLOR[i] + (HORIZON_COLOR[i] - NOON_COLOR[i]) * warmth) * brightness);
+
+        Self { direction, color }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_sun_is_white_and_high_at_noon_and_dark_at_midnight() {
+        let noon: Sun = Sun::at(0.5);
+        assert!(noon.direction.y > 0.9);
+        assert_eq!(noon.color, NOON_COLOR);
+
+        //warmer (less blue) than noon just after dawn
+        let morning: Sun = Sun::at(0.27);
+        assert!(morning.direction.x > 0.0 && morning.direction.y > 0.0);
+        assert!(morning.color[2] < morning.color[0]);
+
+        let midnight: Sun = Sun::at(1.0);
+        assert!(midnight.direction.y < 0.0);
+        assert_eq!(midnight.color, [0.0; 3]);
+    }
+}
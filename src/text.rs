@@ -0,0 +1,385 @@
+//for drawing 2d text (fps counters, coordinates, help ect) over the top of the rendered scene
+
+use glyph_brush::ab_glyph::FontArc;
+use wgpu::util::DeviceExt;
+
+use crate::texture;
+
+//the height (in pixels) text is drawn at
+const TEXT_SCALE: f32 = 24.0;
+
+//the size glyphs are first cached at - glyph_brush asks for a bigger texture if they don't fit
+const INITIAL_ATLAS_SIZE: u32 = 256;
+
+//one glyph's quad on the screen and where it is in the atlas - drawn as an instance of a 6 vertex quad
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct GlyphInstance {
+    //in pixels, from the top left of the screen
+    min: [f32; 2],
+    max: [f32; 2],
+    //in texture coordinates of the atlas
+    tex_min: [f32; 2],
+    tex_max: [f32; 2],
+    color: [f32; 4],
+}
+
+impl GlyphInstance {
+    fn from_vertex(vertex: glyph_brush::GlyphVertex) -> Self {
+        Self {
+            min: [vertex.pixel_coords.min.x, vertex.pixel_coords.min.y],
+            max: [vertex.pixel_coords.max.x, vertex.pixel_coords.max.y],
+            tex_min: [vertex.tex_coords.min.x, vertex.tex_coords.min.y],
+            tex_max: [vertex.tex_coords.max.x, vertex.tex_coords.max.y],
+            color: vertex.extra.color,
+        }
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+            0 => Float32x2,
+            1 => Float32x2,
+            2 => Float32x2,
+            3 => Float32x2,
+            4 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GlyphInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+//also used by the sprite overlay, which is positioned in pixels too
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ScreenUniform {
+    //the size of the screen in pixels, for turning pixel positions into clip space (an orthographic projection)
+    size: [f32; 2],
+    //webgl needs uniform buffers to be a multiple of 16 bytes, and size is only 8
+    _padding: [f32; 2],
+}
+
+impl ScreenUniform {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            size: [width as f32, height as f32],
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+//queues up text during a frame and draws it all in one pass at the end
+pub struct TextOverlay {
+    //lays out and rasterizes queued text - there is nothing to draw text with until a font is set
+    brush: Option<glyph_brush::GlyphBrush<GlyphInstance>>,
+    //every glyph currently in use, rasterized into a single channel texture
+    atlas: texture::Texture,
+    screen_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    //has to be re-created whenever the atlas is, as it points at the atlas texture
+    bind_group: wgpu::BindGroup,
+    //the glyphs drawn last frame (re-used if the text hasn't changed)
+    instance_buffer: Option<wgpu::Buffer>,
+    num_instances: u32,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl TextOverlay {
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        let atlas: texture::Texture =
+            Self::create_atlas(device, INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE);
+
+        let screen_buffer: wgpu::Buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Text Screen Buffer"),
+                contents: bytemuck::cast_slice(&[ScreenUniform::new(config.width, config.height)]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let bind_group_layout: wgpu::BindGroupLayout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("text_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let bind_group: wgpu::BindGroup =
+            Self::create_bind_group(device, &bind_group_layout, &atlas, &screen_buffer);
+
+        let layout: wgpu::PipelineLayout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Text Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let shader: wgpu::ShaderModule =
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Text Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/text.wgsl").into()),
+            });
+        //create_render_pipeline only replaces pixels, but text has to blend with what's behind it
+        let pipeline: wgpu::RenderPipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Text Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[GlyphInstance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    //quads are always facing the screen, so there's nothing to cull
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                //text is always drawn on top, so there's no depth
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+
+        Self {
+            brush: None,
+            atlas,
+            screen_buffer,
+            bind_group_layout,
+            bind_group,
+            instance_buffer: None,
+            num_instances: 0,
+            pipeline,
+        }
+    }
+
+    fn create_atlas(device: &wgpu::Device, width: u32, height: u32) -> texture::Texture {
+        let texture: wgpu::Texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("text_atlas"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            //glyphs are only coverage (how much of each pixel is inside the glyph), so one channel is enough
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+        let view: wgpu::TextureView = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler: wgpu::Sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        texture::Texture {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        atlas: &texture::Texture,
+        screen_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("text_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&atlas.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&atlas.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: screen_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    //the font all text is drawn with (the raw bytes of a .ttf or .otf file)
+    pub fn set_font(&mut self, font_data: Vec<u8>) -> anyhow::Result<()> {
+        let font: FontArc = FontArc::try_from_vec(font_data)?;
+        self.brush = Some(
+            glyph_brush::GlyphBrushBuilder::using_font(font)
+                .initial_cache_size((INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE))
+                .build(),
+        );
+        Ok(())
+    }
+
+    //queue some text to be drawn this frame, with its top left corner at (x, y) pixels from the top left of the screen
+    //without a font, the text is just dropped
+    pub fn queue(&mut self, text: &str, x: f32, y: f32, color: [f32; 4]) {
+        if let Some(brush) = &mut self.brush {
+            brush.queue(
+                glyph_brush::Section::default()
+                    .with_screen_position((x, y))
+                    .add_text(
+                        glyph_brush::Text::new(text)
+                            .with_scale(TEXT_SCALE)
+                            .with_color(color),
+                    ),
+            );
+        }
+    }
+
+    //text is positioned in pixels, so the projection has to follow the size of the screen
+    pub fn resize(&self, queue: &wgpu::Queue, width: u32, height: u32) {
+        queue.write_buffer(
+            &self.screen_buffer,
+            0,
+            bytemuck::cast_slice(&[ScreenUniform::new(width, height)]),
+        );
+    }
+
+    //draws everything queued this frame over the top of the output view
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+    ) {
+        let brush: &mut glyph_brush::GlyphBrush<GlyphInstance> = match &mut self.brush {
+            Some(brush) => brush,
+            None => return,
+        };
+
+        //glyph_brush rasterizes any new glyphs into the atlas, then gives us a quad for every glyph
+        let action: glyph_brush::BrushAction<GlyphInstance> = loop {
+            let atlas: &texture::Texture = &self.atlas;
+            match brush.process_queued(
+                |rect, data| {
+                    queue.write_texture(
+                        wgpu::ImageCopyTexture {
+                            texture: &atlas.texture,
+                            mip_level: 0,
+                            origin: wgpu::Origin3d {
+                                x: rect.min[0],
+                                y: rect.min[1],
+                                z: 0,
+                            },
+                            aspect: wgpu::TextureAspect::All,
+                        },
+                        data,
+                        wgpu::ImageDataLayout {
+                            offset: 0,
+                            //one byte per pixel
+                            bytes_per_row: std::num::NonZeroU32::new(rect.width()),
+                            rows_per_image: std::num::NonZeroU32::new(rect.height()),
+                        },
+                        wgpu::Extent3d {
+                            width: rect.width(),
+                            height: rect.height(),
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                },
+                GlyphInstance::from_vertex,
+            ) {
+                Ok(action) => break action,
+                //the glyphs don't all fit - grow the atlas and try again
+                Err(glyph_brush::BrushError::TextureTooSmall { suggested }) => {
+                    let max: u32 = device.limits().max_texture_dimension_2d;
+                    let (width, height): (u32, u32) = (suggested.0.min(max), suggested.1.min(max));
+                    self.atlas = Self::create_atlas(device, width, height);
+                    self.bind_group = Self::create_bind_group(
+                        device,
+                        &self.bind_group_layout,
+                        &self.atlas,
+                        &self.screen_buffer,
+                    );
+                    brush.resize_texture(width, height);
+                }
+            }
+        };
+
+        //only re-upload the glyphs if the text has changed since last frame
+        if let glyph_brush::BrushAction::Draw(instances) = action {
+            self.num_instances = instances.len() as u32;
+            self.instance_buffer = (!instances.is_empty()).then(|| {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Text Instance Buffer"),
+                    contents: bytemuck::cast_slice(&instances),
+                    usage: wgpu::BufferUsages::VERTEX,
+                })
+            });
+        }
+
+        let instance_buffer: &wgpu::Buffer = match &self.instance_buffer {
+            Some(buffer) => buffer,
+            None => return,
+        };
+
+        let mut render_pass: wgpu::RenderPass =
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Text Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: output,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        //text goes on top of the scene, so we keep what's already there
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, instance_buffer.slice(..));
+        render_pass.draw(0..6, 0..self.num_instances);
+    }
+}
@@ -1,6 +1,82 @@
 use anyhow::*;
 use image::GenericImageView;
 
+use crate::dds;
+
+//how the values stored in an image should be read
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorSpace {
+    //colours, which are stored gamma encoded (diffuse/albedo textures, and almost every image) - the gpu converts them to linear when sampled
+    Srgb,
+    //data that isn't a colour (normal, metallic/roughness and occlusion maps) - read exactly as stored
+    Linear,
+}
+
+//how a texture is read between and beyond its pixels - the default (clamped to the edges, nearest pixel) is what textures have always been sampled with
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SamplerOptions {
+    //what to do for uvs outside of 0 to 1 - ClampToEdge stretches the edge pixels out, Repeat tiles the texture (e.g. for floors and walls), and MirroredRepeat tiles it flipping every other copy
+    pub wrap_mode: wgpu::AddressMode,
+    //Nearest gives a more pixelated game style look, Linear blends neighbouring pixels together
+    pub filter: wgpu::FilterMode,
+    //the sharpest (min) and blurriest (max) mip levels that will be sampled - only matters for textures with mipmaps
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+    //added to the mip level the gpu picks - below 0 is sharper (but shimmers more in the distance), above 0 is blurrier
+    //samplers can't hold a bias in wgpu, so it is applied by the shader instead (see model::Material::set_sampler_options)
+    pub lod_bias: f32,
+}
+
+impl Default for SamplerOptions {
+    fn default() -> Self {
+        Self {
+            wrap_mode: wgpu::AddressMode::ClampToEdge,
+            filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            lod_bias: 0.0,
+        }
+    }
+}
+
+impl SamplerOptions {
+    pub fn with_wrap_mode(mut self, wrap_mode: wgpu::AddressMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    //max is kept at or above min, as the gpu can't sample from an empty range of mip levels
+    pub fn with_lod_clamp(mut self, lod_min_clamp: f32, lod_max_clamp: f32) -> Self {
+        self.lod_min_clamp = lod_min_clamp.max(0.0);
+        self.lod_max_clamp = lod_max_clamp.max(self.lod_min_clamp);
+        self
+    }
+
+    pub fn with_lod_bias(mut self, lod_bias: f32) -> Self {
+        self.lod_bias = lod_bias;
+        self
+    }
+
+    pub fn create_sampler(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: self.wrap_mode,
+            address_mode_v: self.wrap_mode,
+            address_mode_w: self.wrap_mode,
+            mag_filter: self.filter,
+            min_filter: self.filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: self.lod_min_clamp,
+            lod_max_clamp: self.lod_max_clamp,
+            ..Default::default()
+        })
+    }
+}
+
 pub struct Texture {
     //the gpu representation of our texture
     pub texture: wgpu::Texture,
@@ -14,15 +90,52 @@ impl Texture {
     //for when we create the depth stage of the render_pipeline and for creating the depth texture itself
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
+    //how depth is tested and what it is cleared to - with reverse-z (see camera::Projection::set_reverse_z) the far plane is at 0 and nearer things have a greater depth
+    pub fn depth_compare(reverse_z: bool) -> wgpu::CompareFunction {
+        if reverse_z {
+            wgpu::CompareFunction::Greater
+        } else {
+            wgpu::CompareFunction::Less
+        }
+    }
+
+    pub fn depth_clear_value(reverse_z: bool) -> f32 {
+        if reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    //how far in front of the camera (along the way it faces) a value in the depth buffer is - undoes the perspective divide, like depth.wgsl does
+    pub fn linear_depth(depth: f32, znear: f32, zfar: f32, reverse_z: bool) -> f32 {
+        //flip reversed depth back around, so the maths works the same either way
+        let depth: f32 = if reverse_z { 1.0 - depth } else { depth };
+        znear * zfar / (zfar - depth * (zfar - znear))
+    }
+
+    //for high dynamic range textures and render targets - 16 bit floats are filterable on every gpu, unlike 32 bit ones
+    pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
     pub fn create_depth_texture(
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
         label: &str,
     ) -> Self {
         //needs to be the same size as the screen or it won't render correctly
+        Self::create_depth_texture_with_size(device, config.width, config.height, label)
+    }
+
+    //the same as create_depth_texture, but for when we aren't rendering to the surface (and so don't have a SurfaceConfiguration to size it from)
+    pub fn create_depth_texture_with_size(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        label: &str,
+    ) -> Self {
         let size: wgpu::Extent3d = wgpu::Extent3d {
-            width: config.width,
-            height: config.height,
+            width,
+            height,
             depth_or_array_layers: 1,
         };
 
@@ -36,8 +149,10 @@ impl Texture {
                 //mark as a depth texture
                 format: Self::DEPTH_FORMAT,
                 //RENDER_ATTACHMENT - we are rendering this texture so it needs this tag
+                //COPY_SRC - so single texels can be read back (see State::depth_at)
                 usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
             }),
         );
 
@@ -64,6 +179,47 @@ impl Texture {
         }
     }
 
+    //a texture we can render a scene into (RENDER_ATTACHMENT) and then sample from later (TEXTURE_BINDING) - used for off-screen passes
+    pub fn create_render_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        label: &str,
+    ) -> Self {
+        let texture: wgpu::Texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+
+        let view: wgpu::TextureView = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        //the output of a pass is usually stretched over the screen (or another surface), so linear filtering looks better than nearest here
+        let sampler: wgpu::Sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
     //loads an image (from a set of bytes) into a Texture
     pub fn from_bytes(
         //since this isn't part of our main lib.rs program, we need to add references to the device and queue
@@ -71,11 +227,317 @@ impl Texture {
         queue: &wgpu::Queue,
         bytes: &[u8],
         label: &str,
-        is_normal_map: bool,
+        color_space: ColorSpace,
+        sampler: SamplerOptions,
     ) -> Result<Self> {
         //load the bytes from an image into a image::DynamicImage
         let img: image::DynamicImage = image::load_from_memory(bytes)?;
-        Self::from_image(device, queue, &img, Some(label), is_normal_map)
+        Self::from_image(device, queue, &img, Some(label), color_space, sampler)
+    }
+
+    //a 1x1 texture of a single (linear) colour - a stand-in for texture slots a material doesn't have an image for
+    pub fn from_color(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color: [u8; 4],
+        label: &str,
+    ) -> Result<Self> {
+        let img: image::DynamicImage =
+            image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba(color)));
+        Self::from_image(
+            device,
+            queue,
+            &img,
+            Some(label),
+            ColorSpace::Linear,
+            SamplerOptions::default(),
+        )
+    }
+
+    //replace how the texture is sampled (e.g. to make it tile) - anything bound to the old sampler (like a material's bind group) has to be re-created to see the change
+    pub fn set_sampler(&mut self, device: &wgpu::Device, options: SamplerOptions) {
+        self.sampler = options.create_sampler(device);
+    }
+
+    //loads a high dynamic range image (.hdr/.exr, from a set of bytes) into a float Texture, so values brighter than 1.0 don't get clipped
+    pub fn from_hdr_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> Result<Self> {
+        let img: image::DynamicImage = image::load_from_memory(bytes)?;
+        let rgba: image::Rgba32FImage = img.to_rgba32f();
+        let dimensions: (u32, u32) = rgba.dimensions();
+        //HDR_FORMAT stores 16 bit floats, so every channel needs converting down from 32 bits
+        let pixels: Vec<u16> = rgba
+            .into_raw()
+            .into_iter()
+            .map(|channel| half::f16::from_f32(channel).to_bits())
+            .collect();
+
+        let size: wgpu::Extent3d = wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: 1,
+        };
+
+        let texture: wgpu::Texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::HDR_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&pixels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                //4 channels of 2 bytes each
+                bytes_per_row: std::num::NonZeroU32::new(8 * dimensions.0),
+                rows_per_image: std::num::NonZeroU32::new(dimensions.1),
+            },
+            size,
+        );
+
+        let view: wgpu::TextureView = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler: wgpu::Sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+        })
+    }
+
+    //loads six images (from sets of bytes) into a cubemap - in the order +x, -x, +y, -y, +z, -z, and all faces have to be the same size
+    pub fn from_cubemap(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: [&[u8]; 6],
+        label: &str,
+    ) -> Result<Self> {
+        let faces: Vec<image::RgbaImage> = faces
+            .iter()
+            .map(|bytes| Ok(image::load_from_memory(bytes)?.to_rgba8()))
+            .collect::<Result<_>>()?;
+
+        let dimensions: (u32, u32) = faces[0].dimensions();
+        ensure!(
+            faces.iter().all(|face| face.dimensions() == dimensions),
+            "all cubemap faces must be the same size"
+        );
+
+        //a cubemap is stored as a 2d texture with 6 layers, one for each face
+        let size: wgpu::Extent3d = wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: 6,
+        };
+
+        let texture: wgpu::Texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        for (layer, face) in faces.iter().enumerate() {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    //each face goes into its own layer
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                face,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(4 * dimensions.0),
+                    rows_per_image: std::num::NonZeroU32::new(dimensions.1),
+                },
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..size
+                },
+            );
+        }
+
+        //the view has to be a Cube (rather than the default D2Array) so the shader can sample it with a direction
+        let view: wgpu::TextureView = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(label),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        //the skybox is never seen up close, so linear filtering looks much better than nearest
+        let sampler: wgpu::Sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+        })
+    }
+
+    //loads a ktx2 container of BC1 or BC7 compressed data straight onto the gpu (without decoding it) - needs Features::TEXTURE_COMPRESSION_BC
+    pub fn from_ktx2(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> Result<Self> {
+        ensure!(
+            device
+                .features()
+                .contains(wgpu::Features::TEXTURE_COMPRESSION_BC),
+            "{label}: the device doesn't support BC compressed textures"
+        );
+
+        let ktx2: Ktx2 = parse_ktx2(bytes).with_context(|| label.to_string())?;
+        let size: wgpu::Extent3d = wgpu::Extent3d {
+            width: ktx2.width,
+            height: ktx2.height,
+            depth_or_array_layers: 1,
+        };
+
+        Ok(Self::from_compressed_levels(
+            device,
+            queue,
+            label,
+            ktx2.format,
+            ktx2.block_size,
+            size,
+            ktx2.mip_level_count,
+            ktx2.levels.into_iter(),
+        ))
+    }
+
+    //loads a .dds file of BC1, BC3 or BC7 compressed data (with all of its mip levels) straight onto the gpu - needs Features::TEXTURE_COMPRESSION_BC
+    //color_space is only used for older .dds files (without a dx10 header), which don't say whether they hold colours or data
+    pub fn from_dds(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+        color_space: ColorSpace,
+    ) -> Result<Self> {
+        ensure!(
+            device
+                .features()
+                .contains(wgpu::Features::TEXTURE_COMPRESSION_BC),
+            "{label}: the device doesn't support BC compressed textures"
+        );
+
+        let dds: dds::Dds = dds::parse(bytes, color_space).with_context(|| label.to_string())?;
+        let size: wgpu::Extent3d = wgpu::Extent3d {
+            width: dds.width,
+            height: dds.height,
+            depth_or_array_layers: 1,
+        };
+
+        Ok(Self::from_compressed_levels(
+            device,
+            queue,
+            label,
+            dds.format,
+            dds.block_size,
+            size,
+            dds.mip_level_count,
+            dds.levels.into_iter(),
+        ))
+    }
+
+    //uploads already block compressed mip levels (largest first) into a new texture
+    #[allow(clippy::too_many_arguments)]
+    fn from_compressed_levels<'a>(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: &str,
+        format: wgpu::TextureFormat,
+        block_size: u32,
+        size: wgpu::Extent3d,
+        mip_level_count: u32,
+        levels: impl Iterator<Item = &'a [u8]>,
+    ) -> Self {
+        let texture: wgpu::Texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        //each mip level is half the size of the last, but still rounded up to whole blocks
+        for (mip_level, data) in levels.enumerate().take(mip_level_count as usize) {
+            let mip_size: wgpu::Extent3d = size.mip_level_size(mip_level as u32, false);
+            let blocks_wide: u32 = mip_size.width.div_ceil(4);
+            let blocks_high: u32 = mip_size.height.div_ceil(4);
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: mip_level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    //rows are rows of blocks, not rows of pixels
+                    bytes_per_row: std::num::NonZeroU32::new(blocks_wide * block_size),
+                    rows_per_image: std::num::NonZeroU32::new(blocks_high),
+                },
+                wgpu::Extent3d {
+                    width: blocks_wide * 4,
+                    height: blocks_high * 4,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view: wgpu::TextureView = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        //the same sampler as from_image's default, so compressed and uncompressed textures look the same (see set_sampler to change it)
+        let sampler: wgpu::Sampler = SamplerOptions::default().create_sampler(device);
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
     }
 
     //takes an image (in format image::DynamicImage) and returns a Texture
@@ -85,11 +547,23 @@ impl Texture {
         img: &image::DynamicImage,
         //labels must be Option enums, as they can being be None or have data
         label: Option<&str>,
-        is_normal_map: bool,
+        color_space: ColorSpace,
+        sampler: SamplerOptions,
     ) -> Result<Self> {
+        let format: wgpu::TextureFormat = image_format(img.color(), color_space);
         //requires to_rgba8() instead of as_rgba8() as
-        //convert the png into a Vector of Rgba bytes
-        let rgba: image::ImageBuffer<image::Rgba<u8>, Vec<u8>> = img.to_rgba8();
+        //convert the png into a Vector of Rgba bytes (or 16 bit floats, for high precision data)
+        let (pixels, bytes_per_pixel): (Vec<u8>, u32) = if format == Self::HDR_FORMAT {
+            let pixels: Vec<u16> = img
+                .to_rgba32f()
+                .into_raw()
+                .into_iter()
+                .map(|channel| half::f16::from_f32(channel).to_bits())
+                .collect();
+            (bytemuck::cast_slice(&pixels).to_vec(), 8)
+        } else {
+            (img.to_rgba8().into_raw(), 4)
+        };
         //collect the dimentions of the image (for when we create the actual texture)
         let dimensions: (u32, u32) = img.dimensions();
 
@@ -110,13 +584,7 @@ impl Texture {
             sample_count: 1,
             //our texture is 2 dimentional
             dimension: wgpu::TextureDimension::D2,
-            format: if is_normal_map {
-                //normal maps are in a different format, as it has more colour density
-                wgpu::TextureFormat::Rgba8Unorm
-            } else {
-                //almost all textures and images are in sRGB colour format
-                wgpu::TextureFormat::Rgba8UnormSrgb
-            },
+            format,
             //TEXTURE_BINDING tells wgpu that we want to use this texture in our shaders
             //COPY_DST means that we can copy data to this texture
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
@@ -134,12 +602,12 @@ impl Texture {
                 aspect: wgpu::TextureAspect::All,
             },
             //the actual pixel data from our image that is to be written
-            &rgba,
+            &pixels,
             //the layout of the texture
             wgpu::ImageDataLayout {
                 offset: 0,
-                //must be a multiple of 256 - multiplying our width (which is u8) by 4 ensures this (64 * 4 = 256)
-                bytes_per_row: std::num::NonZeroU32::new(4 * dimensions.0),
+                //one row of pixels, unpadded - only buffer to texture copies need rows padded to 256 bytes, not queue.write_texture
+                bytes_per_row: std::num::NonZeroU32::new(bytes_per_pixel * dimensions.0),
                 rows_per_image: std::num::NonZeroU32::new(dimensions.1),
             },
             size,
@@ -149,22 +617,7 @@ impl Texture {
         //describes the texture and associated metadata
         let view: wgpu::TextureView = texture.create_view(&wgpu::TextureViewDescriptor::default());
         //controls how a texture is sampled - returning a colour based on a provided pixel coordinate (and some config)
-        let sampler: wgpu::Sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            //what to do if the sampler looks for a colour outside of our texture - ClampToEdge returns the colour of the pixel on the edge nearest to where the sampler is looking for (can be Repeat or MirroredRepeat aswell)
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            //when looking at our image from up close or far away we can often have multiple pixels overlapping a fragment or vice-versa, so we either:
-            //Linear: (when the texture needs to be enlargened) attempt to blend fragments so they seem to flow together
-            //Nearest gives a more pixelated game style look, which is my intention for this project
-            mag_filter: wgpu::FilterMode::Nearest,
-            //(when the texture needs to be minified) use the colour of the nearest pixel
-            min_filter: wgpu::FilterMode::Nearest,
-            //[TODO] - how to deal with mipmaps
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            //let wgpu set the rest
-            ..Default::default()
-        });
+        let sampler: wgpu::Sampler = sampler.create_sampler(device);
 
         //if anything fails it will return an Err, so if we get to the end we return it with an Ok()  enum
         Ok(Self {
@@ -174,3 +627,198 @@ impl Texture {
         })
     }
 }
+
+//the format an image is uploaded as - data stored with more than 8 bits per channel (e.g. 16 bit png normal maps) keeps its precision in a float texture, as squashing it into 8 bits causes banding on smooth, curved surfaces
+fn image_format(color: image::ColorType, color_space: ColorSpace) -> wgpu::TextureFormat {
+    let high_precision: bool = color.bytes_per_pixel() > color.channel_count();
+    match color_space {
+        //almost all textures and images are in sRGB colour format
+        ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        ColorSpace::Linear if high_precision => Texture::HDR_FORMAT,
+        //data (like normals) has to be read back exactly as it was stored
+        ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+    }
+}
+
+//the level index comes straight after the ktx2 header, with a byte offset, byte length and uncompressed byte length (each a u64) for every mip level
+const KTX2_HEADER_SIZE: usize = 80;
+const KTX2_LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+//what Texture::from_ktx2 needs to know to upload a .ktx2 file
+#[derive(Debug)]
+struct Ktx2<'a> {
+    width: u32,
+    height: u32,
+    mip_level_count: u32,
+    format: wgpu::TextureFormat,
+    //how many bytes each 4x4 block of pixels takes up
+    block_size: u32,
+    //the data of each mip level, largest first
+    levels: Vec<&'a [u8]>,
+}
+
+//checks the header against the mip levels the same way dds::parse does, so a broken file is an error instead of a panic inside wgpu
+fn parse_ktx2(bytes: &[u8]) -> Result<Ktx2<'_>> {
+    let reader: ktx2::Reader<&[u8]> =
+        ktx2::Reader::new(bytes).map_err(|e| anyhow!("invalid ktx2 file ({e:?})"))?;
+    let header: ktx2::Header = reader.header();
+    ensure!(
+        header.supercompression_scheme.is_none(),
+        "supercompressed ktx2 files aren't supported"
+    );
+
+    //BC formats store 4x4 blocks of pixels in a fixed number of bytes, instead of storing each pixel
+    let (format, block_size): (wgpu::TextureFormat, u32) = match header.format {
+        Some(ktx2::Format::BC1_RGB_UNORM_BLOCK) | Some(ktx2::Format::BC1_RGBA_UNORM_BLOCK) => {
+            (wgpu::TextureFormat::Bc1RgbaUnorm, 8)
+        }
+        Some(ktx2::Format::BC1_RGB_SRGB_BLOCK) | Some(ktx2::Format::BC1_RGBA_SRGB_BLOCK) => {
+            (wgpu::TextureFormat::Bc1RgbaUnormSrgb, 8)
+        }
+        Some(ktx2::Format::BC7_UNORM_BLOCK) => (wgpu::TextureFormat::Bc7RgbaUnorm, 16),
+        Some(ktx2::Format::BC7_SRGB_BLOCK) => (wgpu::TextureFormat::Bc7RgbaUnormSrgb, 16),
+        format => bail!("unsupported ktx2 format {format:?}"),
+    };
+
+    let (width, height): (u32, u32) = (header.pixel_width, header.pixel_height);
+    ensure!(
+        width > 0 && height > 0,
+        "ktx2 file is {width}x{height}, which has no pixels"
+    );
+    //compressed textures have to be made up of whole blocks
+    ensure!(
+        width.is_multiple_of(4) && height.is_multiple_of(4),
+        "compressed textures must have a width and height that are multiples of 4"
+    );
+    //0 when the file has no mip levels beyond the first - and there can't be more than it takes to halve the largest side down to 1
+    let mip_level_count: u32 = header.level_count.max(1);
+    let max_mip_level_count: u32 = u32::BITS - width.max(height).leading_zeros();
+    ensure!(
+        mip_level_count <= max_mip_level_count,
+        "ktx2 file has {mip_level_count} mip levels, but a {width}x{height} texture can only have {max_mip_level_count}"
+    );
+
+    //ktx2::Reader only checks that the level furthest into the file fits inside it (and Reader::levels panics on any other that doesn't), so each level is found and checked here instead
+    let mut levels: Vec<&[u8]> = Vec::with_capacity(mip_level_count as usize);
+    for mip_level in 0..mip_level_count {
+        let entry: usize = KTX2_HEADER_SIZE + mip_level as usize * KTX2_LEVEL_INDEX_ENTRY_SIZE;
+        //Reader::new has already checked the whole level index is there
+        let offset: u64 = u64::from_le_bytes(bytes[entry..entry + 8].try_into()?);
+        let length: u64 = u64::from_le_bytes(bytes[entry + 8..entry + 16].try_into()?);
+        let size: usize = dds::level_size(width, height, mip_level, block_size)
+            .with_context(|| format!("ktx2 mip level {mip_level} is too big"))?;
+        ensure!(
+            length == size as u64,
+            "ktx2 mip level {mip_level} is {length} bytes, but should be {size} for a {width}x{height} texture"
+        );
+        let level: &[u8] = usize::try_from(offset)
+            .ok()
+            .and_then(|start| bytes.get(start..start.checked_add(size)?))
+            .with_context(|| {
+                format!("ktx2 file is too short for mip level {mip_level} of {mip_level_count}")
+            })?;
+        levels.push(level);
+    }
+
+    Ok(Ktx2 {
+        width,
+        height,
+        mip_level_count,
+        format,
+        block_size,
+        levels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a .ktx2 file of the given vulkan format with a level index entry for each of levels (offset and length) - the level data still has to be added after
+    fn ktx2_file(vk_format: u32, width: u32, height: u32, levels: &[(u64, u64)]) -> Vec<u8> {
+        let mut file: Vec<u8> = vec![
+            0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+        ];
+        //format, type size, width, height, depth, layers, faces, levels and supercompression - then the (empty) data format descriptor, key/value data and supercompression data ranges
+        for field in [vk_format, 1, width, height, 0, 0, 1, levels.len() as u32, 0] {
+            file.extend(field.to_le_bytes());
+        }
+        file.extend([0; KTX2_HEADER_SIZE - 48]);
+        for (offset, length) in levels {
+            file.extend(offset.to_le_bytes());
+            file.extend(length.to_le_bytes());
+            file.extend(length.to_le_bytes());
+        }
+        file
+    }
+
+    //vulkan's VK_FORMAT_BC1_RGBA_UNORM_BLOCK
+    const BC1_RGBA_UNORM: u32 = 133;
+
+    #[test]
+    fn reads_ktx2_levels() {
+        //8x8 BC1 with 2 mips is 4 blocks then 1 block, at 8 bytes each, straight after the 2 level index entries
+        let data_start: u64 = (KTX2_HEADER_SIZE + 2 * KTX2_LEVEL_INDEX_ENTRY_SIZE) as u64;
+        let mut file: Vec<u8> = ktx2_file(
+            BC1_RGBA_UNORM,
+            8,
+            8,
+            &[(data_start, 32), (data_start + 32, 8)],
+        );
+        file.extend([0; 40]);
+        let ktx2: Ktx2 = parse_ktx2(&file).unwrap();
+        assert_eq!(ktx2.format, wgpu::TextureFormat::Bc1RgbaUnorm);
+        assert_eq!((ktx2.width, ktx2.height, ktx2.mip_level_count), (8, 8, 2));
+        assert_eq!(
+            ktx2.levels
+                .iter()
+                .map(|level| level.len())
+                .collect::<Vec<_>>(),
+            [32, 8]
+        );
+    }
+
+    #[test]
+    fn rejects_hostile_ktx2_headers() {
+        let data_start: u64 = (KTX2_HEADER_SIZE + KTX2_LEVEL_INDEX_ENTRY_SIZE) as u64;
+        //no pixels
+        let mut file: Vec<u8> = ktx2_file(BC1_RGBA_UNORM, 4, 0, &[(data_start, 8)]);
+        file.extend([0; 8]);
+        assert!(parse_ktx2(&file).is_err());
+        //more mip levels than halving 8 down to 1 gives
+        let data_start_40: u64 = (KTX2_HEADER_SIZE + 40 * KTX2_LEVEL_INDEX_ENTRY_SIZE) as u64;
+        let mut file: Vec<u8> = ktx2_file(BC1_RGBA_UNORM, 8, 8, &[(data_start_40, 8); 40]);
+        file.extend([0; 32]);
+        assert!(parse_ktx2(&file).is_err());
+        //a first mip level shorter than an 8x8 texture needs
+        let mut file: Vec<u8> = ktx2_file(BC1_RGBA_UNORM, 8, 8, &[(data_start, 8)]);
+        file.extend([0; 8]);
+        assert!(parse_ktx2(&file).is_err());
+        //a first level that runs past the end of the file, hidden behind a second level further in that fits
+        let data_start: u64 = (KTX2_HEADER_SIZE + 2 * KTX2_LEVEL_INDEX_ENTRY_SIZE) as u64;
+        let mut file: Vec<u8> = ktx2_file(
+            BC1_RGBA_UNORM,
+            8,
+            8,
+            &[(data_start, 32), (data_start + 8, 8)],
+        );
+        file.extend([0; 16]);
+        assert!(parse_ktx2(&file).is_err());
+    }
+
+    #[test]
+    fn only_high_precision_data_gets_a_float_format() {
+        assert_eq!(
+            image_format(image::ColorType::Rgb16, ColorSpace::Linear),
+            wgpu::TextureFormat::Rgba16Float
+        );
+        assert_eq!(
+            image_format(image::ColorType::Rgba8, ColorSpace::Linear),
+            wgpu::TextureFormat::Rgba8Unorm
+        );
+        assert_eq!(
+            image_format(image::ColorType::Rgba16, ColorSpace::Srgb),
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//for drawing the scene more than once side by side (split-screen, or comparing two views), each from its own camera
+
+use wgpu::util::DeviceExt;
+
+use crate::{camera, CameraUniform};
+
+//a part of the screen, in fractions of its size - (0, 0) is the top left and (1, 1) the bottom right
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    //the whole screen
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    //the viewport in pixels (x, y, width, height) of a target of the given size - clamped so it always fits inside the target (which set_viewport requires)
+    pub fn to_pixels(&self, target_width: u32, target_height: u32) -> (f32, f32, f32, f32) {
+        let (target_width, target_height): (f32, f32) = (target_width as f32, target_height as f32);
+        let x: f32 = (self.x * target_width).clamp(0.0, target_width - 1.0);
+        let y: f32 = (self.y * target_height).clamp(0.0, target_height - 1.0);
+        let width: f32 = (self.width * target_width).clamp(1.0, target_width - x);
+        let height: f32 = (self.height * target_height).clamp(1.0, target_height - y);
+        (x, y, width, height)
+    }
+}
+
+//one viewport and the camera (by index - 0 is the main camera) drawn into it, with a camera uniform of its own, as its aspect ratio differs from the screen's
+pub struct ViewportView {
+    pub viewport: Viewport,
+    pub camera: usize,
+    buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl ViewportView {
+    pub fn new(
+        device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        viewport: Viewport,
+        camera: usize,
+    ) -> Self {
+        let buffer: wgpu::Buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Viewport Camera Buffer"),
+            contents: bytemuck::cast_slice(&[CameraUniform::new()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group: wgpu::BindGroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("viewport_camera_bind_group"),
+            layout: camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            viewport,
+            camera,
+            buffer,
+            bind_group,
+        }
+    }
+
+    //updates the camera uniform for a target of the given size - the projection's fov and planes are kept, but its aspect ratio is the viewport's
+    pub fn write_camera(
+        &self,
+        queue: &wgpu::Queue,
+        camera: &camera::Camera,
+        projection: &camera::Projection,
+        target_width: u32,
+        target_height: u32,
+    ) {
+        let (_, _, width, height): (f32, f32, f32, f32) =
+            self.viewport.to_pixels(target_width, target_height);
+        let mut projection: camera::Projection = projection.clone();
+        projection.resize(width as u32, height as u32);
+
+        let mut uniform: CameraUniform = CameraUniform::new();
+        uniform.update_view_proj(camera, &projection);
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewports_stay_inside_the_target() {
+        //the left half of an 800x600 screen
+        assert_eq!(
+            Viewport::new(0.0, 0.0, 0.5, 1.0).to_pixels(800, 600),
+            (0.0, 0.0, 400.0, 600.0)
+        );
+        //hanging off the bottom right corner
+        assert_eq!(
+            Viewport::new(0.75, 0.5, 0.5, 1.0).to_pixels(800, 600),
+            (600.0, 300.0, 200.0, 300.0)
+        );
+    }
+}